@@ -2,15 +2,21 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use quote::{quote, ToTokens};
-use syn::{parse_macro_input, ItemFn};
+use syn::visit_mut::VisitMut;
+use syn::{parse_macro_input, FnArg, ItemFn, Lifetime, Type};
 
 #[proc_macro_attribute]
 pub fn ai_function_to_string(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Parse the input function
     let input_fn: ItemFn = parse_macro_input!(item as ItemFn);
 
-    // String representation of the function
-    let fn_str: String = format!("{}", input_fn.to_token_stream());
+    // Make sure the prompt has everything the LLM needs
+    if let Err(e) = lint_prompt(&input_fn) {
+        return e.to_compile_error().into();
+    }
+
+    // Prompt text of the function
+    let fn_str: String = render_prompt(&input_fn, None);
 
     // Define a new function with the same signature as input function
     let fn_ident: proc_macro2::Ident = input_fn.sig.ident;
@@ -26,3 +32,817 @@ pub fn ai_function_to_string(_attr: TokenStream, item: TokenStream) -> TokenStre
 
     output.into()
 }
+
+// Turns the annotated function into a unit struct of the same name implementing `AiFunction`,
+// so the name, prompt, input type and output type can never be paired up incorrectly.
+// `version` identifies the built-in prompt and defaults to "1", bump it when editing the docs.
+// `examples` names a function returning the typed few-shot examples of the ai function.
+// `prints_code` marks functions whose output is source code of the backend project.
+//
+//   #[ai_function(output = ProjectScope, version = "2", examples = project_scope_examples)]
+//   pub fn print_project_scope(_project_description: &str) { ... }
+#[proc_macro_attribute]
+pub fn ai_function(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut output_ty: Option<Type> = None;
+    let mut version: Option<syn::LitStr> = None;
+    let mut examples: Option<syn::Path> = None;
+    let mut prints_code: bool = false;
+    let attr_parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("output") {
+            output_ty = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("version") {
+            version = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("examples") {
+            examples = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("prints_code") {
+            prints_code = true;
+            Ok(())
+        } else {
+            Err(meta.error(
+                "unsupported ai_function property, expected `output = Type`, `version = \"..\"`, `examples = path` or `prints_code`",
+            ))
+        }
+    });
+    parse_macro_input!(attr with attr_parser);
+
+    let input_fn: ItemFn = parse_macro_input!(item as ItemFn);
+    let version: String = version.map(|v| v.value()).unwrap_or_else(|| "1".to_string());
+
+    match expand_ai_function(input_fn, output_ty, &version, examples, prints_code) {
+        Ok(output) => output.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_ai_function(
+    input_fn: ItemFn,
+    output_ty: Option<Type>,
+    version: &str,
+    few_shot_examples: Option<syn::Path>,
+    prints_code: bool,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let examples: Vec<String> = lint_prompt(&input_fn)?;
+
+    if !input_fn.sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input_fn.sig.generics,
+            "ai_function does not support generic parameters",
+        ));
+    }
+
+    // Every input is handed to the LLM as its own labeled section of the prompt
+    let input_args: Vec<&syn::PatType> = input_fn
+        .sig
+        .inputs
+        .iter()
+        .map(|arg| match arg {
+            FnArg::Typed(pat_ty) => Ok(pat_ty),
+            FnArg::Receiver(recv) => Err(syn::Error::new_spanned(
+                recv,
+                "ai_function cannot take `self`",
+            )),
+        })
+        .collect::<syn::Result<_>>()?;
+    if input_args.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input_fn.sig,
+            "ai_function expects at least one input",
+        ));
+    }
+
+    let fn_vis: &syn::Visibility = &input_fn.vis;
+    let fn_ident: &syn::Ident = &input_fn.sig.ident;
+    let fn_name: String = fn_ident.to_string();
+
+    // `_project_description` is labeled and available to prompt templates as `project_description`
+    let input_names: Vec<String> = input_args
+        .iter()
+        .map(|arg| match &*arg.pat {
+            syn::Pat::Ident(pat_ident) => Ok(pat_ident
+                .ident
+                .to_string()
+                .trim_start_matches('_')
+                .to_string()),
+            pat => Err(syn::Error::new_spanned(pat, "ai_function inputs must be named")),
+        })
+        .collect::<syn::Result<_>>()?;
+    let input_tys: Vec<Type> = input_args
+        .iter()
+        .map(|arg| {
+            let mut input_ty: Type = (*arg.ty).clone();
+            InputLifetime.visit_type_mut(&mut input_ty);
+            input_ty
+        })
+        .collect();
+    let input_ty_strs: Vec<String> = input_args
+        .iter()
+        .map(|arg| tidy_tokens(&arg.ty.to_token_stream().to_string()))
+        .collect();
+    let input_vars: Vec<syn::Ident> = (0..input_args.len())
+        .map(|i| quote::format_ident!("input_{}", i))
+        .collect();
+
+    // A single input is passed as is, several inputs as a tuple in declaration order
+    let (input_ty, input_ty_str, input_pat) = if input_args.len() == 1 {
+        let input_ty: &Type = &input_tys[0];
+        (quote! { #input_ty }, input_ty_strs[0].clone(), quote! { input_0 })
+    } else {
+        (
+            quote! { (#(#input_tys),*) },
+            format!("({})", input_ty_strs.join(", ")),
+            quote! { (#(#input_vars),*) },
+        )
+    };
+
+    let (output_ty_tokens, output_ty_str, parse_body, schema_body) = match &output_ty {
+        Some(ty) => (
+            quote! { #ty },
+            tidy_tokens(&ty.to_token_stream().to_string()),
+            quote! { crate::ai_functions::ai_function::parse_json_output::<#ty>(res) },
+            quote! { Some(<#ty as crate::ai_functions::json_schema::JsonSchema>::json_schema()) },
+        ),
+        None => (
+            quote! { ::std::string::String },
+            "String".to_string(),
+            quote! { Ok(res.to_string()) },
+            quote! { None },
+        ),
+    };
+
+    // The JSON examples are only known to be valid JSON here, whether they fit the declared
+    // output type can only be checked once the type exists, so that check becomes a test
+    let examples_test: proc_macro2::TokenStream = match &output_ty {
+        Some(ty) if !examples.is_empty() => {
+            let test_mod = syn::Ident::new(
+                &format!("{}_doc_examples", fn_ident),
+                fn_ident.span(),
+            );
+            quote! {
+                #[cfg(test)]
+                mod #test_mod {
+                    use super::*;
+
+                    #[test]
+                    fn tests_doc_examples_deserialize_into_output() {
+                        for (i, example) in [#(#examples),*].iter().enumerate() {
+                            if let Err(e) = ::serde_json::from_str::<#ty>(example) {
+                                panic!(
+                                    "Example {} of `{}` does not deserialize into `{}`: {}",
+                                    i + 1, #fn_name, #output_ty_str, e
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        _ => quote! {},
+    };
+
+    // Typed examples must also fit the schema the LLM is asked to follow
+    let few_shot_test: proc_macro2::TokenStream = match (&output_ty, &few_shot_examples) {
+        (Some(ty), Some(examples_fn)) => {
+            let test_mod = syn::Ident::new(
+                &format!("{}_few_shot_examples", fn_ident),
+                fn_ident.span(),
+            );
+            quote! {
+                #[cfg(test)]
+                mod #test_mod {
+                    use super::*;
+
+                    #[test]
+                    fn tests_few_shot_examples_match_output_schema() {
+                        let schema: ::serde_json::Value =
+                            <#ty as crate::ai_functions::json_schema::JsonSchema>::json_schema();
+                        for (i, example) in #examples_fn().iter().enumerate() {
+                            let value: ::serde_json::Value = ::serde_json::to_value(&example.output).unwrap();
+                            if let Err(errors) = crate::ai_functions::json_schema::validate(&schema, &value) {
+                                panic!(
+                                    "Few-shot example {} of `{}` does not match the schema of `{}`: {:?}",
+                                    i + 1, #fn_name, #output_ty_str, errors
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        _ => quote! {},
+    };
+    let examples_body: proc_macro2::TokenStream = match &few_shot_examples {
+        Some(examples_fn) => quote! { #examples_fn() },
+        None => quote! { ::std::vec::Vec::new() },
+    };
+
+    let fn_str: String = render_prompt(&input_fn, Some(&output_ty_str));
+
+    Ok(quote! {
+        #[allow(non_camel_case_types)]
+        #[derive(Debug, Clone, Copy)]
+        #fn_vis struct #fn_ident;
+
+        impl crate::ai_functions::ai_function::AiFunction for #fn_ident {
+            type Input<'a> = #input_ty;
+            type Output = #output_ty_tokens;
+
+            fn name(&self) -> &'static str {
+                #fn_name
+            }
+
+            fn prompt(&self) -> &'static str {
+                #fn_str
+            }
+
+            fn version(&self) -> &'static str {
+                #version
+            }
+
+            fn prints_code(&self) -> bool {
+                #prints_code
+            }
+
+            fn input_names(&self) -> &'static [&'static str] {
+                &[#(#input_names),*]
+            }
+
+            fn input_type(&self) -> &'static str {
+                #input_ty_str
+            }
+
+            fn output_type(&self) -> &'static str {
+                #output_ty_str
+            }
+
+            fn render_input(
+                &self,
+                input: Self::Input<'_>,
+            ) -> ::std::vec::Vec<(&'static str, ::std::string::String)> {
+                let #input_pat = input;
+                vec![#((#input_names, crate::ai_functions::ai_function::prompt_value(&#input_vars))),*]
+            }
+
+            fn output_schema(&self) -> ::std::option::Option<::serde_json::Value> {
+                #schema_body
+            }
+
+            fn parse_output(
+                &self,
+                res: &str,
+            ) -> ::std::result::Result<Self::Output, crate::ai_functions::ai_function::AiFunctionError> {
+                #parse_body
+            }
+
+            fn examples(
+                &self,
+            ) -> ::std::vec::Vec<crate::ai_functions::ai_function::FewShotExample<Self::Output>> {
+                #examples_body
+            }
+
+            fn rendered_examples(
+                &self,
+            ) -> ::std::vec::Vec<crate::ai_functions::ai_function::FewShotExample<::std::string::String>> {
+                self.examples()
+                    .into_iter()
+                    .map(|example| crate::ai_functions::ai_function::FewShotExample {
+                        output: crate::ai_functions::ai_function::prompt_value(&example.output),
+                        input: example.input,
+                    })
+                    .collect()
+            }
+        }
+
+        #examples_test
+        #few_shot_test
+    })
+}
+
+// Every ai function prompt needs these sections, matched case-insensitively at the start of a line
+const REQUIRED_SECTIONS: [&str; 3] = ["Input:", "Function:", "Output:"];
+
+// Checks the required sections are present and the JSON examples (the block following a
+// `prints:` line) are valid JSON. Returns the examples so they can be checked against the
+// output type.
+fn lint_prompt(input_fn: &ItemFn) -> syn::Result<Vec<String>> {
+    let lines: Vec<String> = doc_lines(input_fn);
+    let fn_ident: &syn::Ident = &input_fn.sig.ident;
+
+    for section in REQUIRED_SECTIONS {
+        let has_section: bool = lines.iter().any(|line| {
+            line.trim_start()
+                .to_lowercase()
+                .starts_with(&section.to_lowercase())
+        });
+        if !has_section {
+            return Err(syn::Error::new_spanned(
+                fn_ident,
+                format!(
+                    "ai_function `{}` is missing the `{}` section in its docs",
+                    fn_ident, section
+                ),
+            ));
+        }
+    }
+
+    let examples: Vec<String> = json_examples(&lines);
+    for (i, example) in examples.iter().enumerate() {
+        if let Err(e) = serde_json::from_str::<serde_json::Value>(example) {
+            return Err(syn::Error::new_spanned(
+                fn_ident,
+                format!(
+                    "example {} of ai_function `{}` is not valid JSON: {}\n{}",
+                    i + 1,
+                    fn_ident,
+                    e,
+                    example
+                ),
+            ));
+        }
+    }
+
+    Ok(examples)
+}
+
+fn json_examples(lines: &[String]) -> Vec<String> {
+    let mut examples: Vec<String> = Vec::new();
+    let mut lines = lines.iter();
+
+    while let Some(line) = lines.next() {
+        if !line.trim_end().to_lowercase().ends_with("prints:") {
+            continue;
+        }
+
+        let mut example: String = String::new();
+        let mut depth: i32 = 0;
+        let mut in_string: bool = false;
+        let mut escaped: bool = false;
+        for line in lines.by_ref() {
+            let trimmed: &str = line.trim();
+            if example.is_empty() && !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+                break;
+            }
+
+            for c in trimmed.chars() {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' if in_string => escaped = true,
+                    '"' => in_string = !in_string,
+                    '{' | '[' if !in_string => depth += 1,
+                    '}' | ']' if !in_string => depth -= 1,
+                    _ => {}
+                }
+            }
+            example.push_str(trimmed);
+            example.push('\n');
+
+            if depth <= 0 {
+                break;
+            }
+        }
+
+        if !example.is_empty() {
+            examples.push(example.trim_end().to_string());
+        }
+    }
+
+    examples
+}
+
+// Implements `JsonSchema` for structs with named fields and enums with unit variants.
+// Field `///` docs become the schema descriptions and `Option` fields are not required.
+#[proc_macro_derive(JsonSchema)]
+pub fn derive_json_schema(item: TokenStream) -> TokenStream {
+    let input: syn::DeriveInput = parse_macro_input!(item as syn::DeriveInput);
+
+    match expand_json_schema(input) {
+        Ok(output) => output.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_json_schema(input: syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let ident: &syn::Ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let schema: proc_macro2::TokenStream = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => {
+            let mut properties: Vec<proc_macro2::TokenStream> = Vec::new();
+            let mut required: Vec<String> = Vec::new();
+
+            // Fields serde never writes out are not part of the schema either
+            for field in fields.named.iter().filter(|field| !is_serde_skipped(&field.attrs)) {
+                let field_name: String = field
+                    .ident
+                    .as_ref()
+                    .expect("named field")
+                    .to_string();
+                let field_ty: &Type = &field.ty;
+                let description: String = attr_docs(&field.attrs).join(" ");
+
+                if !is_option(field_ty) {
+                    required.push(field_name.clone());
+                }
+                let describe: proc_macro2::TokenStream = if description.is_empty() {
+                    quote! {}
+                } else {
+                    quote! {
+                        if let Some(field_schema) = field_schema.as_object_mut() {
+                            field_schema.insert("description".to_string(), ::serde_json::json!(#description));
+                        }
+                    }
+                };
+                properties.push(quote! {
+                    #[allow(unused_mut)]
+                    let mut field_schema: ::serde_json::Value =
+                        <#field_ty as crate::ai_functions::json_schema::JsonSchema>::json_schema();
+                    #describe
+                    properties.insert(#field_name.to_string(), field_schema);
+                });
+            }
+
+            quote! {
+                let mut properties: ::serde_json::Map<::std::string::String, ::serde_json::Value> =
+                    ::serde_json::Map::new();
+                #(#properties)*
+                ::serde_json::json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": [#(#required),*],
+                    "additionalProperties": false
+                })
+            }
+        }
+        syn::Data::Enum(data_enum) => {
+            let variants: Vec<String> = data_enum
+                .variants
+                .iter()
+                .map(|variant| match variant.fields {
+                    syn::Fields::Unit => Ok(variant.ident.to_string()),
+                    _ => Err(syn::Error::new_spanned(
+                        variant,
+                        "JsonSchema can only be derived for enums with unit variants",
+                    )),
+                })
+                .collect::<syn::Result<_>>()?;
+
+            quote! {
+                ::serde_json::json!({
+                    "type": "string",
+                    "enum": [#(#variants),*]
+                })
+            }
+        }
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "JsonSchema can only be derived for structs with named fields and unit enums",
+            ))
+        }
+    };
+
+    Ok(quote! {
+        impl #impl_generics crate::ai_functions::json_schema::JsonSchema for #ident #ty_generics #where_clause {
+            fn json_schema() -> ::serde_json::Value {
+                #schema
+            }
+        }
+    })
+}
+
+fn is_serde_skipped(attrs: &[syn::Attribute]) -> bool {
+    let mut skipped: bool = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") {
+                skipped = true;
+            }
+            // Consume values like `rename = "..."` so the remaining items still parse
+            if meta.input.peek(syn::Token![=]) {
+                meta.value()?.parse::<syn::Expr>()?;
+            }
+            Ok(())
+        });
+    }
+    skipped
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "Option")
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+// Renders the prompt the LLM receives for an ai function:
+//
+//   NAME: print_project_scope
+//   SIGNATURE: fn print_project_scope(_project_description: &str) -> ProjectScope
+//   DOCS:
+//     Input: ...
+//   OUTPUT: println!(OUTPUT)
+fn render_prompt(input_fn: &ItemFn, output_ty: Option<&str>) -> String {
+    let mut prompt: String = format!(
+        "NAME: {}\nSIGNATURE: {}",
+        input_fn.sig.ident,
+        render_signature(&input_fn.sig)
+    );
+    if let Some(output_ty) = output_ty {
+        prompt.push_str(&format!(" -> {}", output_ty));
+    }
+
+    prompt.push_str("\nDOCS:\n");
+    for line in doc_lines(input_fn) {
+        if line.is_empty() {
+            prompt.push('\n');
+        } else {
+            prompt.push_str(&format!("  {}\n", line));
+        }
+    }
+
+    prompt.push_str("OUTPUT: println!(OUTPUT)");
+    prompt
+}
+
+fn render_signature(sig: &syn::Signature) -> String {
+    let inputs: Vec<String> = sig
+        .inputs
+        .iter()
+        .map(|arg| tidy_tokens(&arg.to_token_stream().to_string()))
+        .collect();
+
+    format!("fn {}({})", sig.ident, inputs.join(", "))
+}
+
+// Collects the `///` lines of an ai function, both on the function itself and on the
+// statements of its body, with the single space rustdoc adds after `///` removed
+fn doc_lines(input_fn: &ItemFn) -> Vec<String> {
+    let stmt_attrs = input_fn.block.stmts.iter().flat_map(|stmt| match stmt {
+        syn::Stmt::Local(local) => local.attrs.as_slice(),
+        syn::Stmt::Macro(stmt_macro) => stmt_macro.attrs.as_slice(),
+        syn::Stmt::Expr(syn::Expr::Macro(expr_macro), _) => expr_macro.attrs.as_slice(),
+        _ => &[],
+    });
+
+    let attrs: Vec<syn::Attribute> = input_fn.attrs.iter().chain(stmt_attrs).cloned().collect();
+    attr_docs(&attrs)
+}
+
+fn attr_docs(attrs: &[syn::Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }),
+                ..
+            }) => Some(doc.value()),
+            _ => None,
+        })
+        .flat_map(|doc| {
+            doc.lines()
+                .map(|line| line.strip_prefix(' ').unwrap_or(line).trim_end().to_string())
+                .collect::<Vec<String>>()
+        })
+        .collect()
+}
+
+// Gives every elided or anonymous reference lifetime in an input type the `'a` lifetime
+// of `AiFunction::Input<'a>`
+struct InputLifetime;
+
+impl VisitMut for InputLifetime {
+    fn visit_type_reference_mut(&mut self, ty_ref: &mut syn::TypeReference) {
+        match &ty_ref.lifetime {
+            Some(lifetime) if lifetime.ident != "_" => {}
+            _ => ty_ref.lifetime = Some(Lifetime::new("'a", proc_macro2::Span::call_site())),
+        }
+        syn::visit_mut::visit_type_reference_mut(self, ty_ref);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = Lifetime::new("'a", proc_macro2::Span::call_site());
+        }
+    }
+}
+
+// Removes the spacing `to_string` puts between tokens, e.g. `& [RouteObject]` -> `&[RouteObject]`
+fn tidy_tokens(tokens: &str) -> String {
+    tokens
+        .replace(" :: ", "::")
+        .replace(" < ", "<")
+        .replace("< ", "<")
+        .replace(" <", "<")
+        .replace(" >", ">")
+        .replace("& ", "&")
+        .replace("[ ", "[")
+        .replace(" ]", "]")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace(" ,", ",")
+        .replace(" :", ":")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_tidy_tokens() {
+        let ty: Type = syn::parse_quote!(&[Vec<std::string::String>]);
+        assert_eq!(
+            tidy_tokens(&ty.to_token_stream().to_string()),
+            "&[Vec<std::string::String>]"
+        );
+    }
+
+    #[test]
+    fn tests_input_lifetime() {
+        let mut ty: Type = syn::parse_quote!(&[&'_ str]);
+        InputLifetime.visit_type_mut(&mut ty);
+        assert_eq!(
+            tidy_tokens(&ty.to_token_stream().to_string()),
+            "&'a [&'a str]"
+        );
+    }
+
+    #[test]
+    fn tests_render_prompt() {
+        let input_fn: ItemFn = syn::parse_quote! {
+            pub fn print_goal(_usr_req: &str) {
+                /// Input: Takes in a user request
+                /// Output: Prints goal
+                ///   "build a website that ..."
+                println!(OUTPUT)
+            }
+        };
+
+        let expected: String = [
+            "NAME: print_goal",
+            "SIGNATURE: fn print_goal(_usr_req: &str) -> String",
+            "DOCS:",
+            "  Input: Takes in a user request",
+            "  Output: Prints goal",
+            "    \"build a website that ...\"",
+            "OUTPUT: println!(OUTPUT)",
+        ]
+        .join("\n");
+        assert_eq!(render_prompt(&input_fn, Some("String")), expected);
+    }
+
+    #[test]
+    fn tests_lint_requires_sections() {
+        let input_fn: ItemFn = syn::parse_quote! {
+            pub fn print_code(_code: &str) {
+                /// INPUT: Takes in some code
+                /// FUNCTION: Improves the code
+                println!(OUTPUT)
+            }
+        };
+
+        let err: syn::Error = lint_prompt(&input_fn).unwrap_err();
+        assert!(err.to_string().contains("`Output:` section"));
+    }
+
+    #[test]
+    fn tests_lint_rejects_invalid_json_examples() {
+        let input_fn: ItemFn = syn::parse_quote! {
+            pub fn print_scope(_description: &str) {
+                /// Input: Takes in a description
+                /// Function: Works out the scope
+                /// Output: Prints the scope
+                /// Example 1:
+                ///   prints:
+                ///   {
+                ///     "is_crud_required": bool true
+                ///   }
+                println!(OUTPUT)
+            }
+        };
+
+        let err: syn::Error = lint_prompt(&input_fn).unwrap_err();
+        assert!(err.to_string().contains("example 1 of ai_function `print_scope`"));
+    }
+
+    #[test]
+    fn tests_json_examples() {
+        let lines: Vec<String> = [
+            "Output: Prints a list",
+            "Example 1:",
+            "  prints:",
+            r#"  ["https://a.com/{id}", "b"]"#,
+            "Example 2:",
+            "  prints:",
+            "  {",
+            r#"    "note": "braces } in strings","#,
+            r#"    "nested": { "ok": true }"#,
+            "  }",
+            "  that is all",
+        ]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+
+        assert_eq!(
+            json_examples(&lines),
+            vec![
+                r#"["https://a.com/{id}", "b"]"#.to_string(),
+                "{\n\"note\": \"braces } in strings\",\n\"nested\": { \"ok\": true }\n}".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_expands_json_schema() {
+        let input: syn::DeriveInput = syn::parse_quote! {
+            pub struct ProjectScope {
+                /// true if site needs CRUD functionality
+                pub is_crud_required: bool,
+                pub external_urls: Option<Vec<String>>,
+            }
+        };
+
+        let expanded: String = expand_json_schema(input).unwrap().to_string();
+        assert!(expanded.contains("\"required\" : [\"is_crud_required\"]"));
+        assert!(expanded.contains("\"true if site needs CRUD functionality\""));
+    }
+
+    #[test]
+    fn tests_rejects_json_schema_for_tuple_enums() {
+        let input: syn::DeriveInput = syn::parse_quote! {
+            pub enum Stack {
+                Backend(String),
+            }
+        };
+        assert!(expand_json_schema(input).is_err());
+    }
+
+    #[test]
+    fn tests_rejects_missing_inputs() {
+        let input_fn: ItemFn = syn::parse_quote! {
+            pub fn print_nothing() {
+                /// Input: Nothing
+                /// Function: Prints nothing
+                /// Output: Prints an empty string
+                println!(OUTPUT)
+            }
+        };
+        assert!(expand_ai_function(input_fn, None, "1", None, false).is_err());
+    }
+
+    #[test]
+    fn tests_expands_multiple_inputs() {
+        let input_fn: ItemFn = syn::parse_quote! {
+            pub fn print_code(description: &str, _routes: &[RouteObject]) {
+                /// Input: Takes in a description and routes
+                /// Function: Writes the code
+                /// Output: Prints the code
+                println!(OUTPUT)
+            }
+        };
+
+        let expanded: String = expand_ai_function(input_fn, None, "1", None, false).unwrap().to_string();
+        assert!(expanded.contains("type Input < 'a > = (& 'a str , & 'a [RouteObject]) ;"));
+        assert!(expanded.contains("& [\"description\" , \"routes\"]"));
+        assert!(expanded.contains("\"(&str, &[RouteObject])\""));
+    }
+
+    #[test]
+    fn tests_expands_few_shot_examples() {
+        let input_fn: ItemFn = syn::parse_quote! {
+            pub fn print_scope(_description: &str) {
+                /// Input: Takes in a description
+                /// Function: Works out the scope
+                /// Output: Prints the scope
+                println!(OUTPUT)
+            }
+        };
+
+        let expanded: String = expand_ai_function(
+            input_fn,
+            Some(syn::parse_quote!(ProjectScope)),
+            "1",
+            Some(syn::parse_quote!(scope_examples)),
+            false,
+        )
+        .unwrap()
+        .to_string();
+        assert!(expanded.contains("{ scope_examples () }"));
+        assert!(expanded.contains("mod print_scope_few_shot_examples"));
+    }
+}
@@ -2,22 +2,33 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 
 use crate::ai_functions::ai_functions::print_project_scope;
 use crate::utils::general::check_status_code;
 use crate::{
     agents::base::{
         agent_base::{AgentAttributes, AgentState},
-        agent_traits::{BasicAgentTraits, ProjectScope, ProjectSpec, SpecialFunctions},
+        agent_error::AgentError,
+        agent_traits::{BasicAgentTraits, ProjectScope, ProjectSpec, SpecField, SpecialFunctions},
+        state_machine::{run_state_machine, StateHandlers, StateMachine},
     },
     ai_functions::ai_functions::print_site_urls,
+    events::workflow_events::{emit, emit_message, WorkflowEvent},
     utils::{
         command_line::PrintMessage,
-        llm_apis::{request_task_llm, request_task_llm_deserialized},
+        llm_apis::request_task_llm_traced,
     },
 };
 
-#[derive(Debug)]
+// Discovery and UnitTesting, with room for nothing else
+const MAX_STATE_ITERATIONS: usize = 2;
+// Generating the scope and the URLs takes two LLM calls
+const LLM_STATE_TIMEOUT: Duration = Duration::from_secs(300);
+// Every URL is checked with a 5 second timeout
+const URL_CHECK_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ArchitectAgent {
     attributes: AgentAttributes,
 }
@@ -29,85 +40,92 @@ impl ArchitectAgent {
     }
 
     // Generate project scope and update project specification
-    async fn generate_project_scope(&mut self, project_spec: &mut ProjectSpec) -> ProjectScope {
+    async fn generate_project_scope(&mut self, project_spec: &mut ProjectSpec) -> Result<ProjectScope, AgentError> {
         let project_description = project_spec
             .project_description
             .as_ref()
-            .expect("Project description not defined yet!");
+            .ok_or(AgentError::MissingSpecField(SpecField::ProjectDescription))?;
 
-        let project_scope = request_task_llm_deserialized::<ProjectScope>(
+        let (project_scope, prompt_version): (ProjectScope, String) = request_task_llm_traced(
             print_project_scope,
-            project_description.to_string(),
+            project_description,
             &self.attributes.position,
-            get_function_string!(print_project_scope),
         )
-        .await;
+        .await?;
 
-        project_spec.project_scope = Some(project_scope.clone());
-        self.attributes.update_agent_state(AgentState::Finished);
-        project_scope
+        project_spec.project_scope = Some(project_scope);
+        project_spec.record_prompt_version("project_scope", prompt_version);
+        Ok(project_scope)
     }
 
     async fn generate_possible_external_urls(
         &self,
         project_spec: &mut ProjectSpec,
         msg_context: Option<String>,
-    ) {
-        let external_urls: Vec<String> = request_task_llm_deserialized::<Vec<String>>(
+    ) -> Result<(), AgentError> {
+        let (external_urls, prompt_version): (Vec<String>, String) = request_task_llm_traced(
             print_site_urls,
-            msg_context
-                .expect("Project description is missing!")
-                .to_string(),
+            &msg_context.ok_or(AgentError::MissingSpecField(SpecField::ProjectDescription))?,
             self.attributes.get_agent_position(),
-            get_function_string!(print_site_urls),
         )
-        .await;
+        .await?;
 
         project_spec.external_urls = Some(external_urls);
+        project_spec.record_prompt_version("external_urls", prompt_version);
+        Ok(())
     }
 
     // Check the validity of the external APIs
-    async fn verify_possible_external_urls(&self, project_spec: &mut ProjectSpec) {
+    async fn verify_possible_external_urls(&self, project_spec: &mut ProjectSpec) -> Result<(), AgentError> {
         let mut exclude_urls: Vec<String> = Vec::new();
 
-        let client: Client = match Client::builder().timeout(Duration::from_secs(5)).build() {
-            Ok(c) => c,
-            Err(_) => panic!("Something went wrong when creating the Client"),
-        };
+        let client: Client = Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .map_err(|e| AgentError::Other(format!("Could not create the HTTP client: {}", e)))?;
 
         // Find faulty urls from the provided urls
         let urls: &Vec<String> = project_spec
             .external_urls
             .as_ref()
-            .expect("No URL object in Project Spec");
+            .ok_or(AgentError::MissingSpecField(SpecField::ExternalUrls))?;
 
+        let mut failures: Vec<String> = Vec::new();
         for url in urls {
             let str_msg: String = format!("Testing URL endpoint: {}", url);
-            PrintMessage::Testing.print_agent_msg(self.attributes.get_agent_position(), &str_msg);
+            emit_message(PrintMessage::Testing, self.attributes.get_agent_position(), str_msg);
 
             // check URL
             match check_status_code(&client, url).await {
                 Ok(status_code) => {
                     if status_code != 200 {
                         exclude_urls.push(url.clone());
+                        failures.push(format!("{} returned {}", url, status_code));
                     }
                 }
-                Err(e) => println!("Error checking {}: {}", url, e),
+                Err(e) => failures.push(format!("{} could not be checked: {}", url, e)),
             }
         }
+        emit(WorkflowEvent::TestResult {
+            agent: self.attributes.position.clone(),
+            suite: "external urls".to_string(),
+            passed: urls.len() - failures.len(),
+            failures,
+        });
 
         // Exclude any faulty urls
-        if exclude_urls.len() > 0 {
+        if !exclude_urls.is_empty() {
             let new_urls: Vec<String> = project_spec
                 .external_urls
                 .as_ref()
                 .unwrap()
                 .iter()
-                .filter(|url| !exclude_urls.contains(&url))
+                .filter(|url| !exclude_urls.contains(url))
                 .cloned()
                 .collect();
             project_spec.external_urls = Some(new_urls);
         }
+        Ok(())
     }
 }
 
@@ -117,38 +135,73 @@ impl SpecialFunctions for ArchitectAgent {
         &self.attributes
     }
 
+    fn reads(&self) -> Vec<SpecField> {
+        vec![SpecField::ProjectDescription]
+    }
+
+    fn writes(&self) -> Vec<SpecField> {
+        vec![SpecField::ProjectScope, SpecField::ExternalUrls]
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("Failed to serialize the architect agent")
+    }
+
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        *self = serde_json::from_value(state)?;
+        Ok(())
+    }
+
     async fn execute(
         &mut self,
         proj_spec: &mut ProjectSpec,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // ! Warning - be careful of infinite loop !
-        while self.attributes.state != AgentState::Finished {
-            match self.attributes.state {
-                AgentState::Discovery => {
-                    let project_scope = self.generate_project_scope(proj_spec).await;
-
-                    // Check if there are external URLs we have to check
-                    if project_scope.is_external_urls_required {
-                        self.generate_possible_external_urls(
-                            proj_spec,
-                            proj_spec.project_description.clone(),
-                        )
-                        .await;
-                        self.attributes.update_agent_state(AgentState::UnitTesting);
-                    }
-                }
-                AgentState::UnitTesting => {
-                    // This stage will check verify the external URLs provided by GPT
-                    self.verify_possible_external_urls(proj_spec).await;
-                    self.attributes.update_agent_state(AgentState::Finished);
-                }
-                _ => {
-                    self.attributes.update_agent_state(AgentState::Finished);
+    ) -> Result<(), AgentError> {
+        run_state_machine(self, proj_spec).await
+    }
+}
+
+#[async_trait]
+impl StateHandlers for ArchitectAgent {
+    fn state_machine(&self) -> StateMachine {
+        StateMachine::new(MAX_STATE_ITERATIONS)
+            .transition(AgentState::Discovery, AgentState::UnitTesting)
+            .transition(AgentState::Discovery, AgentState::Finished)
+            .transition(AgentState::UnitTesting, AgentState::Finished)
+            .timeout(AgentState::Discovery, LLM_STATE_TIMEOUT)
+            .timeout(AgentState::UnitTesting, URL_CHECK_TIMEOUT)
+    }
+
+    fn attributes_mut(&mut self) -> &mut AgentAttributes {
+        &mut self.attributes
+    }
+
+    async fn handle_state(
+        &mut self,
+        state: AgentState,
+        proj_spec: &mut ProjectSpec,
+    ) -> Result<AgentState, AgentError> {
+        match state {
+            AgentState::Discovery => {
+                let project_scope = self.generate_project_scope(proj_spec).await?;
+
+                // Check if there are external URLs we have to check
+                if !project_scope.is_external_urls_required {
+                    return Ok(AgentState::Finished);
                 }
+                self.generate_possible_external_urls(
+                    proj_spec,
+                    proj_spec.project_description.clone(),
+                )
+                .await?;
+                Ok(AgentState::UnitTesting)
             }
+            AgentState::UnitTesting => {
+                // This stage will check verify the external URLs provided by GPT
+                self.verify_possible_external_urls(proj_spec).await?;
+                Ok(AgentState::Finished)
+            }
+            _ => Ok(AgentState::Finished),
         }
-
-        Ok(())
     }
 }
 
@@ -181,7 +234,7 @@ mod tests {
             "Solutions Architect".to_string(),
         );
 
-        architect.generate_project_scope(&mut project_spec).await;
+        architect.generate_project_scope(&mut project_spec).await.unwrap();
 
         dbg!(project_spec);
     }
@@ -5,159 +5,577 @@ use crate::{
             AgentAttributes, 
             AgentState
         }, 
+        agent_error::AgentError,
         agent_traits::{
-            BasicAgentTraits,
-            ProjectSpec, 
+            ProjectSpec,
+            RouteObject,
+            SpecField,
             SpecialFunctions
-        }
+        },
+        state_machine::{run_state_machine, StateHandlers, StateMachine}
     }, 
-    ai_functions::ai_functions::{print_backend_webserver_code, print_improved_webserver_code}, 
-    utils::{command_line::{confirm_safe_code, PrintMessage}, general::{read_code_template, save_code_to_file}, llm_apis::request_task_llm}
+    ai_functions::ai_functions::{
+        print_backend_webserver_code, print_fixed_code, print_improved_webserver_code, print_rest_api_endpoints
+    },
+    events::workflow_events::{emit, emit_message, WorkflowEvent},
+    utils::{
+        cargo_diagnostics::{apply_machine_applicable, diagnostic_snippets, parse_diagnostics, rendered_diagnostics, Diagnostic},
+        code_review::ReviewDecision,
+        command_line::{confirm_safe_code, PrintMessage},
+        endpoint_testing::{free_port, launch_backend_server, test_endpoints, wait_until_ready, EndpointFailure},
+        general::{backend_project_dir, build_backend_project, code_file_name, save_code_to_file, BuildOutput},
+        language::{ProjectCommand, ProjectLanguage},
+        llm_apis::request_task_llm_traced,
+        openapi::{dynamic_routes_without_parameters, openapi_document, save_openapi, OpenApiDocument},
+        route_extraction::{extract_actix_routes, merge_responses, save_api_endpoints}
+    }
 };
 use dotenv::dotenv;
-use std::{env, process::{Command, Stdio}};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::PathBuf, time::Duration};
+use tokio::{process::Child, task::JoinHandle};
 
+// Fix attempts when MAX_BUG_FIX_ATTEMPTS is not set
+const DEFAULT_MAX_BUG_FIX_ATTEMPTS: u8 = 3;
+// Revisions a reviewer may ask for, counted apart from the fix attempts of failed builds
+const MAX_REVIEW_REVISIONS: u8 = 10;
+// Time the built server gets to start listening
+const SERVER_STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+// Writing or fixing the code is one LLM call
+const LLM_STATE_TIMEOUT: Duration = Duration::from_secs(300);
+// Building the code and calling every route
+const UNIT_TESTING_TIMEOUT: Duration = Duration::from_secs(900);
 
-#[derive(Debug)]
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BackendAgent {
     attributes: AgentAttributes,
+    language: ProjectLanguage,
     bug_errors: Option<String>,
-    bug_count: u8
+    bug_count: u8,
+    // Checkpoints written before this setting existed use MAX_BUG_FIX_ATTEMPTS
+    #[serde(default = "max_bug_fix_attempts")]
+    max_bug_fix_attempts: u8,
+    #[serde(default)]
+    review_revisions: u8,
+    // Code the reviewer last accepted, the diff of the next review starts from it
+    #[serde(default)]
+    reviewed_code: Option<String>
 }
 
 impl BackendAgent {
-    pub fn new(objective: String, position: String) -> Self {
+    pub fn new(objective: String, position: String, language: ProjectLanguage) -> Self {
         let attributes: AgentAttributes = AgentAttributes::new(objective, position);
         Self { 
             attributes, 
+            language,
             bug_errors: None, 
-            bug_count: 0 
+            bug_count: 0,
+            max_bug_fix_attempts: max_bug_fix_attempts(),
+            review_revisions: 0,
+            reviewed_code: None
         }
     }
 
-    async fn call_initial_backend_code(&mut self, proj_spec: &mut ProjectSpec) {
-        dotenv().ok();
-        let filepath: String = env::var("CODE_FILEPATH").expect("Could not find CODE_FILEPATH value from .env");
-        let template_code: String = read_code_template(&filepath);
+    // Overrides MAX_BUG_FIX_ATTEMPTS, e.g. from the settings of a workflow config
+    pub fn with_max_bug_fix_attempts(mut self, attempts: u8) -> Self {
+        self.max_bug_fix_attempts = attempts;
+        self
+    }
 
-        let user_req: String = format!(
-            "CODE TEMPLATE: {} \n PROJECT DESCRIPTION: {} \n",
-            template_code, proj_spec.project_description.as_ref().expect("Project description is missing").to_string()
-        );
+    async fn call_initial_backend_code(&mut self, proj_spec: &mut ProjectSpec) -> Result<(), AgentError> {
+        let template_code: String = self.language.code_template()?;
+        let output_file: String = self.language.code_output_filepath()?;
+        let project_description: &str = proj_spec
+            .project_description
+            .as_ref()
+            .ok_or(AgentError::MissingSpecField(SpecField::ProjectDescription))?;
 
-        let content = request_task_llm(
-            print_backend_webserver_code, 
-            user_req, 
-            &self.attributes.position, 
-            get_function_string!(print_backend_webserver_code)
-        ).await;
-
-        let output_file: String = env::var("CODE_OUTPUT_FILEPATH")
-            .expect("Could not find CODE_OUTPUT_FILEPATH value from .env");
-        
-        save_code_to_file(&output_file, &content);
+        let (content, prompt_version): (String, String) = request_task_llm_traced(
+            print_backend_webserver_code,
+            (project_description, &template_code, &self.language.to_string(), self.language.allowed_libraries()),
+            &self.attributes.position
+        ).await?;
+
+        self.save_backend_code(&output_file, &content)?;
         proj_spec.backend_code = Some(content);
+        proj_spec.record_prompt_version("backend_code", prompt_version);
+        Ok(())
     }
 
-    async fn improve_backend_code(&mut self, proj_spec: &mut ProjectSpec) {
+    async fn improve_backend_code(&mut self, proj_spec: &mut ProjectSpec) -> Result<(), AgentError> {
         dotenv().ok();
 
-        let msg_context: String = format!(
-            "CODE TEMPLATE: {:?} \n PROJECT SPECIFICATIONS: {:?} \n",
-            proj_spec.backend_code, proj_spec
-        );
+        let output_file: String = self.language.code_output_filepath()?;
+        let backend_code: String = proj_spec.backend_code.clone().unwrap_or_default();
 
         // Get LLM response
-        let content: String = request_task_llm(
+        let (content, prompt_version): (String, String) = request_task_llm_traced(
             print_improved_webserver_code,
-            msg_context,
-            &self.attributes.position,
-            get_function_string!(print_improved_webserver_code)
-        ).await;
+            (&*proj_spec, &backend_code, &self.language.to_string(), self.language.allowed_libraries()),
+            &self.attributes.position
+        ).await?;
+
+        self.save_backend_code(&output_file, &content)?;
+        proj_spec.backend_code = Some(content);
+        proj_spec.record_prompt_version("backend_code", prompt_version);
+        Ok(())
+    }
+
+    // Sends the build errors together with the current code to the LLM and saves the fixed code
+    async fn fix_backend_bugs(&mut self, proj_spec: &mut ProjectSpec) -> Result<(), AgentError> {
+        dotenv().ok();
 
-        let output_file: String = env::var("CODE_OUTPUT_FILEPATH")
-            .expect("Could not find CODE_OUTPUT_FILEPATH value from .env");
+        let output_file: String = self.language.code_output_filepath()?;
+        let backend_code: String = proj_spec.backend_code.clone().unwrap_or_default();
+        let bug_errors: String = self.bug_errors.clone().unwrap_or_default();
 
-        save_code_to_file(&output_file, &content);
+        let (content, prompt_version): (String, String) = request_task_llm_traced(
+            print_fixed_code,
+            (&backend_code, &bug_errors, &self.language.to_string()),
+            &self.attributes.position
+        ).await?;
+
+        self.save_backend_code(&output_file, &content)?;
         proj_spec.backend_code = Some(content);
+        proj_spec.record_prompt_version("backend_code", prompt_version);
+        Ok(())
+    }
+
+    fn save_backend_code(&self, output_file: &str, content: &str) -> Result<(), AgentError> {
+        save_code_to_file(output_file, content).map_err(|e| AgentError::io(output_file, e))?;
+        emit(WorkflowEvent::FileWritten { agent: self.attributes.position.clone(), path: output_file.to_string() });
+        Ok(())
+    }
+
+    // Code edited during the review replaces the generated code, it was already saved by the editor
+    fn use_reviewed_code(&self, proj_spec: &mut ProjectSpec, code: String) {
+        if proj_spec.backend_code.as_deref() == Some(code.as_str()) {
+            return;
+        }
+        emit_message(PrintMessage::Info, &self.attributes.position, "Using the code as edited in the review");
+        proj_spec.backend_code = Some(code);
+    }
+
+    // Builds the backend. Rust builds first apply the fixes rustc is certain about so the LLM is
+    // only asked about the remaining errors. Returns the final build and its diagnostics.
+    async fn build_with_machine_fixes(
+        &self,
+        proj_spec: &mut ProjectSpec
+    ) -> Result<(BuildOutput, Vec<Diagnostic>), AgentError> {
+        let output_file: String = self.language.code_output_filepath()?;
+        let project_dir: PathBuf = backend_project_dir(&output_file);
+
+        let build: BuildOutput = build_backend_project(self.language, &output_file).await;
+        if self.language != ProjectLanguage::Rust {
+            return Ok((build, Vec::new()));
+        }
+        let diagnostics: Vec<Diagnostic> = parse_diagnostics(&build.stdout);
+        if build.success {
+            return Ok((build, diagnostics));
+        }
+
+        // Rewrites the source files, kept off the threads the other agents run on
+        let fixes: JoinHandle<std::io::Result<usize>> = tokio::task::spawn_blocking({
+            let diagnostics: Vec<Diagnostic> = diagnostics.clone();
+            move || apply_machine_applicable(&project_dir, &diagnostics)
+        });
+        let applied: std::io::Result<usize> = fixes.await.unwrap_or_else(|e| Err(std::io::Error::other(e)));
+        match applied {
+            Ok(applied) if applied > 0 => {
+                emit_message(
+                    PrintMessage::Testing,
+                    &self.attributes.position,
+                    format!("Applied {} compiler suggestions, rebuilding...", applied)
+                );
+                let fixed_code: String = fs::read_to_string(&output_file).map_err(|e| AgentError::io(&output_file, e))?;
+                proj_spec.backend_code = Some(fixed_code);
+
+                let rebuild: BuildOutput = build_backend_project(self.language, &output_file).await;
+                let diagnostics: Vec<Diagnostic> = parse_diagnostics(&rebuild.stdout);
+                Ok((rebuild, diagnostics))
+            },
+            Ok(_) => Ok((build, diagnostics)),
+            Err(e) => {
+                emit_message(
+                    PrintMessage::Error,
+                    &self.attributes.position,
+                    format!("Could not apply compiler suggestions: {}", e)
+                );
+                Ok((build, diagnostics))
+            }
+        }
+    }
+
+    // Derives the endpoint schema from the backend code, parsing the actix-web routes of Rust
+    // backends and asking the LLM for their responses, or for the whole schema for other
+    // languages or when none are found. Saves it as api_endpoints.json next to the code and as an
+    // OpenAPI document in the project.
+    async fn extract_api_endpoints(&self, proj_spec: &mut ProjectSpec) -> Result<(), AgentError> {
+        let backend_code: String = proj_spec.backend_code.clone().unwrap_or_default();
+
+        let parsed_routes: Vec<RouteObject> = match self.language {
+            ProjectLanguage::Rust => extract_actix_routes(&backend_code).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+        let routes: Vec<RouteObject> = match parsed_routes {
+            mut routes if !routes.is_empty() => {
+                // The handlers do not declare their responses, the LLM describes them. Without
+                // them the endpoint tests only check the status codes.
+                match request_task_llm_traced(print_rest_api_endpoints, &backend_code, &self.attributes.position).await {
+                    Ok((described, prompt_version)) => {
+                        merge_responses(&mut routes, &described);
+                        proj_spec.record_prompt_version("api_endpoint_schema", prompt_version);
+                    },
+                    Err(e) => {
+                        emit_message(
+                            PrintMessage::Error,
+                            &self.attributes.position,
+                            format!("Could not describe the route responses, only testing status codes: {}", e)
+                        );
+                        proj_spec.prompt_versions.remove("api_endpoint_schema");
+                    }
+                }
+                routes
+            },
+            _ => {
+                let (routes, prompt_version): (Vec<RouteObject>, String) = request_task_llm_traced(
+                    print_rest_api_endpoints,
+                    &backend_code,
+                    &self.attributes.position
+                ).await?;
+                proj_spec.record_prompt_version("api_endpoint_schema", prompt_version);
+                routes
+            }
+        };
+
+        let output_file: String = self.language.code_output_filepath()?;
+        match save_api_endpoints(&output_file, &routes) {
+            Ok(path) => {
+                emit_message(
+                    PrintMessage::Testing,
+                    &self.attributes.position,
+                    format!("Found {} backend routes", routes.len())
+                );
+                emit(WorkflowEvent::FileWritten { agent: self.attributes.position.clone(), path: path.display().to_string() });
+            },
+            Err(e) => emit_message(
+                PrintMessage::Error,
+                &self.attributes.position,
+                format!("Could not save the backend routes: {}", e)
+            ),
+        }
+
+        for route in dynamic_routes_without_parameters(&routes) {
+            emit_message(
+                PrintMessage::Error,
+                &self.attributes.position,
+                format!("Route {} {} is marked dynamic but has no path parameter", route.method, route.route)
+            );
+        }
+        let document: OpenApiDocument = openapi_document(
+            "Backend API",
+            proj_spec.project_description.as_deref(),
+            &routes
+        );
+        match save_openapi(&backend_project_dir(&output_file), &document) {
+            Ok((json_path, yaml_path)) => {
+                for path in [json_path, yaml_path] {
+                    emit(WorkflowEvent::FileWritten { agent: self.attributes.position.clone(), path: path.display().to_string() });
+                }
+            },
+            Err(e) => emit_message(
+                PrintMessage::Error,
+                &self.attributes.position,
+                format!("Could not save the OpenAPI document: {}", e)
+            ),
+        }
+        proj_spec.api_endpoint_schema = Some(routes);
+        // The frontend can be written while the routes are still being tested and fixed
+        proj_spec.publish(SpecField::ApiEndpointSchema);
+        Ok(())
+    }
+
+    // Runs the built server on a free port and calls every route of the endpoint schema,
+    // returning the routes that did not respond as described
+    async fn test_backend_endpoints(&self, routes: &[RouteObject]) -> Result<Vec<EndpointFailure>, AgentError> {
+        let output_file: String = self.language.code_output_filepath()?;
+        let server_failure = |reason: String| vec![EndpointFailure { route: "server".to_string(), reason }];
+
+        let port: u16 = match free_port() {
+            Ok(port) => port,
+            Err(e) => return Ok(server_failure(format!("no free port to run the server on: {}", e))),
+        };
+        let run_command: ProjectCommand = self.language.run_command(code_file_name(&output_file));
+        let mut server: Child = match launch_backend_server(&backend_project_dir(&output_file), &run_command, port) {
+            Ok(server) => server,
+            Err(e) => return Ok(server_failure(format!("could not be started: {}", e))),
+        };
+
+        emit_message(
+            PrintMessage::Testing,
+            &self.attributes.position,
+            format!("Backend server launched on port {}, waiting until it is ready...", port)
+        );
+
+        if !wait_until_ready(port, SERVER_STARTUP_TIMEOUT).await {
+            // Its output explains why it never started listening
+            let _ = server.start_kill();
+            let stderr: String = match server.wait_with_output().await {
+                Ok(output) => String::from_utf8_lossy(&output.stderr).into_owned(),
+                Err(e) => e.to_string(),
+            };
+            return Ok(server_failure(format!(
+                "did not listen on port {} within {} seconds:\n{}",
+                port,
+                SERVER_STARTUP_TIMEOUT.as_secs(),
+                stderr
+            )));
+        }
+
+        let client: Client = match Client::builder().timeout(Duration::from_secs(5)).build() {
+            Ok(client) => client,
+            Err(e) => {
+                let _ = server.kill().await;
+                return Err(AgentError::Other(format!("Could not create the HTTP client: {}", e)));
+            }
+        };
+        let failures: Vec<EndpointFailure> = test_endpoints(&client, &format!("http://127.0.0.1:{}", port), routes).await;
+
+        let _ = server.kill().await;
+        Ok(failures)
+    }
+
+    // Keeps the errors of a failed build or endpoint test for the next fix attempt, giving up once
+    // the maximum number of attempts has been used. Returns the state to move to.
+    fn record_bugs(&mut self, bug_errors: String, max_attempts: u8) -> Result<AgentState, AgentError> {
+        self.bug_count += 1;
+        self.bug_errors = Some(bug_errors);
+
+        if self.bug_count > max_attempts {
+            return Err(AgentError::FixAttemptsExhausted {
+                attempts: max_attempts,
+                errors: self.bug_errors.clone().unwrap_or_default(),
+            });
+        }
+
+        Ok(AgentState::Working)
     }
 
-    async fn fix_backend_bugs(&mut self, proj_spec: &mut ProjectSpec) {
+    // Keeps the comments of the reviewer for the next revision of the code. Revisions do not use
+    // up the fix attempts, but are limited by MAX_REVIEW_REVISIONS.
+    fn record_review_comments(&mut self, comments: &str) -> Result<AgentState, AgentError> {
+        self.review_revisions += 1;
+        if self.review_revisions > MAX_REVIEW_REVISIONS {
+            return Err(AgentError::Other(format!(
+                "The reviewer asked for more than {} revisions of the backend code",
+                MAX_REVIEW_REVISIONS
+            )));
+        }
 
+        self.bug_errors = Some(format!("The reviewer asked for these changes:\n{}", comments));
+        Ok(AgentState::Working)
     }
 }
 
+// Number of times the build errors are sent back to the LLM before giving up
+fn max_bug_fix_attempts() -> u8 {
+    dotenv().ok();
+    env::var("MAX_BUG_FIX_ATTEMPTS")
+        .ok()
+        .and_then(|attempts| attempts.parse().ok())
+        .unwrap_or(DEFAULT_MAX_BUG_FIX_ATTEMPTS)
+}
+
 #[async_trait]
 impl SpecialFunctions for BackendAgent {
     fn get_attributes_from_agent(&self) -> &AgentAttributes {
         &self.attributes
     }
 
+    fn reads(&self) -> Vec<SpecField> {
+        vec![SpecField::ProjectDescription, SpecField::ProjectScope, SpecField::ExternalUrls]
+    }
+
+    fn writes(&self) -> Vec<SpecField> {
+        vec![SpecField::BackendCode, SpecField::ApiEndpointSchema]
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("Failed to serialize the backend agent")
+    }
+
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        *self = serde_json::from_value(state)?;
+        Ok(())
+    }
+
     async fn execute(
         &mut self, 
         proj_spec: &mut ProjectSpec
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // Keep execution until AgentState::Finished state has been reached
-        // Take project description and code template and generate backend code
-        while self.attributes.state != AgentState::Finished {
-            match self.attributes.state {
-                AgentState::Discovery => {
-                    self.call_initial_backend_code(proj_spec).await;
-                    self.attributes.update_agent_state(AgentState::Working);
-                    continue;
-                },
-                AgentState::Working => {
-                    if self.bug_count == 0 {
-                        self.improve_backend_code(proj_spec).await;
-                    } else {
-                        self.fix_backend_bugs(proj_spec).await;
-                    }
-                    self.attributes.update_agent_state(AgentState::UnitTesting);
-                    continue;
-                },
-                AgentState::UnitTesting => {
-                    PrintMessage::Testing.print_agent_msg(
-                        &self.attributes.position, 
-                        "Testing backend code: Ensuring safe code..."
-                    );
+    ) -> Result<(), AgentError> {
+        run_state_machine(self, proj_spec).await
+    }
+}
 
-                    let is_safe_code: bool = confirm_safe_code();
-                    if !is_safe_code {
-                        // Exit program
-                        panic!("Better work on some AI alignment.");
+#[async_trait]
+impl StateHandlers for BackendAgent {
+    // Writing the code, then a Working, Review and UnitTesting round per fix attempt and a Working
+    // and Review round per review revision. The review waits on a person and is not timed.
+    fn state_machine(&self) -> StateMachine {
+        StateMachine::new(1 + 3 * (self.max_bug_fix_attempts as usize + 1) + 2 * MAX_REVIEW_REVISIONS as usize)
+            .transition(AgentState::Discovery, AgentState::Working)
+            .transition(AgentState::Working, AgentState::Review)
+            .transition(AgentState::Review, AgentState::Working)
+            .transition(AgentState::Review, AgentState::UnitTesting)
+            .transition(AgentState::UnitTesting, AgentState::Working)
+            .transition(AgentState::UnitTesting, AgentState::Finished)
+            .timeout(AgentState::Discovery, LLM_STATE_TIMEOUT)
+            .timeout(AgentState::Working, LLM_STATE_TIMEOUT)
+            .timeout(AgentState::UnitTesting, UNIT_TESTING_TIMEOUT)
+    }
+
+    fn attributes_mut(&mut self) -> &mut AgentAttributes {
+        &mut self.attributes
+    }
+
+    // Take project description and code template and generate backend code
+    async fn handle_state(
+        &mut self,
+        state: AgentState,
+        proj_spec: &mut ProjectSpec
+    ) -> Result<AgentState, AgentError> {
+        match state {
+            AgentState::Discovery => {
+                self.call_initial_backend_code(proj_spec).await?;
+                Ok(AgentState::Working)
+            },
+            AgentState::Working => {
+                // Build errors, failed routes or review comments are fixed, otherwise the code is improved
+                if self.bug_errors.is_none() {
+                    self.improve_backend_code(proj_spec).await?;
+                } else {
+                    self.fix_backend_bugs(proj_spec).await?;
+                }
+                Ok(AgentState::Review)
+            },
+            AgentState::Review => {
+                emit_message(
+                    PrintMessage::Testing,
+                    &self.attributes.position,
+                    "Testing backend code: Ensuring safe code..."
+                );
+
+                // The first review shows what was changed from the code template
+                let output_file: String = self.language.code_output_filepath()?;
+                let previous_code: String = match &self.reviewed_code {
+                    Some(code) => code.clone(),
+                    None => self.language.code_template()?,
+                };
+                let backend_code: String = proj_spec.backend_code.clone().unwrap_or_default();
+                // Waiting for the reviewer must not hold up the agents running alongside. The blocking
+                // thread reads stdin until answered, so the review must never be cancelled, e.g. by a
+                // timeout of its state, or the next review would compete with it for the answers.
+                let review: JoinHandle<ReviewDecision> = tokio::task::spawn_blocking(move || {
+                    confirm_safe_code(&previous_code, &backend_code, &output_file)
+                });
+                let decision: ReviewDecision = review
+                    .await
+                    .map_err(|e| AgentError::Other(format!("The code review failed: {}", e)))?;
+                match decision {
+                    ReviewDecision::Accept(code) => {
+                        self.use_reviewed_code(proj_spec, code);
+                        self.reviewed_code = proj_spec.backend_code.clone();
+                        Ok(AgentState::UnitTesting)
+                    },
+                    ReviewDecision::Reject => Err(AgentError::CodeRejected),
+                    ReviewDecision::Revise { code, comments } => {
+                        self.use_reviewed_code(proj_spec, code);
+                        emit_message(
+                            PrintMessage::Testing,
+                            &self.attributes.position,
+                            format!("Sending the review comments back for revision {}...", self.review_revisions + 1)
+                        );
+                        self.record_review_comments(&comments)
                     }
+                }
+            },
+            AgentState::UnitTesting => {
+                let output_file: String = self.language.code_output_filepath()?;
+                emit_message(
+                    PrintMessage::Testing,
+                    &self.attributes.position,
+                    "Backend code united testing: Building web server..."
+                );
 
-                    PrintMessage::Testing.print_agent_msg(
-                        &self.attributes.position, 
-                        "Backend code united testing: Building web server..."
-                    );
+                // Runs the build commands of the language, e.g. `cargo build`
+                let (build_backend_server, diagnostics): (BuildOutput, Vec<Diagnostic>) =
+                    self.build_with_machine_fixes(proj_spec).await?;
 
-                    // Runs the command `cargo build`
-                    let build_backend_server: std::process::Output = Command::new("cargo")
-                        .arg("build")
-                        .current_dir(env::var("CODE_OUTPUT_FILEPATH").expect("Could not find CODE_OUTPUT_FILEPATH in .env file"))
-                        .stdout(Stdio::piped())
-                        .stdout(Stdio::piped())
-                        .output()
-                        .expect("Failed to build backend application code");
-
-                    // Determin if build errors
-                    if build_backend_server.status.success() {
+                // Determin if build errors
+                if build_backend_server.success {
+                    emit(WorkflowEvent::BuildResult {
+                        agent: self.attributes.position.clone(),
+                        success: true,
+                        errors: None,
+                    });
+
+                    self.extract_api_endpoints(proj_spec).await?;
+                    let routes: Vec<RouteObject> = proj_spec.api_endpoint_schema.clone().unwrap_or_default();
+                    let failures: Vec<EndpointFailure> = self.test_backend_endpoints(&routes).await?;
+                    emit(WorkflowEvent::TestResult {
+                        agent: self.attributes.position.clone(),
+                        suite: "backend routes".to_string(),
+                        passed: routes.len().saturating_sub(failures.len()),
+                        failures: failures.iter().map(|failure| failure.to_string()).collect(),
+                    });
+                    if failures.is_empty() {
                         self.bug_count = 0;
-                        PrintMessage::Testing.print_agent_msg(
-                            self.attributes.position.as_str(), 
-                            "Test server build successfully..."
-                        );
+                        self.bug_errors = None;
+                        Ok(AgentState::Finished)
                     } else {
-
+                        let bug_errors: String = failures
+                            .iter()
+                            .map(|failure| failure.to_string())
+                            .collect::<Vec<String>>()
+                            .join("\n");
+                        emit_message(
+                            PrintMessage::Error,
+                            &self.attributes.position,
+                            format!("{} backend routes failed, sending them back for fix attempt {}...", failures.len(), self.bug_count + 1)
+                        );
+                        self.record_bugs(bug_errors, self.max_bug_fix_attempts)
                     }
-                },
-                _ => {
-                    self.attributes.update_agent_state(AgentState::Finished);
+                } else {
+                    // Only the relevant snippets go to the LLM, then the diagnostics as rustc rendered them,
+                    // the raw output if nothing could be parsed. Other checkers print text to either stream.
+                    let snippets: String = diagnostic_snippets(&backend_project_dir(&output_file), &diagnostics);
+                    let rendered: String = rendered_diagnostics(&diagnostics);
+                    let bug_errors: String = if !snippets.is_empty() {
+                        snippets
+                    } else if !rendered.is_empty() {
+                        rendered
+                    } else {
+                        format!("{}\n{}", build_backend_server.stdout.trim(), build_backend_server.stderr.trim())
+                            .trim()
+                            .to_string()
+                    };
+                    emit(WorkflowEvent::BuildResult {
+                        agent: self.attributes.position.clone(),
+                        success: false,
+                        errors: Some(bug_errors.clone()),
+                    });
+                    emit_message(
+                        PrintMessage::Error,
+                        &self.attributes.position,
+                        format!("Sending the build errors back for fix attempt {}...", self.bug_count + 1)
+                    );
+                    self.record_bugs(bug_errors, self.max_bug_fix_attempts)
                 }
-            }
+            },
+            _ => Ok(AgentState::Finished)
         }
-        Ok(())
     }
 }
 
@@ -170,9 +588,66 @@ mod tests {
     fn create_backend_agent() {
         let backend_agent = BackendAgent::new(
             "Build server side application".to_owned(),
-            "Backend Agent".to_owned()
+            "Backend Agent".to_owned(),
+            ProjectLanguage::Rust
         );
 
         dbg!(backend_agent);
     }
+
+    #[test]
+    fn tests_record_bugs() {
+        let mut backend_agent = BackendAgent::new(
+            "Build server side application".to_owned(),
+            "Backend Agent".to_owned(),
+            ProjectLanguage::Rust
+        );
+
+        for attempt in 1..=2 {
+            let next_state: AgentState = backend_agent.record_bugs(format!("error[E0425]: attempt {}", attempt), 2).unwrap();
+            assert_eq!(next_state, AgentState::Working);
+        }
+        assert_eq!(backend_agent.bug_errors.as_deref(), Some("error[E0425]: attempt 2"));
+
+        let err = backend_agent.record_bugs("error[E0425]: attempt 3".to_owned(), 2).unwrap_err();
+        assert!(err.to_string().starts_with("Backend code still fails after 2 fix attempts"));
+    }
+
+    #[test]
+    fn tests_review_state_untimed() {
+        let backend_agent = BackendAgent::new(
+            "Build server side application".to_owned(),
+            "Backend Agent".to_owned(),
+            ProjectLanguage::Rust
+        );
+        let machine: StateMachine = backend_agent.state_machine();
+        assert_eq!(machine.timeout_for(AgentState::Review), None);
+        assert_eq!(machine.timeout_for(AgentState::UnitTesting), Some(UNIT_TESTING_TIMEOUT));
+    }
+
+    #[test]
+    fn tests_review_revisions_keep_fix_attempts() {
+        let mut backend_agent = BackendAgent::new(
+            "Build server side application".to_owned(),
+            "Backend Agent".to_owned(),
+            ProjectLanguage::Rust
+        ).with_max_bug_fix_attempts(1);
+
+        // More review rounds than fix attempts
+        for revision in 1..=3 {
+            let next_state: AgentState = backend_agent.record_review_comments(&format!("rename handler {}", revision)).unwrap();
+            assert_eq!(next_state, AgentState::Working);
+        }
+        assert_eq!(backend_agent.bug_count, 0);
+        assert_eq!(backend_agent.bug_errors.as_deref(), Some("The reviewer asked for these changes:\nrename handler 3"));
+
+        // The build still gets its fix attempt after the reviews
+        assert_eq!(backend_agent.record_bugs("error[E0425]".to_owned(), 1).unwrap(), AgentState::Working);
+        assert!(backend_agent.record_bugs("error[E0425]".to_owned(), 1).is_err());
+
+        for _ in 3..MAX_REVIEW_REVISIONS {
+            backend_agent.record_review_comments("more changes").unwrap();
+        }
+        assert!(backend_agent.record_review_comments("more changes").is_err());
+    }
 }
@@ -0,0 +1,244 @@
+use async_trait::async_trait;
+use crate::{
+    agents::base::{
+        agent_base::{
+            AgentAttributes,
+            AgentState
+        },
+        agent_error::AgentError,
+        agent_traits::{
+            FrontendCode,
+            ProjectSpec,
+            RouteObject,
+            SpecField,
+            SpecialFunctions
+        },
+        state_machine::{run_state_machine, StateHandlers, StateMachine}
+    },
+    ai_functions::ai_functions::print_frontend_code,
+    events::workflow_events::{emit, emit_message, WorkflowEvent},
+    utils::{command_line::PrintMessage, llm_apis::request_task_llm_traced}
+};
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use std::{env, fs, path::{Path, PathBuf}, time::Duration};
+
+// Generating the code and checking it
+const MAX_STATE_ITERATIONS: usize = 2;
+// The complete client is written by one LLM call
+const LLM_STATE_TIMEOUT: Duration = Duration::from_secs(300);
+
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FrontendAgent {
+    attributes: AgentAttributes
+}
+
+impl FrontendAgent {
+    pub fn new(objective: String, position: String) -> Self {
+        let attributes: AgentAttributes = AgentAttributes::new(objective, position);
+        Self { attributes }
+    }
+
+    async fn generate_frontend_code(&mut self, proj_spec: &mut ProjectSpec) -> Result<(), AgentError> {
+        dotenv().ok();
+        let output_dir: PathBuf = PathBuf::from(
+            env::var("FRONTEND_OUTPUT_DIR").map_err(|_| AgentError::config("FRONTEND_OUTPUT_DIR"))?
+        );
+        let project_description: &str = proj_spec
+            .project_description
+            .as_ref()
+            .ok_or(AgentError::MissingSpecField(SpecField::ProjectDescription))?;
+
+        // Frontend only websites have no backend routes to call
+        let api_endpoint_schema: &[RouteObject] = proj_spec
+            .api_endpoint_schema
+            .as_deref()
+            .unwrap_or_default();
+
+        let (frontend_code, prompt_version): (FrontendCode, String) = request_task_llm_traced(
+            print_frontend_code,
+            (project_description, api_endpoint_schema),
+            &self.attributes.position
+        ).await?;
+
+        let written: Vec<PathBuf> = write_frontend_files(&output_dir, &frontend_code)
+            .map_err(|e| AgentError::io(output_dir.display(), e))?;
+        for path in written {
+            emit(WorkflowEvent::FileWritten {
+                agent: self.attributes.position.clone(),
+                path: path.display().to_string(),
+            });
+        }
+
+        proj_spec.frontend_code = Some(frontend_code);
+        proj_spec.record_prompt_version("frontend_code", prompt_version);
+        Ok(())
+    }
+}
+
+// Writes the client as index.html, styles.css and app.js into the output directory, returning
+// the written files
+fn write_frontend_files(output_dir: &Path, frontend_code: &FrontendCode) -> std::io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(output_dir)?;
+    let files: [(&str, &str); 3] = [
+        ("index.html", &frontend_code.html),
+        ("styles.css", &frontend_code.css),
+        ("app.js", &frontend_code.js),
+    ];
+    let mut written: Vec<PathBuf> = Vec::new();
+    for (name, content) in files {
+        let path: PathBuf = output_dir.join(name);
+        fs::write(&path, content)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+// Routes of the schema the client never calls. Dynamic routes only have to match up to their
+// first path parameter, e.g. `/todos/{id}` is called by `/todos/${todo.id}`.
+fn missing_routes(js: &str, routes: &[RouteObject]) -> Vec<String> {
+    routes
+        .iter()
+        .filter(|route| {
+            let static_prefix: &str = route
+                .route
+                .split(['{', ':'])
+                .next()
+                .unwrap_or_default();
+            !js.contains(static_prefix)
+        })
+        .map(|route| format!("{} {}", route.method.to_uppercase(), route.route))
+        .collect()
+}
+
+#[async_trait]
+impl SpecialFunctions for FrontendAgent {
+    fn get_attributes_from_agent(&self) -> &AgentAttributes {
+        &self.attributes
+    }
+
+    fn reads(&self) -> Vec<SpecField> {
+        vec![SpecField::ProjectDescription, SpecField::ApiEndpointSchema]
+    }
+
+    fn writes(&self) -> Vec<SpecField> {
+        vec![SpecField::FrontendCode]
+    }
+
+    fn save_state(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("Failed to serialize the frontend agent")
+    }
+
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+        *self = serde_json::from_value(state)?;
+        Ok(())
+    }
+
+    async fn execute(
+        &mut self,
+        proj_spec: &mut ProjectSpec
+    ) -> Result<(), AgentError> {
+        run_state_machine(self, proj_spec).await
+    }
+}
+
+#[async_trait]
+impl StateHandlers for FrontendAgent {
+    fn state_machine(&self) -> StateMachine {
+        StateMachine::new(MAX_STATE_ITERATIONS)
+            .transition(AgentState::Discovery, AgentState::UnitTesting)
+            .transition(AgentState::UnitTesting, AgentState::Finished)
+            .timeout(AgentState::Discovery, LLM_STATE_TIMEOUT)
+    }
+
+    fn attributes_mut(&mut self) -> &mut AgentAttributes {
+        &mut self.attributes
+    }
+
+    async fn handle_state(
+        &mut self,
+        state: AgentState,
+        proj_spec: &mut ProjectSpec
+    ) -> Result<AgentState, AgentError> {
+        match state {
+            AgentState::Discovery => {
+                self.generate_frontend_code(proj_spec).await?;
+                Ok(AgentState::UnitTesting)
+            },
+            AgentState::UnitTesting => {
+                emit_message(
+                    PrintMessage::Testing,
+                    &self.attributes.position,
+                    "Testing frontend code: Checking every backend route is called..."
+                );
+
+                let js: &str = proj_spec.frontend_code.as_ref().map(|code| code.js.as_str()).unwrap_or_default();
+                let routes: &[RouteObject] = proj_spec.api_endpoint_schema.as_deref().unwrap_or_default();
+                let missing: Vec<String> = missing_routes(js, routes);
+                emit(WorkflowEvent::TestResult {
+                    agent: self.attributes.position.clone(),
+                    suite: "backend routes called by the frontend".to_string(),
+                    passed: routes.len() - missing.len(),
+                    failures: missing.iter().map(|route| format!("Frontend does not call {}", route)).collect(),
+                });
+                Ok(AgentState::Finished)
+            },
+            _ => Ok(AgentState::Finished)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(method: &str, route: &str) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: route.contains('{').to_string(),
+            method: method.to_string(),
+            request_body: serde_json::Value::Null,
+            response: serde_json::Value::Null,
+            route: route.to_string(),
+        }
+    }
+
+    #[test]
+    fn create_frontend_agent() {
+        let frontend_agent = FrontendAgent::new(
+            "Build client side application".to_owned(),
+            "Frontend Agent".to_owned()
+        );
+
+        dbg!(frontend_agent);
+    }
+
+    #[test]
+    fn tests_missing_routes() {
+        let js: &str = r#"
+            fetch(`${API_BASE_URL}/todos`);
+            fetch(`${API_BASE_URL}/todos/${id}`, { method: "DELETE" });
+        "#;
+        let routes: Vec<RouteObject> = vec![
+            route("get", "/todos"),
+            route("delete", "/todos/{id}"),
+            route("post", "/users/login"),
+        ];
+        assert_eq!(missing_routes(js, &routes), vec!["POST /users/login".to_string()]);
+    }
+
+    #[test]
+    fn tests_write_frontend_files() {
+        let output_dir: PathBuf = env::temp_dir().join(format!("autumn_frontend_{}", std::process::id()));
+        let frontend_code: FrontendCode = FrontendCode {
+            html: "<!DOCTYPE html>".to_string(),
+            css: "body {}".to_string(),
+            js: "const API_BASE_URL = window.API_BASE_URL;".to_string(),
+        };
+
+        write_frontend_files(&output_dir, &frontend_code).unwrap();
+        assert_eq!(fs::read_to_string(output_dir.join("index.html")).unwrap(), frontend_code.html);
+        assert_eq!(fs::read_to_string(output_dir.join("app.js")).unwrap(), frontend_code.js);
+    }
+}
@@ -0,0 +1 @@
+pub mod frontend_agent;
@@ -0,0 +1,156 @@
+use crate::agents::agent_architect::architect_agent::ArchitectAgent;
+use crate::agents::agent_backend::backend_agent::BackendAgent;
+use crate::agents::agent_frontend::frontend_agent::FrontendAgent;
+use crate::agents::agent_manager::workflow_config::AgentConfig;
+use crate::agents::agent_plugin::plugin_agent::{load_plugins, PluginAgent, PluginModule};
+use crate::agents::base::agent_traits::SpecialFunctions;
+use crate::utils::language::ProjectLanguage;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+// What a factory gets to know about the run besides the config of its agent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AgentContext {
+    pub language: ProjectLanguage,
+}
+
+// Creates an agent from its entry in the workflow config
+pub type AgentFactory = dyn Fn(&AgentConfig, &AgentContext) -> Result<Box<dyn SpecialFunctions>, String> + Send + Sync;
+
+// Agents a workflow config can name. Custom agents are registered next to the built-in ones, e.g.
+//
+//   let mut registry: AgentRegistry = AgentRegistry::with_builtin_agents();
+//   registry.register("qa", create_qa_agent);
+#[derive(Clone, Default)]
+pub struct AgentRegistry {
+    factories: BTreeMap<String, Arc<AgentFactory>>,
+}
+
+impl fmt::Debug for AgentRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AgentRegistry").field("agents", &self.names()).finish()
+    }
+}
+
+impl AgentRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Registry with the architect, backend and frontend agents
+    pub fn with_builtin_agents() -> Self {
+        let mut registry: Self = Self::new();
+        registry.register("architect", create_architect);
+        registry.register("backend", create_backend);
+        registry.register("frontend", create_frontend);
+        registry
+    }
+
+    // Registers a factory under a name, replacing an agent registered with the same name
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&AgentConfig, &AgentContext) -> Result<Box<dyn SpecialFunctions>, String> + Send + Sync + 'static
+    ) {
+        self.factories.insert(name.to_string(), Arc::new(factory));
+    }
+
+    // Registers a WebAssembly plugin under the name in its manifest
+    pub fn register_plugin(&mut self, plugin: PluginModule) {
+        let name: String = plugin.manifest.name.clone();
+        self.register(&name, move |config, _context| Ok(Box::new(PluginAgent::new(plugin.clone(), config))));
+    }
+
+    // Registers every plugin of the directory, see `plugins_dir`, and returns their names. A
+    // plugin may not take the name of an agent registered before it.
+    pub fn load_plugins(&mut self, dir: &Path) -> Result<Vec<String>, String> {
+        let mut names: Vec<String> = Vec::new();
+        for plugin in load_plugins(dir)? {
+            let name: String = plugin.manifest.name.clone();
+            if self.factories.contains_key(&name) {
+                return Err(format!(
+                    "Plugin {} is named {:?}, which is already a registered agent",
+                    plugin.path.display(),
+                    name
+                ));
+            }
+            self.register_plugin(plugin);
+            names.push(name);
+        }
+        Ok(names)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.factories.keys().map(String::as_str).collect()
+    }
+
+    pub fn create(&self, config: &AgentConfig, context: &AgentContext) -> Result<Box<dyn SpecialFunctions>, String> {
+        match self.factories.get(&config.agent) {
+            Some(factory) => factory(config, context),
+            None => Err(format!(
+                "Unknown agent {:?} in the workflow, registered agents are {}",
+                config.agent,
+                self.names().join(", ")
+            )),
+        }
+    }
+}
+
+fn create_architect(config: &AgentConfig, _context: &AgentContext) -> Result<Box<dyn SpecialFunctions>, String> {
+    Ok(Box::new(ArchitectAgent::new(
+        config.objective_or("Gathers information and design solutions for website development"),
+        config.position_or("Solutions Architect"),
+    )))
+}
+
+// Settings: `language` overrides the language of the run, `max_bug_fix_attempts` overrides
+// MAX_BUG_FIX_ATTEMPTS
+fn create_backend(config: &AgentConfig, context: &AgentContext) -> Result<Box<dyn SpecialFunctions>, String> {
+    let language: ProjectLanguage = match config.setting::<String>("language")? {
+        Some(language) => language.parse()?,
+        None => context.language,
+    };
+    let mut backend: BackendAgent = BackendAgent::new(
+        config.objective_or(&format!("Develops the {} backend code of the website", language)),
+        config.position_or("Backend Developer"),
+        language,
+    );
+    if let Some(attempts) = config.setting::<u8>("max_bug_fix_attempts")? {
+        backend = backend.with_max_bug_fix_attempts(attempts);
+    }
+    Ok(Box::new(backend))
+}
+
+fn create_frontend(config: &AgentConfig, _context: &AgentContext) -> Result<Box<dyn SpecialFunctions>, String> {
+    Ok(Box::new(FrontendAgent::new(
+        config.objective_or("Builds the static client of the website calling the backend routes"),
+        config.position_or("Frontend Developer"),
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn tests_create_registered_agents() {
+        let registry: AgentRegistry = AgentRegistry::with_builtin_agents();
+        let context: AgentContext = AgentContext { language: ProjectLanguage::Rust };
+
+        let mut backend: AgentConfig = AgentConfig::new("backend");
+        backend.position = Some("API Developer".to_string());
+        backend.settings.insert("language".to_string(), json!("python"));
+        let agent: Box<dyn SpecialFunctions> = registry.create(&backend, &context).unwrap();
+        assert_eq!(agent.get_attributes_from_agent().position, "API Developer");
+        assert_eq!(agent.get_attributes_from_agent().objective, "Develops the Python backend code of the website");
+
+        backend.settings.insert("max_bug_fix_attempts".to_string(), json!("many"));
+        assert!(registry.create(&backend, &context).unwrap_err().starts_with("Invalid setting max_bug_fix_attempts"));
+
+        let err: String = registry.create(&AgentConfig::new("qa"), &context).unwrap_err();
+        assert_eq!(err, "Unknown agent \"qa\" in the workflow, registered agents are architect, backend, frontend");
+    }
+}
@@ -0,0 +1,152 @@
+use crate::agents::agent_manager::manager_agent::ProjectStack;
+use crate::agents::agent_manager::workflow_config::WorkflowConfig;
+use crate::agents::base::agent_traits::{ProjectSpec, SpecField};
+use crate::utils::language::ProjectLanguage;
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CHECKPOINT_FILE: &str = "checkpoint.json";
+
+// The CLI answers a run was started with
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RunInfo {
+    pub run_id: String,
+    pub user_req: String,
+    pub language: ProjectLanguage,
+    pub stack: ProjectStack,
+    // Agents of the run, the ones of the stack when missing
+    #[serde(default)]
+    pub workflow: Option<WorkflowConfig>,
+}
+
+// A ProjectSpec with the prompt versions, which its own serialization leaves out
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpecCheckpoint {
+    pub spec: ProjectSpec,
+    pub prompt_versions: BTreeMap<String, String>,
+}
+
+impl SpecCheckpoint {
+    pub fn new(project_spec: &ProjectSpec) -> Self {
+        Self {
+            spec: ProjectSpec {
+                publisher: None,
+                ..project_spec.clone()
+            },
+            prompt_versions: project_spec.prompt_versions.clone(),
+        }
+    }
+
+    pub fn into_spec(self) -> ProjectSpec {
+        ProjectSpec {
+            prompt_versions: self.prompt_versions,
+            ..self.spec
+        }
+    }
+}
+
+// An agent that has started, with its own state and its working copy of the spec
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentCheckpoint {
+    pub position: String,
+    pub agent: Value,
+    pub project_spec: SpecCheckpoint,
+}
+
+// Everything needed to continue a run, written to <RUNS_DIR>/<run-id>/checkpoint.json after
+// every state transition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub run: RunInfo,
+    // Spec with the fields released by the agents so far
+    pub project_spec: SpecCheckpoint,
+    // (agent index, field) pairs released to the waiting agents
+    pub released: Vec<(usize, SpecField)>,
+    // One entry per registered agent, None until it has started
+    pub agents: Vec<Option<AgentCheckpoint>>,
+}
+
+impl Checkpoint {
+    pub fn new(run: RunInfo, project_spec: &ProjectSpec, agent_count: usize) -> Self {
+        Self {
+            run,
+            project_spec: SpecCheckpoint::new(project_spec),
+            released: Vec::new(),
+            agents: vec![None; agent_count],
+        }
+    }
+}
+
+// Directory holding one directory per run, RUNS_DIR or `runs`
+pub fn runs_dir() -> PathBuf {
+    dotenv().ok();
+    PathBuf::from(env::var("RUNS_DIR").unwrap_or_else(|_| "runs".to_string()))
+}
+
+pub fn new_run_id() -> String {
+    let started: u128 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    format!("run-{}", started)
+}
+
+// Writes to a temporary file first, so a crash while writing keeps the previous checkpoint
+pub fn save_checkpoint(runs_dir: &Path, checkpoint: &Checkpoint) -> io::Result<PathBuf> {
+    let run_dir: PathBuf = runs_dir.join(&checkpoint.run.run_id);
+    fs::create_dir_all(&run_dir)?;
+
+    let path: PathBuf = run_dir.join(CHECKPOINT_FILE);
+    let tmp_path: PathBuf = run_dir.join(format!("{}.tmp", CHECKPOINT_FILE));
+    fs::write(&tmp_path, serde_json::to_string_pretty(checkpoint)?)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(path)
+}
+
+pub fn load_checkpoint(runs_dir: &Path, run_id: &str) -> Result<Checkpoint, String> {
+    let path: PathBuf = runs_dir.join(run_id).join(CHECKPOINT_FILE);
+    let content: String = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read the checkpoint {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Invalid checkpoint {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_save_and_load_checkpoint() {
+        let runs_dir: PathBuf = env::temp_dir().join(format!("autumn_runs_{}", std::process::id()));
+        let mut project_spec: ProjectSpec = ProjectSpec::new(Some("build a todo app".to_string()), None, None, None, None, None);
+        project_spec.record_prompt_version("project_description", "convert_user_input_to_goal@2".to_string());
+
+        let run: RunInfo = RunInfo {
+            run_id: new_run_id(),
+            user_req: "a todo app".to_string(),
+            language: ProjectLanguage::Python,
+            stack: ProjectStack::Fullstack,
+            workflow: Some(WorkflowConfig::for_stack(ProjectStack::Fullstack)),
+        };
+        let mut checkpoint: Checkpoint = Checkpoint::new(run.clone(), &project_spec, 2);
+        checkpoint.released.push((0, SpecField::ProjectScope));
+        save_checkpoint(&runs_dir, &checkpoint).unwrap();
+
+        let loaded: Checkpoint = load_checkpoint(&runs_dir, &run.run_id).unwrap();
+        assert_eq!(loaded.run, run);
+        assert_eq!(loaded.released, vec![(0, SpecField::ProjectScope)]);
+        assert_eq!(loaded.agents.len(), 2);
+
+        let spec: ProjectSpec = loaded.project_spec.into_spec();
+        assert_eq!(spec.project_description.as_deref(), Some("build a todo app"));
+        assert_eq!(spec.prompt_versions["project_description"], "convert_user_input_to_goal@2");
+
+        assert!(load_checkpoint(&runs_dir, "run-missing").unwrap_err().starts_with("Could not read the checkpoint"));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::agents::base::agent_error::AgentError;
+use crate::utils::command_line::normalize_answer;
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+
+// Extra runs of a retried or re-planned agent when the policy gives no number
+const DEFAULT_EXTRA_ATTEMPTS: u8 = 1;
+
+// What the manager does when an agent fails. Written as in the environment, e.g. "retry:2", in
+// workflow configs and checkpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FailurePolicy {
+    // Runs the agent again from the start, up to the given number of times
+    Retry(u8),
+    // Goes on without the agent, the agents reading its fields work with what it left behind
+    Skip,
+    // Stops the workflow, the agents that have not started yet are not run
+    Abort,
+    // Runs the agent again from the start with the error added to its project description, so
+    // the LLM can avoid it, up to the given number of times
+    Replan(u8),
+}
+
+impl FailurePolicy {
+    // Policy of an agent from <POSITION>_FAILURE_POLICY, e.g. BACKEND_DEVELOPER_FAILURE_POLICY,
+    // falling back to FAILURE_POLICY and then to skipping the agent
+    pub fn for_agent(position: &str) -> Result<Self, String> {
+        dotenv().ok();
+        let agent_key: String = policy_env_key(position);
+        match env::var(&agent_key).or_else(|_| env::var("FAILURE_POLICY")) {
+            Ok(policy) => policy.parse(),
+            Err(_) => Ok(Self::Skip),
+        }
+    }
+
+    // Times the agent may run again after a failure
+    pub fn extra_attempts(&self) -> u8 {
+        match self {
+            Self::Retry(attempts) | Self::Replan(attempts) => *attempts,
+            Self::Skip | Self::Abort => 0,
+        }
+    }
+}
+
+impl FromStr for FailurePolicy {
+    type Err = String;
+
+    // Accepts `retry`, `retry:3`, `skip`, `abort`, `replan` and `replan:2`
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let normalized: String = normalize_answer(input);
+        let (name, attempts): (&str, Option<&str>) = match normalized.split_once(':') {
+            Some((name, attempts)) => (name.trim(), Some(attempts.trim())),
+            None => (normalized.as_str(), None),
+        };
+        let attempts: u8 = match attempts {
+            Some(attempts) => attempts
+                .parse()
+                .map_err(|_| format!("Invalid number of attempts {:?} in failure policy {:?}", attempts, input.trim()))?,
+            None => DEFAULT_EXTRA_ATTEMPTS,
+        };
+
+        match (name, normalized.contains(':')) {
+            ("retry", _) => Ok(Self::Retry(attempts)),
+            ("replan", _) => Ok(Self::Replan(attempts)),
+            ("skip", false) => Ok(Self::Skip),
+            ("abort", false) => Ok(Self::Abort),
+            _ => Err(format!(
+                "Unknown failure policy {:?}, choose one of retry[:attempts], skip, abort or replan[:attempts]",
+                input.trim()
+            )),
+        }
+    }
+}
+
+impl fmt::Display for FailurePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Retry(attempts) => write!(f, "retry:{}", attempts),
+            Self::Skip => write!(f, "skip"),
+            Self::Abort => write!(f, "abort"),
+            Self::Replan(attempts) => write!(f, "replan:{}", attempts),
+        }
+    }
+}
+
+impl TryFrom<String> for FailurePolicy {
+    type Error = String;
+
+    fn try_from(policy: String) -> Result<Self, Self::Error> {
+        policy.parse()
+    }
+}
+
+impl From<FailurePolicy> for String {
+    fn from(policy: FailurePolicy) -> Self {
+        policy.to_string()
+    }
+}
+
+// "Backend Developer" reads BACKEND_DEVELOPER_FAILURE_POLICY
+fn policy_env_key(position: &str) -> String {
+    let name: String = position
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+        .collect();
+    format!("{}_FAILURE_POLICY", name)
+}
+
+// Project description a re-planned agent works from, telling the LLM what went wrong last time
+pub fn replan_description(project_description: Option<&str>, error: &AgentError) -> String {
+    format!(
+        "{}\n\nA previous attempt at this step failed, avoid repeating the problem: {}",
+        project_description.unwrap_or_default(),
+        error
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parse_failure_policy() {
+        assert_eq!("retry".parse::<FailurePolicy>(), Ok(FailurePolicy::Retry(1)));
+        assert_eq!(" Replan:3 ".parse::<FailurePolicy>(), Ok(FailurePolicy::Replan(3)));
+        assert_eq!("[abort]".parse::<FailurePolicy>(), Ok(FailurePolicy::Abort));
+        assert!("skip:2".parse::<FailurePolicy>().is_err());
+        assert!("retry:many".parse::<FailurePolicy>().is_err());
+        assert!("ignore".parse::<FailurePolicy>().is_err());
+        assert_eq!(FailurePolicy::Retry(2).to_string(), "retry:2");
+        assert_eq!(policy_env_key("Backend Developer"), "BACKEND_DEVELOPER_FAILURE_POLICY");
+    }
+}
@@ -1,21 +1,97 @@
-use crate::agents::agent_architect::architect_agent::ArchitectAgent;
-use crate::agents::base::agent_base::{AgentAttributes, AgentState};
+use crate::agents::base::agent_base::AgentAttributes;
+use crate::agents::agent_manager::agent_registry::{AgentContext, AgentRegistry};
+use crate::agents::agent_manager::checkpoint::{load_checkpoint, new_run_id, runs_dir, save_checkpoint, Checkpoint, RunInfo};
+use crate::agents::agent_manager::failure_policy::FailurePolicy;
+use crate::agents::agent_manager::scheduler::{describe_schedule, plan_schedule, run_schedule, Checkpointing};
+use crate::agents::agent_manager::workflow_config::{AgentConfig, WorkflowConfig};
+use crate::agents::agent_plugin::plugin_agent::plugins_dir;
+use crate::agents::agent_manager::workflow_report::{AgentReport, WorkflowReport};
+use crate::agents::base::agent_error::AgentError;
 use crate::agents::base::agent_traits::{ProjectSpec, SpecialFunctions};
-use crate::utils::llm_apis::request_task_llm;
+use crate::events::subscribers::{TranscriptLogger, WorkflowMetrics};
+use crate::events::workflow_events::{emit, emit_message, subscribe, unsubscribe, SubscriptionId, WorkflowEvent};
+use crate::utils::llm_apis::request_task_llm_traced;
 use crate::ai_functions::ai_functions::convert_user_input_to_goal;
-use crate::utils::command_line::PrintMessage;
+use crate::ai_functions::prompt_library::{set_prompt_language, set_prompt_variable};
+use crate::utils::command_line::{normalize_answer, PrintMessage};
+use crate::utils::language::ProjectLanguage;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
+// Which parts of the website get built
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProjectStack {
+    Backend,
+    Frontend,
+    Fullstack,
+}
+
+impl FromStr for ProjectStack {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match normalize_answer(input).as_str() {
+            "backend" => Ok(Self::Backend),
+            "frontend" => Ok(Self::Frontend),
+            "fullstack" | "full stack" => Ok(Self::Fullstack),
+            _ => Err(format!("Unknown stack {:?}, choose one of backend, frontend or fullstack", input.trim())),
+        }
+    }
+}
+
+impl ProjectStack {
+    pub fn has_backend(&self) -> bool {
+        matches!(self, Self::Backend | Self::Fullstack)
+    }
+
+    pub fn has_frontend(&self) -> bool {
+        matches!(self, Self::Frontend | Self::Fullstack)
+    }
+}
 
 #[derive(Debug)]
 pub struct ManagerAgent {
     attributes: AgentAttributes,
     project_spec: ProjectSpec,
+    run_id: String,
+    user_req: String,
+    language: ProjectLanguage,
+    stack: ProjectStack,
+    workflow: WorkflowConfig,
     agents: Vec<Box<dyn SpecialFunctions>>, // list of agents manager is managing
+    // Failure policy of each agent, in the order of `agents`
+    policies: Vec<FailurePolicy>,
+    // Checkpoint of an interrupted run the workflow continues from
+    resume_from: Option<Checkpoint>,
 }
 
 impl ManagerAgent {
 
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+    // Runs the agents the stack needs, followed by the plugins of PLUGINS_DIR
+    pub fn new(
+        user_req: String,
+        language: ProjectLanguage,
+        stack: ProjectStack
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut registry: AgentRegistry = AgentRegistry::with_builtin_agents();
+        let plugins: Vec<String> = registry.load_plugins(&plugins_dir())?;
+        let mut workflow: WorkflowConfig = WorkflowConfig::for_stack(stack);
+        workflow.agents.extend(plugins.iter().map(|plugin| AgentConfig::new(plugin)));
+
+        Self::with_workflow(user_req, language, stack, workflow, &registry)
+    }
+
+    // Runs the agents of a workflow config, created by the registry, instead of the ones the
+    // stack needs
+    pub fn with_workflow(
+        user_req: String,
+        language: ProjectLanguage,
+        stack: ProjectStack,
+        workflow: WorkflowConfig,
+        registry: &AgentRegistry
+    ) -> Result<Self, Box<dyn std::error::Error>> {
         // Initializing manager agent attributes
         let attributes: AgentAttributes = AgentAttributes::new(
             "manage agents that are building the website for the end user".to_string(),
@@ -34,62 +110,250 @@ impl ManagerAgent {
 
         let agents: Vec<Box<dyn SpecialFunctions>> = vec![];
 
-        Ok(Self {
+        let mut manager: Self = Self {
             attributes,
             project_spec,
-            agents
-        })
+            run_id: new_run_id(),
+            user_req,
+            language,
+            stack,
+            workflow,
+            agents,
+            policies: Vec::new(),
+            resume_from: None
+        };
+        manager.register_agents(registry)?;
+        Ok(manager)
+    }
+
+    // Continues an interrupted run from its last checkpoint, with the agents of the original
+    // answers and workflow
+    pub fn resume(run_id: &str, registry: &AgentRegistry) -> Result<Self, Box<dyn std::error::Error>> {
+        let checkpoint: Checkpoint = load_checkpoint(&runs_dir(), run_id)?;
+        let run: RunInfo = checkpoint.run.clone();
+
+        let workflow: WorkflowConfig = run.workflow.unwrap_or_else(|| WorkflowConfig::for_stack(run.stack));
+        let mut manager: Self = Self::with_workflow(run.user_req, run.language, run.stack, workflow, registry)?;
+        if checkpoint.agents.len() != manager.agents.len() {
+            return Err(format!(
+                "The checkpoint of {} has {} agents, the workflow has {}",
+                run_id,
+                checkpoint.agents.len(),
+                manager.agents.len()
+            ).into());
+        }
+        manager.run_id = run.run_id;
+        manager.project_spec = checkpoint.project_spec.clone().into_spec();
+        manager.resume_from = Some(checkpoint);
+        Ok(manager)
+    }
+
+    fn run_info(&self) -> RunInfo {
+        RunInfo {
+            run_id: self.run_id.clone(),
+            user_req: self.user_req.clone(),
+            language: self.language,
+            stack: self.stack,
+            workflow: Some(self.workflow.clone()),
+        }
     }
 
     // Step 1. Generate a project description for Solutions Architect agent to interpret
-    pub async fn articulate_project_description(&mut self, user_req: String, agent_operation: &str) {
+    pub async fn articulate_project_description(&mut self, user_req: String, agent_operation: &str) -> Result<(), AgentError> {
 
-        let project_description: String = request_task_llm(
-            convert_user_input_to_goal, 
-            user_req,
-            &self.attributes.position,
-            get_function_string!(convert_user_input_to_goal)
-        ).await;
+        let (project_description, prompt_version): (String, String) = request_task_llm_traced(
+            convert_user_input_to_goal,
+            &user_req,
+            &self.attributes.position
+        ).await?;
         let agent_pos: String = self.attributes.position.clone();
 
-        PrintMessage::Info.print_agent_msg(&agent_pos, agent_operation);
+        emit_message(PrintMessage::Info, &agent_pos, agent_operation);
 
+        set_prompt_variable("project_description", &project_description);
         self.project_spec.project_description = Some(project_description);
+        self.project_spec.record_prompt_version("project_description", prompt_version);
+        Ok(())
+    }
 
+    // Creating the agents of the workflow in order, each with the failure policy of its config
+    // or else of the environment
+    fn register_agents(&mut self, registry: &AgentRegistry) -> Result<(), String> {
+        let context: AgentContext = AgentContext { language: self.language };
+        for config in &self.workflow.agents {
+            let agent: Box<dyn SpecialFunctions> = registry.create(config, &context)?;
+            let policy: FailurePolicy = match config.failure_policy {
+                Some(policy) => policy,
+                None => FailurePolicy::for_agent(&agent.get_attributes_from_agent().position)?,
+            };
+            self.agents.push(agent);
+            self.policies.push(policy);
+        }
+        Ok(())
     }
 
-    fn add_agent(&mut self, agent: Box<dyn SpecialFunctions>) {
-        self.agents.push(agent);
+    // Runs the agents and reports how each of them went. Fails only when there is no project
+    // description for the agents to work from. The events of the run are logged to
+    // runs/<run id>/transcript.jsonl.
+    pub async fn execute_workflow(&mut self) -> Result<WorkflowReport, AgentError> {
+        let agent_pos: String = self.attributes.position.clone();
+        let metrics: Arc<Mutex<WorkflowMetrics>> = Arc::default();
+        let mut subscriptions: Vec<SubscriptionId> = vec![subscribe(Arc::clone(&metrics))];
+        let transcript_path: PathBuf = runs_dir().join(&self.run_id).join("transcript.jsonl");
+        match TranscriptLogger::create(&transcript_path) {
+            Ok(transcript) => subscriptions.push(subscribe(transcript)),
+            Err(e) => emit_message(
+                PrintMessage::Error,
+                &agent_pos,
+                format!("Could not create the transcript {}: {}", transcript_path.display(), e)
+            ),
+        }
+
+        let result: Result<WorkflowReport, AgentError> = self.run_workflow().await;
+        if let Ok(report) = &result {
+            let summary: String = metrics.lock().expect("Metrics lock poisoned").summary();
+            emit_message(PrintMessage::Info, &agent_pos, summary);
+            emit(WorkflowEvent::WorkflowFinished {
+                run_id: report.run_id.clone(),
+                status: report.status(),
+                agents: report.summaries(),
+            });
+        }
+        for subscription in subscriptions {
+            unsubscribe(subscription);
+        }
+        result
     }
-    
-    pub async fn execute_workflow(&mut self) {
 
-        // Adding all the agents:
-        // 1. Solutions Architect
-        // 2. Backend Developer
-        // 3. Frontend Developer
-        self.add_agent(Box::new(
-            ArchitectAgent::new(
-                "Gathers information and design solutions for website development".to_owned(),
-                "Solutions Architect".to_owned()
-            ))
-        );
+    async fn run_workflow(&mut self) -> Result<WorkflowReport, AgentError> {
+        let agent_pos: String = self.attributes.position.clone();
+        set_prompt_language(self.language);
+
+        // A resumed run keeps the description it was started with
+        match self.project_spec.project_description.clone() {
+            Some(project_description) => {
+                emit_message(PrintMessage::Info, &agent_pos, format!("Resuming run {}", self.run_id));
+                set_prompt_variable("project_description", &project_description);
+            }
+            None => {
+                let user_req: String = self.user_req.clone();
+                self.articulate_project_description(user_req, "Converting user input to project description").await?;
+            }
+        }
 
-        for agent in &mut self.agents {
-            // Execute agents workflow
-            let _agent_res: Result<(), Box<dyn std::error::Error>> = agent.execute(&mut self.project_spec).await;
+        let runs_dir: PathBuf = runs_dir();
+        let checkpoint: Checkpoint = self
+            .resume_from
+            .take()
+            .unwrap_or_else(|| Checkpoint::new(self.run_info(), &self.project_spec, self.agents.len()));
+        match save_checkpoint(&runs_dir, &checkpoint) {
+            Ok(_) => emit_message(
+                PrintMessage::Info,
+                &agent_pos,
+                format!("Checkpointing run {}, continue it with `autumn resume {}` if it is interrupted", self.run_id, self.run_id)
+            ),
+            Err(e) => emit_message(PrintMessage::Error, &agent_pos, format!("Could not save the checkpoint: {}", e)),
         }
+
+        emit_message(PrintMessage::Info, &agent_pos, "Planning the workflow, agents without dependencies run concurrently:");
+        for step in describe_schedule(&plan_schedule(&self.agents)) {
+            emit_message(PrintMessage::Info, &agent_pos, step);
+        }
+
+        // Execute agents workflow
+        let project_spec: ProjectSpec = std::mem::replace(
+            &mut self.project_spec,
+            ProjectSpec::new(None, None, None, None, None, None)
+        );
+        let checkpointing: Checkpointing = Checkpointing { runs_dir, checkpoint };
+        let (project_spec, agents): (ProjectSpec, Vec<AgentReport>) =
+            run_schedule(&mut self.agents, &self.policies, project_spec, Some(checkpointing)).await;
+        self.project_spec = project_spec;
+
+        Ok(WorkflowReport {
+            run_id: self.run_id.clone(),
+            agents,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ai_functions::ai_function::AiFunction;
+
+    fn agent_positions(manager: &ManagerAgent) -> Vec<&str> {
+        manager
+            .agents
+            .iter()
+            .map(|agent| agent.get_attributes_from_agent().position.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn tests_parse_stack() {
+        assert_eq!("[fullstack]\n".parse::<ProjectStack>(), Ok(ProjectStack::Fullstack));
+        assert_eq!(" Backend ".parse::<ProjectStack>(), Ok(ProjectStack::Backend));
+        assert!("mobile".parse::<ProjectStack>().is_err());
+    }
+
+    #[test]
+    fn tests_registers_agents_for_stack() {
+        let user_req: String = "Create a simple todo app".to_string();
+
+        let fullstack = ManagerAgent::new(user_req.clone(), ProjectLanguage::Rust, ProjectStack::Fullstack).unwrap();
+        assert_eq!(agent_positions(&fullstack), vec!["Solutions Architect", "Backend Developer", "Frontend Developer"]);
+
+        let backend = ManagerAgent::new(user_req.clone(), ProjectLanguage::Rust, ProjectStack::Backend).unwrap();
+        assert_eq!(agent_positions(&backend), vec!["Solutions Architect", "Backend Developer"]);
+
+        let frontend = ManagerAgent::new(user_req.clone(), ProjectLanguage::Go, ProjectStack::Frontend).unwrap();
+        assert_eq!(agent_positions(&frontend), vec!["Solutions Architect", "Frontend Developer"]);
+
+        let python = ManagerAgent::new(user_req, ProjectLanguage::Python, ProjectStack::Fullstack).unwrap();
+        assert_eq!(agent_positions(&python), vec!["Solutions Architect", "Backend Developer", "Frontend Developer"]);
+    }
+
+    #[test]
+    fn tests_registers_agents_of_workflow() {
+        use crate::agents::agent_frontend::frontend_agent::FrontendAgent;
+        use crate::agents::agent_manager::workflow_config::AgentConfig;
+
+        // A custom pipeline: architect, backend, then a QA agent registered outside the manager
+        let mut registry: AgentRegistry = AgentRegistry::with_builtin_agents();
+        registry.register("qa", |config, _| {
+            Ok(Box::new(FrontendAgent::new(config.objective_or("Reviews the website"), config.position_or("QA Engineer"))))
+        });
+        let mut qa: AgentConfig = AgentConfig::new("qa");
+        qa.failure_policy = Some(FailurePolicy::Abort);
+        let workflow: WorkflowConfig = WorkflowConfig {
+            agents: vec![AgentConfig::new("architect"), AgentConfig::new("backend"), qa],
+        };
+
+        let manager = ManagerAgent::with_workflow(
+            "Create a simple todo app".to_string(),
+            ProjectLanguage::Go,
+            ProjectStack::Backend,
+            workflow,
+            &registry
+        ).unwrap();
+        assert_eq!(agent_positions(&manager), vec!["Solutions Architect", "Backend Developer", "QA Engineer"]);
+        assert_eq!(manager.policies[2], FailurePolicy::Abort);
+
+        let unknown: WorkflowConfig = WorkflowConfig { agents: vec![AgentConfig::new("qa")] };
+        let err = ManagerAgent::with_workflow("todo".to_string(), ProjectLanguage::Go, ProjectStack::Backend, unknown, &AgentRegistry::with_builtin_agents())
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Unknown agent \"qa\""));
+    }
 
     #[tokio::test]
     async fn tests_creating_managing_agent() {
-        let mut managing_agent = ManagerAgent::new().unwrap();
-        managing_agent.articulate_project_description("Create a simple todo app".to_string(), get_function_string!(convert_user_input_to_goal)).await;
+        let mut managing_agent = ManagerAgent::new(
+            "Create a simple todo app".to_string(),
+            ProjectLanguage::Rust,
+            ProjectStack::Fullstack
+        ).unwrap();
+        managing_agent.articulate_project_description("Create a simple todo app".to_string(), convert_user_input_to_goal.name()).await.unwrap();
         dbg!(managing_agent);
     }
 }
\ No newline at end of file
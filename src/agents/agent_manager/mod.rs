@@ -1 +1,7 @@
+pub mod agent_registry;
+pub mod checkpoint;
+pub mod failure_policy;
 pub mod manager_agent;
+pub mod scheduler;
+pub mod workflow_config;
+pub mod workflow_report;
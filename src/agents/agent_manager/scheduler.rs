@@ -0,0 +1,659 @@
+use crate::agents::agent_manager::checkpoint::{save_checkpoint, AgentCheckpoint, Checkpoint, SpecCheckpoint};
+use crate::agents::agent_manager::failure_policy::{replan_description, FailurePolicy};
+use crate::agents::agent_manager::workflow_report::{AgentOutcome, AgentReport};
+use crate::agents::base::agent_base::AgentState;
+use crate::agents::base::agent_error::AgentError;
+use crate::agents::base::agent_traits::{ProjectSpec, SpecField, SpecialFunctions};
+use crate::events::workflow_events::{emit, emit_message, WorkflowEvent};
+use crate::utils::command_line::PrintMessage;
+use futures::future::join_all;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, Mutex as AsyncMutex};
+
+// An agent of the workflow and the earlier agents it has to wait for
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledAgent {
+    pub position: String,
+    // (index of the writing agent, field) for every field the agent reads
+    pub waits_for: Vec<(usize, SpecField)>,
+}
+
+// Checkpoint of a run and the directory of runs it is written to
+#[derive(Debug)]
+pub struct Checkpointing {
+    pub runs_dir: PathBuf,
+    pub checkpoint: Checkpoint,
+}
+
+// Spec shared by the concurrently running agents. Every agent works on its own copy; the fields
+// it writes are copied back once it publishes them or finishes, waking up the agents waiting
+// for them.
+struct SharedSpec {
+    spec: Mutex<ProjectSpec>,
+    // (agent index, field) pairs whose value is final enough for the agents reading it
+    released: watch::Sender<BTreeSet<(usize, SpecField)>>,
+    // Set once an agent with the abort policy gave up
+    aborted: AtomicBool,
+    checkpointing: Option<Mutex<Checkpointing>>,
+}
+
+impl SharedSpec {
+    fn release(&self, agent: usize, from: &ProjectSpec, fields: &[SpecField]) {
+        self.spec
+            .lock()
+            .expect("Project spec lock poisoned")
+            .copy_fields_from(from, fields);
+        self.released.send_modify(|released| {
+            released.extend(fields.iter().map(|field| (agent, *field)));
+        });
+        self.save_checkpoint(|_| {});
+    }
+
+    // Stops the agents still waiting for their fields from starting
+    fn abort(&self) {
+        self.aborted.store(true, Ordering::SeqCst);
+        self.released.send_modify(|_| {});
+    }
+
+    fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::SeqCst)
+    }
+
+    fn snapshot(&self) -> ProjectSpec {
+        self.spec.lock().expect("Project spec lock poisoned").clone()
+    }
+
+    // Applies the update and writes the checkpoint with the current spec and released fields.
+    // A checkpoint that cannot be written only costs the ability to resume, so the run goes on.
+    fn save_checkpoint(&self, update: impl FnOnce(&mut Checkpoint)) {
+        let Some(checkpointing) = &self.checkpointing else {
+            return;
+        };
+        let mut checkpointing = checkpointing.lock().expect("Checkpoint lock poisoned");
+        update(&mut checkpointing.checkpoint);
+        checkpointing.checkpoint.project_spec = SpecCheckpoint::new(&self.snapshot());
+        checkpointing.checkpoint.released = self.released.borrow().iter().copied().collect();
+
+        if let Err(e) = save_checkpoint(&checkpointing.runs_dir, &checkpointing.checkpoint) {
+            emit_message(PrintMessage::Error, "Project Manager", format!("Could not save the checkpoint: {}", e));
+        }
+    }
+}
+
+// Handle an agent uses to publish a field of its copy of the spec before it has finished
+#[derive(Clone)]
+pub struct SpecPublisher {
+    shared: Arc<SharedSpec>,
+    agent: usize,
+}
+
+impl SpecPublisher {
+    pub fn publish(&self, from: &ProjectSpec, field: SpecField) {
+        self.shared.release(self.agent, from, &[field]);
+    }
+
+    pub fn checkpoint(&self, from: &ProjectSpec, position: &str, agent: serde_json::Value) {
+        self.shared.save_checkpoint(|checkpoint| {
+            checkpoint.agents[self.agent] = Some(AgentCheckpoint {
+                position: position.to_string(),
+                agent,
+                project_spec: SpecCheckpoint::new(from),
+            });
+        });
+    }
+}
+
+impl fmt::Debug for SpecPublisher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpecPublisher").field("agent", &self.agent).finish()
+    }
+}
+
+// An agent waits for the closest earlier agent writing each field it reads. Fields no earlier
+// agent writes, e.g. the project description of the manager, are read as they are.
+pub fn plan_schedule(agents: &[Box<dyn SpecialFunctions>]) -> Vec<ScheduledAgent> {
+    agents
+        .iter()
+        .enumerate()
+        .map(|(index, agent)| {
+            let waits_for: Vec<(usize, SpecField)> = agent
+                .reads()
+                .into_iter()
+                .filter_map(|field| {
+                    (0..index)
+                        .rev()
+                        .find(|writer| agents[*writer].writes().contains(&field))
+                        .map(|writer| (writer, field))
+                })
+                .collect();
+
+            ScheduledAgent {
+                position: agent.get_attributes_from_agent().position.clone(),
+                waits_for,
+            }
+        })
+        .collect()
+}
+
+// One line per agent for the run log, e.g.
+//
+//   2. Backend Developer waits for Solutions Architect (project_scope, external_urls)
+pub fn describe_schedule(plan: &[ScheduledAgent]) -> Vec<String> {
+    plan.iter()
+        .enumerate()
+        .map(|(index, agent)| {
+            let mut writers: Vec<usize> = agent.waits_for.iter().map(|(writer, _)| *writer).collect();
+            writers.sort();
+            writers.dedup();
+            if writers.is_empty() {
+                return format!("{}. {} starts right away", index + 1, agent.position);
+            }
+
+            let waits: Vec<String> = writers
+                .iter()
+                .map(|writer| {
+                    let fields: Vec<&str> = agent
+                        .waits_for
+                        .iter()
+                        .filter(|(field_writer, _)| field_writer == writer)
+                        .map(|(_, field)| field.name())
+                        .collect();
+                    format!("{} ({})", plan[*writer].position, fields.join(", "))
+                })
+                .collect();
+            format!("{}. {} waits for {}", index + 1, agent.position, waits.join(" and "))
+        })
+        .collect()
+}
+
+// Runs the agents as soon as the fields they read are released, so independent agents run
+// concurrently. A failed agent is run again or given up on following its failure policy; the
+// fields of an agent that gave up are released too, the agents after it work with whatever it
+// left behind, unless its policy aborts the agents that have not started yet. Returns the merged
+// spec and a report per agent.
+//
+// With checkpointing, the agents the checkpoint has started continue from their saved state
+// and copy of the spec, finished agents are not run again.
+pub async fn run_schedule(
+    agents: &mut Vec<Box<dyn SpecialFunctions>>,
+    policies: &[FailurePolicy],
+    project_spec: ProjectSpec,
+    checkpointing: Option<Checkpointing>,
+) -> (ProjectSpec, Vec<AgentReport>) {
+    let plan: Vec<ScheduledAgent> = plan_schedule(agents);
+    let mut reports: Vec<AgentReport> = plan
+        .iter()
+        .zip(policies)
+        .map(|(scheduled, policy)| AgentReport {
+            position: scheduled.position.clone(),
+            policy: *policy,
+            outcome: AgentOutcome::NotRun,
+            attempts: 0,
+            errors: Vec::new(),
+        })
+        .collect();
+
+    let mut resumed_specs: Vec<Option<ProjectSpec>> = vec![None; agents.len()];
+    let mut released: BTreeSet<(usize, SpecField)> = BTreeSet::new();
+    if let Some(checkpointing) = &checkpointing {
+        let checkpoint: &Checkpoint = &checkpointing.checkpoint;
+        for (index, agent) in agents.iter_mut().enumerate() {
+            let Some(saved) = checkpoint.agents.get(index).cloned().flatten() else {
+                continue;
+            };
+            let position: String = agent.get_attributes_from_agent().position.clone();
+            let restored: Result<(), AgentError> = if saved.position != position {
+                Err(AgentError::Other(format!("The checkpoint has {} where the workflow has {}", saved.position, position)))
+            } else {
+                agent
+                    .restore_state(saved.agent)
+                    .map_err(|e| AgentError::Other(format!("Could not restore the checkpoint: {}", e)))
+            };
+            if let Err(e) = restored {
+                reports[index].outcome = AgentOutcome::Failed;
+                reports[index].errors.push(e);
+                return (project_spec, reports);
+            }
+            resumed_specs[index] = Some(saved.project_spec.into_spec());
+        }
+        released.extend(checkpoint.released.iter().copied());
+    }
+
+    let (released, _) = watch::channel(released);
+    let shared: Arc<SharedSpec> = Arc::new(SharedSpec {
+        spec: Mutex::new(project_spec),
+        released,
+        aborted: AtomicBool::new(false),
+        checkpointing: checkpointing.map(Mutex::new),
+    });
+
+    // Every agent runs in its own task, so an agent blocking its thread, e.g. on a build or the
+    // code review, does not stop the others. The agents are handed back once their tasks ended.
+    let writes: Vec<Vec<SpecField>> = agents.iter().map(|agent| agent.writes()).collect();
+    let slots: Vec<Arc<AsyncMutex<Box<dyn SpecialFunctions>>>> =
+        agents.drain(..).map(|agent| Arc::new(AsyncMutex::new(agent))).collect();
+    let runs = slots
+        .iter()
+        .zip(plan)
+        .zip(resumed_specs)
+        .zip(reports)
+        .enumerate()
+        .map(|(index, (((slot, scheduled), resumed_spec), report))| {
+            let shared: Arc<SharedSpec> = Arc::clone(&shared);
+            let position: String = report.position.clone();
+            let policy: FailurePolicy = report.policy;
+            let writes: Vec<SpecField> = writes[index].clone();
+            let task = tokio::spawn(run_agent(
+                Arc::clone(slot),
+                index,
+                scheduled,
+                resumed_spec,
+                report,
+                Arc::clone(&shared),
+            ));
+            async move {
+                match task.await {
+                    Ok(report) => report,
+                    // A panicking agent fails like one returning an error. Its fields are released
+                    // as they are so the agents waiting for them do not wait forever.
+                    Err(e) => {
+                        let error: AgentError = AgentError::Other(format!("The agent panicked: {}", e));
+                        emit_message(PrintMessage::Error, &position, error.to_string());
+                        shared.release(index, &shared.snapshot(), &writes);
+                        if policy == FailurePolicy::Abort {
+                            shared.abort();
+                        }
+                        AgentReport {
+                            position,
+                            policy,
+                            outcome: AgentOutcome::Failed,
+                            attempts: 1,
+                            errors: vec![error],
+                        }
+                    }
+                }
+            }
+        });
+    let reports: Vec<AgentReport> = join_all(runs).await;
+
+    for slot in slots {
+        let agent: AsyncMutex<Box<dyn SpecialFunctions>> = Arc::try_unwrap(slot)
+            .unwrap_or_else(|_| unreachable!("The agent tasks have ended"));
+        agents.push(agent.into_inner());
+    }
+
+    let project_spec: ProjectSpec = shared.snapshot();
+    (project_spec, reports)
+}
+
+async fn run_agent(
+    slot: Arc<AsyncMutex<Box<dyn SpecialFunctions>>>,
+    index: usize,
+    scheduled: ScheduledAgent,
+    resumed_spec: Option<ProjectSpec>,
+    mut report: AgentReport,
+    shared: Arc<SharedSpec>,
+) -> AgentReport {
+    let mut agent = slot.lock().await;
+    if agent.get_attributes_from_agent().state == AgentState::Finished {
+        if let Some(agent_spec) = &resumed_spec {
+            shared.release(index, agent_spec, &agent.writes());
+        }
+        report.outcome = AgentOutcome::Finished;
+        return report;
+    }
+
+    let mut released = shared.released.subscribe();
+    let _ = released
+        .wait_for(|released| {
+            shared.is_aborted()
+                || scheduled.waits_for.iter().all(|dependency| released.contains(dependency))
+        })
+        .await;
+    if shared.is_aborted() {
+        return report;
+    }
+
+    // A retried agent starts over from the state this run started it in
+    let initial_state: serde_json::Value = agent.save_state();
+    let mut agent_spec: ProjectSpec = resumed_spec.unwrap_or_else(|| shared.snapshot());
+    loop {
+        agent_spec.publisher = Some(SpecPublisher {
+            shared: Arc::clone(&shared),
+            agent: index,
+        });
+        report.attempts += 1;
+        emit(WorkflowEvent::AgentStarted { agent: report.position.clone(), attempt: report.attempts });
+        let error: AgentError = match agent.execute(&mut agent_spec).await {
+            Ok(()) => {
+                report.outcome = AgentOutcome::Finished;
+                break;
+            }
+            Err(e) => e,
+        };
+
+        let runs_again: bool = report.errors.len() < report.policy.extra_attempts() as usize
+            && !shared.is_aborted();
+        if !runs_again {
+            emit_message(PrintMessage::Error, &report.position, error.to_string());
+            report.outcome = AgentOutcome::Failed;
+            report.errors.push(error);
+            if report.policy == FailurePolicy::Abort {
+                shared.abort();
+            }
+            break;
+        }
+
+        emit_message(
+            PrintMessage::Error,
+            &report.position,
+            format!("{}, running again ({})", error, report.policy)
+        );
+        if let Err(e) = agent.restore_state(initial_state.clone()) {
+            report.outcome = AgentOutcome::Failed;
+            report.errors.push(error);
+            report.errors.push(AgentError::Other(format!("Could not reset the agent: {}", e)));
+            break;
+        }
+        agent_spec = shared.snapshot();
+        if let FailurePolicy::Replan(_) = report.policy {
+            let description: String = replan_description(agent_spec.project_description.as_deref(), &error);
+            agent_spec.project_description = Some(description);
+        }
+        report.errors.push(error);
+    }
+    shared.release(index, &agent_spec, &agent.writes());
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::agent_manager::checkpoint::{load_checkpoint, new_run_id, RunInfo};
+    use crate::agents::agent_manager::manager_agent::ProjectStack;
+    use crate::agents::base::agent_base::AgentAttributes;
+    use crate::agents::base::agent_traits::{BasicAgentTraits, ProjectScope};
+    use crate::utils::language::ProjectLanguage;
+    use async_trait::async_trait;
+    use std::time::Duration;
+
+    // Writes its fields after a delay, logging when it starts and ends
+    #[derive(Debug)]
+    struct TestAgent {
+        attributes: AgentAttributes,
+        reads: Vec<SpecField>,
+        writes: Vec<SpecField>,
+        delay_ms: u64,
+        publish_early: bool,
+        // Sleeps on its thread instead of awaiting, like a synchronous build
+        blocking: bool,
+        // Attempts that fail before one succeeds, kept across restored states
+        failures_left: u8,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl TestAgent {
+        fn new(position: &str, reads: &[SpecField], writes: &[SpecField], delay_ms: u64, log: &Arc<Mutex<Vec<String>>>) -> Self {
+            Self {
+                attributes: AgentAttributes::new("test".to_string(), position.to_string()),
+                reads: reads.to_vec(),
+                writes: writes.to_vec(),
+                delay_ms,
+                publish_early: false,
+                blocking: false,
+                failures_left: 0,
+                log: Arc::clone(log),
+            }
+        }
+
+        fn log(&self, event: &str) {
+            self.log.lock().unwrap().push(format!("{} {}", event, self.attributes.position));
+        }
+    }
+
+    #[async_trait]
+    impl SpecialFunctions for TestAgent {
+        fn get_attributes_from_agent(&self) -> &AgentAttributes {
+            &self.attributes
+        }
+
+        fn reads(&self) -> Vec<SpecField> {
+            self.reads.clone()
+        }
+
+        fn writes(&self) -> Vec<SpecField> {
+            self.writes.clone()
+        }
+
+        fn save_state(&self) -> serde_json::Value {
+            serde_json::to_value(&self.attributes).unwrap()
+        }
+
+        fn restore_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+            self.attributes = serde_json::from_value(state)?;
+            Ok(())
+        }
+
+        async fn execute(&mut self, proj_spec: &mut ProjectSpec) -> Result<(), AgentError> {
+            self.log("start");
+            if self.failures_left > 0 {
+                tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+                self.failures_left -= 1;
+                self.log(&format!("fail ({})", proj_spec.project_description.as_deref().unwrap_or_default()));
+                return Err(AgentError::Other("no project scope".to_string()));
+            }
+            for field in &self.writes {
+                match field {
+                    SpecField::ProjectScope => {
+                        proj_spec.project_scope = Some(ProjectScope {
+                            is_crud_required: true,
+                            is_user_login_and_logout: false,
+                            is_external_urls_required: false,
+                        })
+                    }
+                    SpecField::BackendCode => proj_spec.backend_code = Some("fn main() {}".to_string()),
+                    SpecField::ApiEndpointSchema => proj_spec.api_endpoint_schema = Some(Vec::new()),
+                    SpecField::FrontendCode => {
+                        // The frontend only gets the routes the backend released
+                        assert!(proj_spec.api_endpoint_schema.is_some());
+                    }
+                    _ => {}
+                }
+                if self.publish_early {
+                    proj_spec.publish(*field);
+                }
+            }
+            if self.blocking {
+                std::thread::sleep(Duration::from_millis(self.delay_ms));
+            } else {
+                tokio::time::sleep(Duration::from_millis(self.delay_ms)).await;
+            }
+            self.attributes.update_agent_state(AgentState::Finished);
+            proj_spec.checkpoint(&self.attributes.position, self.save_state());
+            self.log("end");
+            Ok(())
+        }
+    }
+
+    fn agents(log: &Arc<Mutex<Vec<String>>>) -> Vec<Box<dyn SpecialFunctions>> {
+        let mut backend: TestAgent = TestAgent::new(
+            "Backend",
+            &[SpecField::ProjectScope],
+            &[SpecField::BackendCode, SpecField::ApiEndpointSchema],
+            60,
+            log,
+        );
+        backend.publish_early = true;
+
+        vec![
+            Box::new(TestAgent::new("Architect", &[SpecField::ProjectDescription], &[SpecField::ProjectScope], 10, log)),
+            Box::new(TestAgent::new("Docs", &[SpecField::ProjectDescription], &[], 30, log)),
+            Box::new(backend),
+            Box::new(TestAgent::new("Frontend", &[SpecField::ApiEndpointSchema], &[SpecField::FrontendCode], 10, log)),
+        ]
+    }
+
+    #[test]
+    fn tests_plan_schedule() {
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let plan: Vec<ScheduledAgent> = plan_schedule(&agents(&log));
+
+        assert_eq!(plan[2].waits_for, vec![(0, SpecField::ProjectScope)]);
+        assert_eq!(plan[3].waits_for, vec![(2, SpecField::ApiEndpointSchema)]);
+        assert_eq!(
+            describe_schedule(&plan),
+            vec![
+                "1. Architect starts right away",
+                "2. Docs starts right away",
+                "3. Backend waits for Architect (project_scope)",
+                "4. Frontend waits for Backend (api_endpoint_schema)",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_run_schedule() {
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = agents(&log);
+        let spec: ProjectSpec = ProjectSpec::new(Some("build a todo app".to_string()), None, None, None, None, None);
+
+        let (spec, reports) = run_schedule(&mut agents, &[FailurePolicy::Skip; 4], spec, None).await;
+
+        assert!(reports.iter().all(|report| report.outcome == AgentOutcome::Finished && report.attempts == 1));
+        assert!(spec.project_scope.is_some());
+        assert_eq!(spec.backend_code.as_deref(), Some("fn main() {}"));
+        assert_eq!(spec.project_description.as_deref(), Some("build a todo app"));
+        assert!(spec.publisher.is_none());
+
+        // Architect and Docs are independent, the Frontend starts once the Backend released its
+        // routes instead of waiting for it to finish
+        let log: Vec<String> = log.lock().unwrap().clone();
+        let position = |event: &str| log.iter().position(|entry| entry == event).unwrap();
+        assert!(position("start Docs") < position("end Architect"));
+        assert!(position("end Architect") < position("start Backend"));
+        assert!(position("start Frontend") < position("end Backend"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn tests_run_schedule_blocking_agent() {
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let mut architect: TestAgent =
+            TestAgent::new("Architect", &[SpecField::ProjectDescription], &[SpecField::ProjectScope], 500, &log);
+        architect.blocking = true;
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = vec![
+            Box::new(architect),
+            Box::new(TestAgent::new("Docs", &[SpecField::ProjectDescription], &[], 10, &log)),
+        ];
+        let spec: ProjectSpec = ProjectSpec::new(Some("build a todo app".to_string()), None, None, None, None, None);
+
+        let (_, reports) = run_schedule(&mut agents, &[FailurePolicy::Skip; 2], spec, None).await;
+
+        assert!(reports.iter().all(|report| report.outcome == AgentOutcome::Finished));
+        // The agents are handed back in their order
+        assert_eq!(agents[0].get_attributes_from_agent().position, "Architect");
+        assert_eq!(agents[0].get_attributes_from_agent().state, AgentState::Finished);
+        let log: Vec<String> = log.lock().unwrap().clone();
+        let position = |event: &str| log.iter().position(|entry| entry == event).unwrap();
+        assert!(position("end Docs") < position("end Architect"));
+    }
+
+    #[tokio::test]
+    async fn tests_run_schedule_resumes_from_checkpoint() {
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = agents(&log);
+        let runs_dir: PathBuf = std::env::temp_dir().join(format!("autumn_resume_{}", std::process::id()));
+        let spec: ProjectSpec = ProjectSpec::new(Some("build a todo app".to_string()), None, None, None, None, None);
+
+        // The Architect finished before the run was interrupted
+        let mut architect_spec: ProjectSpec = spec.clone();
+        architect_spec.project_scope = Some(ProjectScope {
+            is_crud_required: false,
+            is_user_login_and_logout: true,
+            is_external_urls_required: false,
+        });
+        let mut architect: AgentAttributes = AgentAttributes::new("test".to_string(), "Architect".to_string());
+        architect.update_agent_state(AgentState::Finished);
+
+        let run: RunInfo = RunInfo {
+            run_id: new_run_id(),
+            user_req: "a todo app".to_string(),
+            language: ProjectLanguage::Rust,
+            stack: ProjectStack::Fullstack,
+            workflow: None,
+        };
+        let mut checkpoint: Checkpoint = Checkpoint::new(run.clone(), &spec, agents.len());
+        checkpoint.agents[0] = Some(AgentCheckpoint {
+            position: "Architect".to_string(),
+            agent: serde_json::to_value(&architect).unwrap(),
+            project_spec: SpecCheckpoint::new(&architect_spec),
+        });
+        let checkpointing: Checkpointing = Checkpointing { runs_dir: runs_dir.clone(), checkpoint };
+
+        let (spec, reports) = run_schedule(&mut agents, &[FailurePolicy::Skip; 4], spec, Some(checkpointing)).await;
+
+        assert!(reports.iter().all(|report| report.outcome == AgentOutcome::Finished));
+        assert_eq!(reports[0].attempts, 0);
+        assert!(!log.lock().unwrap().contains(&"start Architect".to_string()));
+        assert!(spec.project_scope.unwrap().is_user_login_and_logout);
+        assert_eq!(spec.backend_code.as_deref(), Some("fn main() {}"));
+
+        // Every agent is saved as finished, a second resume has nothing left to do
+        let saved: Checkpoint = load_checkpoint(&runs_dir, &run.run_id).unwrap();
+        assert!(saved.agents.iter().all(|agent| agent.as_ref().unwrap().agent["state"] == "Finished"));
+        assert!(saved.released.contains(&(2, SpecField::ApiEndpointSchema)));
+        assert!(saved.project_spec.into_spec().backend_code.is_some());
+    }
+
+    fn failing_architect(log: &Arc<Mutex<Vec<String>>>, failures: u8) -> Vec<Box<dyn SpecialFunctions>> {
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = agents(log);
+        let mut architect: TestAgent =
+            TestAgent::new("Architect", &[SpecField::ProjectDescription], &[SpecField::ProjectScope], 10, log);
+        architect.failures_left = failures;
+        agents[0] = Box::new(architect);
+        agents
+    }
+
+    fn policies(architect: FailurePolicy) -> Vec<FailurePolicy> {
+        vec![architect, FailurePolicy::Skip, FailurePolicy::Skip, FailurePolicy::Skip]
+    }
+
+    #[tokio::test]
+    async fn tests_run_schedule_failure_policies() {
+        let spec: ProjectSpec = ProjectSpec::new(Some("build a todo app".to_string()), None, None, None, None, None);
+
+        // Retried until the attempts run out, then skipped; the Backend still runs
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = failing_architect(&log, 5);
+        let (skipped, reports) = run_schedule(&mut agents, &policies(FailurePolicy::Retry(2)), spec.clone(), None).await;
+        assert_eq!(reports[0].outcome, AgentOutcome::Failed);
+        assert_eq!(reports[0].attempts, 3);
+        assert_eq!(reports[0].errors.len(), 3);
+        assert_eq!(reports[2].outcome, AgentOutcome::Finished);
+        assert!(skipped.project_scope.is_none());
+
+        // Re-planned with the error in the project description
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = failing_architect(&log, 2);
+        let (replanned, reports) = run_schedule(&mut agents, &policies(FailurePolicy::Replan(2)), spec.clone(), None).await;
+        assert_eq!(reports[0].outcome, AgentOutcome::Finished);
+        assert!(replanned.project_scope.is_some());
+        assert_eq!(replanned.project_description.as_deref(), Some("build a todo app"));
+        let failures: Vec<String> = log.lock().unwrap().iter().filter(|entry| entry.starts_with("fail")).cloned().collect();
+        assert_eq!(failures[0], "fail (build a todo app) Architect");
+        assert!(failures[1].contains("A previous attempt at this step failed, avoid repeating the problem: no project scope"));
+
+        // Aborted, the agents waiting for the Architect never start while Docs, already running,
+        // finishes
+        let log: Arc<Mutex<Vec<String>>> = Arc::new(Mutex::new(Vec::new()));
+        let mut agents: Vec<Box<dyn SpecialFunctions>> = failing_architect(&log, 1);
+        let (_, reports) = run_schedule(&mut agents, &policies(FailurePolicy::Abort), spec, None).await;
+        let outcomes: Vec<AgentOutcome> = reports.iter().map(|report| report.outcome).collect();
+        assert_eq!(outcomes, vec![AgentOutcome::Failed, AgentOutcome::Finished, AgentOutcome::NotRun, AgentOutcome::NotRun]);
+        assert!(!log.lock().unwrap().contains(&"start Backend".to_string()));
+    }
+}
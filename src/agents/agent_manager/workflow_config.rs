@@ -0,0 +1,140 @@
+use crate::agents::agent_manager::failure_policy::FailurePolicy;
+use crate::agents::agent_manager::manager_agent::ProjectStack;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+// Agents of a workflow in the order they are registered, e.g. from a workflows/*.toml file:
+//
+//   [[agents]]
+//   agent = "backend"
+//   position = "Backend Developer"
+//   failure_policy = "replan:2"
+//   settings = { max_bug_fix_attempts = 5 }
+//
+// The manager still runs agents concurrently when they do not read each other's fields.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WorkflowConfig {
+    pub agents: Vec<AgentConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AgentConfig {
+    // Name the agent is registered with in the AgentRegistry
+    pub agent: String,
+    // Defaults of the registered agent when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub objective: Option<String>,
+    // Overrides <POSITION>_FAILURE_POLICY and FAILURE_POLICY
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure_policy: Option<FailurePolicy>,
+    // Agent specific settings, e.g. max_bug_fix_attempts of the backend agent
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub settings: Map<String, Value>,
+}
+
+impl AgentConfig {
+    pub fn new(agent: &str) -> Self {
+        Self {
+            agent: agent.to_string(),
+            position: None,
+            objective: None,
+            failure_policy: None,
+            settings: Map::new(),
+        }
+    }
+
+    pub fn position_or(&self, default: &str) -> String {
+        self.position.clone().unwrap_or_else(|| default.to_string())
+    }
+
+    pub fn objective_or(&self, default: &str) -> String {
+        self.objective.clone().unwrap_or_else(|| default.to_string())
+    }
+
+    // Reads a setting, None when it is not set
+    pub fn setting<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, String> {
+        match self.settings.get(key) {
+            Some(value) => serde_json::from_value(value.clone())
+                .map(Some)
+                .map_err(|e| format!("Invalid setting {} of the {} agent: {}", key, self.agent, e)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl WorkflowConfig {
+    // Workflow of the CLI answers: the architect, then the backend and frontend the stack needs
+    pub fn for_stack(stack: ProjectStack) -> Self {
+        let mut agents: Vec<AgentConfig> = vec![AgentConfig::new("architect")];
+        if stack.has_backend() {
+            agents.push(AgentConfig::new("backend"));
+        }
+        if stack.has_frontend() {
+            agents.push(AgentConfig::new("frontend"));
+        }
+        Self { agents }
+    }
+
+    // Reads a .toml, .yaml or .yml workflow file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content: String = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read the workflow config {}: {}", path.display(), e))?;
+        let extension: &str = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+
+        let config: Self = match extension {
+            "toml" => toml::from_str(&content).map_err(|e| e.to_string()),
+            "yaml" | "yml" => serde_yaml::from_str(&content).map_err(|e| e.to_string()),
+            _ => return Err(format!("Workflow config {} is not a .toml, .yaml or .yml file", path.display())),
+        }
+        .map_err(|e| format!("Invalid workflow config {}: {}", path.display(), e))?;
+
+        if config.agents.is_empty() {
+            return Err(format!("Workflow config {} has no agents", path.display()));
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn tests_load_workflow_config() {
+        let config: WorkflowConfig = WorkflowConfig::load(Path::new("workflows/fullstack.toml")).unwrap();
+        assert_eq!(
+            config.agents.iter().map(|agent| agent.agent.as_str()).collect::<Vec<&str>>(),
+            vec!["architect", "backend", "frontend"]
+        );
+        assert_eq!(config.agents[1].failure_policy, Some(FailurePolicy::Replan(2)));
+        assert_eq!(config.agents[1].setting::<u8>("max_bug_fix_attempts"), Ok(Some(5)));
+        assert_eq!(config.agents[1].setting::<u8>("language"), Ok(None));
+
+        // The same workflow as YAML
+        let dir: PathBuf = std::env::temp_dir().join(format!("autumn_workflow_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let yaml_path: PathBuf = dir.join("fullstack.yaml");
+        fs::write(&yaml_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(WorkflowConfig::load(&yaml_path).unwrap(), config);
+
+        let unknown_field: PathBuf = dir.join("typo.yml");
+        fs::write(&unknown_field, "agents:\n  - agent: backend\n    postion: QA\n").unwrap();
+        assert!(WorkflowConfig::load(&unknown_field).unwrap_err().contains("unknown field `postion`"));
+        assert!(WorkflowConfig::load(&dir.join("fullstack.json")).is_err());
+    }
+
+    #[test]
+    fn tests_workflow_for_stack() {
+        let backend: WorkflowConfig = WorkflowConfig::for_stack(ProjectStack::Backend);
+        assert_eq!(backend.agents, vec![AgentConfig::new("architect"), AgentConfig::new("backend")]);
+        assert_eq!(WorkflowConfig::for_stack(ProjectStack::Fullstack).agents.len(), 3);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::agents::agent_manager::failure_policy::FailurePolicy;
+use crate::agents::base::agent_error::AgentError;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentOutcome {
+    Finished,
+    // Failed on every attempt its policy allowed
+    Failed,
+    // Not started because an earlier failure aborted the workflow
+    NotRun,
+}
+
+// How one agent of the workflow went
+#[derive(Debug)]
+pub struct AgentReport {
+    pub position: String,
+    pub policy: FailurePolicy,
+    pub outcome: AgentOutcome,
+    // Times the agent ran, 0 for an agent a resumed run had already finished
+    pub attempts: u8,
+    // Error of every failed attempt, in order
+    pub errors: Vec<AgentError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkflowStatus {
+    Succeeded,
+    // Some agents failed and were skipped, the others finished
+    CompletedWithFailures,
+    // An agent with the abort policy failed
+    Aborted,
+}
+
+// An AgentReport as it is shown and logged
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AgentSummary {
+    pub position: String,
+    pub policy: FailurePolicy,
+    pub outcome: AgentOutcome,
+    pub attempts: u8,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct WorkflowReport {
+    pub run_id: String,
+    pub agents: Vec<AgentReport>,
+}
+
+impl WorkflowReport {
+    pub fn status(&self) -> WorkflowStatus {
+        let aborted: bool = self
+            .agents
+            .iter()
+            .any(|agent| agent.outcome == AgentOutcome::Failed && agent.policy == FailurePolicy::Abort);
+        if aborted {
+            WorkflowStatus::Aborted
+        } else if self.agents.iter().all(|agent| agent.outcome == AgentOutcome::Finished) {
+            WorkflowStatus::Succeeded
+        } else {
+            WorkflowStatus::CompletedWithFailures
+        }
+    }
+
+    // Outcome of every agent with its errors as text, for the WorkflowFinished event
+    pub fn summaries(&self) -> Vec<AgentSummary> {
+        self.agents
+            .iter()
+            .map(|agent| AgentSummary {
+                position: agent.position.clone(),
+                policy: agent.policy,
+                outcome: agent.outcome,
+                attempts: agent.attempts,
+                errors: agent.errors.iter().map(|error| error.to_string()).collect(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(outcome: AgentOutcome, policy: FailurePolicy) -> AgentReport {
+        AgentReport {
+            position: "Backend Developer".to_string(),
+            policy,
+            outcome,
+            attempts: 1,
+            errors: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn tests_workflow_status() {
+        let report = |agents: Vec<AgentReport>| WorkflowReport { run_id: "run-1".to_string(), agents }.status();
+
+        assert_eq!(report(vec![agent(AgentOutcome::Finished, FailurePolicy::Abort)]), WorkflowStatus::Succeeded);
+        assert_eq!(
+            report(vec![agent(AgentOutcome::Finished, FailurePolicy::Skip), agent(AgentOutcome::Failed, FailurePolicy::Retry(2))]),
+            WorkflowStatus::CompletedWithFailures
+        );
+        assert_eq!(
+            report(vec![agent(AgentOutcome::Failed, FailurePolicy::Abort), agent(AgentOutcome::NotRun, FailurePolicy::Skip)]),
+            WorkflowStatus::Aborted
+        );
+    }
+}
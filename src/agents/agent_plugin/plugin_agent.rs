@@ -0,0 +1,680 @@
+use async_trait::async_trait;
+use crate::{
+    agents::{
+        agent_manager::workflow_config::AgentConfig,
+        base::{
+            agent_base::{AgentAttributes, AgentState},
+            agent_error::AgentError,
+            agent_traits::{ProjectSpec, SpecField, SpecialFunctions},
+            state_machine::{run_state_machine, StateHandlers, StateMachine}
+        }
+    },
+    events::workflow_events::emit_message,
+    models::general::llm::Message,
+    utils::{command_line::PrintMessage, llm_apis::{call_gpt, observe_llm_call}}
+};
+use dotenv::dotenv;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{env, fmt, fs, path::{Path, PathBuf}, sync::Arc, time::Duration};
+use wasmi::{core::Trap, Caller, Config, Engine, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, TypedFunc};
+
+// Plugin agents are WebAssembly modules loaded from PLUGINS_DIR. They run in an embedded
+// interpreter without access to the file system, the network or the clock, only to the host
+// functions below, and every call into a plugin is bounded by fuel and a memory limit.
+//
+// A plugin exports its linear memory as `memory` and
+//
+//   autumn_alloc(len: i32) -> i32                    a buffer of `len` bytes for the host's input
+//   autumn_manifest() -> i64                         its PluginManifest as JSON
+//   autumn_handle_state(ptr: i32, len: i32) -> i64   handles the PluginInput in the buffer
+//
+// where an i64 result is a JSON buffer packed as `ptr << 32 | len`. autumn_handle_state answers
+// with the state to move to, {"next_state": "Working"}, or with a prompt for the LLM of the host,
+// {"llm_request": "..."}, after which it is called again for the same state with the answer in
+// `llm_response`.
+//
+// A plugin may import from the `autumn` module
+//
+//   spec_read(name_ptr, name_len, out_ptr, out_cap: i32) -> i32
+//       Writes the ProjectSpec field as JSON to the buffer when it fits and returns its length,
+//       -1 when the field is unknown or not in the reads or writes of the manifest
+//   spec_write(name_ptr, name_len, json_ptr, json_len: i32) -> i32
+//       0 when the field is written, -1 when it is unknown or not in the writes of the manifest,
+//       -2 when the JSON does not match the field
+//   log(ptr, len: i32)
+//       Prints a message as the agent
+
+// Fuel of every call into a plugin, about the number of instructions it may execute, unless
+// PLUGIN_FUEL_PER_CALL is set. Calls run on a blocking thread, so a plugin using all of it only
+// holds up its own agent.
+const DEFAULT_FUEL_PER_CALL: u64 = 1_000_000_000;
+// Linear memory a plugin may grow to
+const PLUGIN_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+// Prompts a plugin may send while handling one state
+const MAX_LLM_REQUESTS_PER_STATE: usize = 8;
+// A state may wait on several LLM answers
+const PLUGIN_STATE_TIMEOUT: Duration = Duration::from_secs(600);
+
+// Sends the prompts of plugins to the LLM of the host
+type LlmProvider = fn(Vec<Message>) -> BoxFuture<'static, Result<String, String>>;
+
+pub fn plugins_dir() -> PathBuf {
+    dotenv().ok();
+    PathBuf::from(env::var("PLUGINS_DIR").unwrap_or_else(|_| "plugins".to_string()))
+}
+
+// What a plugin tells the host about itself, e.g.
+//   {"name": "qa", "position": "QA Engineer", "objective": "Reviews the backend",
+//    "reads": ["backend_code"], "writes": ["api_endpoint_schema"], "max_states": 10}
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginManifest {
+    // Name workflow configs register the plugin with
+    pub name: String,
+    // Defaults of the agent when its workflow config leaves them out
+    pub position: String,
+    pub objective: String,
+    #[serde(default)]
+    pub reads: Vec<SpecField>,
+    #[serde(default)]
+    pub writes: Vec<SpecField>,
+    // States the plugin may handle before the runner gives up
+    #[serde(default = "default_max_states")]
+    pub max_states: usize,
+}
+
+fn default_max_states() -> usize {
+    10
+}
+
+// A compiled plugin, shared by every agent created from it
+#[derive(Debug, Clone)]
+pub struct PluginModule {
+    pub path: PathBuf,
+    pub manifest: PluginManifest,
+    engine: Engine,
+    module: Arc<Module>,
+    fuel_per_call: u64,
+}
+
+impl PluginModule {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let wasm: Vec<u8> = fs::read(path)
+            .map_err(|e| format!("Could not read the plugin {}: {}", path.display(), e))?;
+        Self::from_wasm(path, &wasm)
+    }
+
+    // Compiles the module and asks it for its manifest
+    pub fn from_wasm(path: &Path, wasm: &[u8]) -> Result<Self, String> {
+        let mut config: Config = Config::default();
+        config.consume_fuel(true);
+        let engine: Engine = Engine::new(&config);
+        let module: Module = Module::new(&engine, wasm)
+            .map_err(|e| format!("Invalid plugin {}: {}", path.display(), e))?;
+
+        let host: PluginHost = PluginHost::new(path.display().to_string(), Vec::new(), Vec::new());
+        let manifest: PluginManifest = PluginRuntime::new(&engine, &module, host, DEFAULT_FUEL_PER_CALL)
+            .and_then(|mut runtime| runtime.manifest())
+            .and_then(|manifest| serde_json::from_slice(&manifest).map_err(|e| format!("invalid manifest: {}", e)))
+            .map_err(|e| format!("Invalid plugin {}: {}", path.display(), e))?;
+        if manifest.name.trim().is_empty() {
+            return Err(format!("Invalid plugin {}: the manifest has no name", path.display()));
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            manifest,
+            engine,
+            module: Arc::new(module),
+            fuel_per_call: DEFAULT_FUEL_PER_CALL,
+        })
+    }
+
+    pub fn with_fuel_per_call(mut self, fuel_per_call: u64) -> Self {
+        self.fuel_per_call = fuel_per_call;
+        self
+    }
+}
+
+// Compiles every .wasm file of the directory in file name order. A missing directory has no plugins.
+pub fn load_plugins(dir: &Path) -> Result<Vec<PluginModule>, String> {
+    dotenv().ok();
+    let fuel_per_call: u64 = match env::var("PLUGIN_FUEL_PER_CALL") {
+        Ok(fuel) => fuel.parse().map_err(|_| format!("Invalid PLUGIN_FUEL_PER_CALL {:?}", fuel))?,
+        Err(_) => DEFAULT_FUEL_PER_CALL,
+    };
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Could not read the plugins directory {}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "wasm"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| PluginModule::load(path).map(|plugin| plugin.with_fuel_per_call(fuel_per_call)))
+        .collect()
+}
+
+// State the host functions work on
+struct PluginHost {
+    position: String,
+    reads: Vec<SpecField>,
+    writes: Vec<SpecField>,
+    // Copy of the project spec while the plugin handles a state
+    spec: ProjectSpec,
+    limits: StoreLimits,
+}
+
+impl PluginHost {
+    fn new(position: String, reads: Vec<SpecField>, writes: Vec<SpecField>) -> Self {
+        Self {
+            position,
+            reads,
+            writes,
+            spec: empty_spec(),
+            limits: StoreLimitsBuilder::new().memory_size(PLUGIN_MEMORY_LIMIT).build(),
+        }
+    }
+}
+
+fn empty_spec() -> ProjectSpec {
+    ProjectSpec::new(None, None, None, None, None, None)
+}
+
+// An instance of a plugin with the exports the host calls
+struct PluginRuntime {
+    store: Store<PluginHost>,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+    manifest: TypedFunc<(), i64>,
+    handle_state: TypedFunc<(i32, i32), i64>,
+    fuel_per_call: u64,
+}
+
+impl fmt::Debug for PluginRuntime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PluginRuntime").finish_non_exhaustive()
+    }
+}
+
+impl PluginRuntime {
+    fn new(engine: &Engine, module: &Module, host: PluginHost, fuel_per_call: u64) -> Result<Self, String> {
+        let mut store: Store<PluginHost> = Store::new(engine, host);
+        store.limiter(|host| &mut host.limits);
+        refuel(&mut store, fuel_per_call)?;
+
+        let mut linker: Linker<PluginHost> = Linker::new(engine);
+        define_host_functions(&mut linker).map_err(|e| e.to_string())?;
+        let instance = linker
+            .instantiate(&mut store, module)
+            .and_then(|instance| instance.start(&mut store))
+            .map_err(|e| format!("could not instantiate the plugin: {}", e))?;
+
+        let memory: Memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("the plugin does not export its memory")?;
+        let alloc: TypedFunc<i32, i32> = instance
+            .get_typed_func(&store, "autumn_alloc")
+            .map_err(|e| format!("the plugin does not export autumn_alloc(i32) -> i32: {}", e))?;
+        let manifest: TypedFunc<(), i64> = instance
+            .get_typed_func(&store, "autumn_manifest")
+            .map_err(|e| format!("the plugin does not export autumn_manifest() -> i64: {}", e))?;
+        let handle_state: TypedFunc<(i32, i32), i64> = instance
+            .get_typed_func(&store, "autumn_handle_state")
+            .map_err(|e| format!("the plugin does not export autumn_handle_state(i32, i32) -> i64: {}", e))?;
+
+        Ok(Self { store, memory, alloc, manifest, handle_state, fuel_per_call })
+    }
+
+    fn manifest(&mut self) -> Result<Vec<u8>, String> {
+        refuel(&mut self.store, self.fuel_per_call)?;
+        let packed: i64 = self.manifest.call(&mut self.store, ()).map_err(|e| e.to_string())?;
+        self.read_packed(packed)
+    }
+
+    // Copies the input into a buffer of the plugin and hands it to autumn_handle_state
+    fn handle_state(&mut self, input: &[u8]) -> Result<Vec<u8>, String> {
+        refuel(&mut self.store, self.fuel_per_call)?;
+        let len: i32 = i32::try_from(input.len()).map_err(|_| "the input does not fit the plugin memory")?;
+        let ptr: i32 = self.alloc.call(&mut self.store, len).map_err(|e| e.to_string())?;
+        self.memory
+            .write(&mut self.store, guest_offset(ptr), input)
+            .map_err(|e| format!("autumn_alloc returned an invalid buffer: {}", e))?;
+        let packed: i64 = self.handle_state.call(&mut self.store, (ptr, len)).map_err(|e| e.to_string())?;
+        self.read_packed(packed)
+    }
+
+    fn read_packed(&self, packed: i64) -> Result<Vec<u8>, String> {
+        let ptr: usize = (packed as u64 >> 32) as usize;
+        let len: usize = (packed as u64 & 0xffff_ffff) as usize;
+        self.memory
+            .data(&self.store)
+            .get(ptr..ptr + len)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(|| format!("the plugin returned a buffer outside its memory at {} with {} bytes", ptr, len))
+    }
+}
+
+// Tops the remaining fuel up to the budget of one call
+fn refuel(store: &mut Store<PluginHost>, fuel_per_call: u64) -> Result<(), String> {
+    let remaining: u64 = store.consume_fuel(0).map_err(|e| e.to_string())?;
+    store
+        .add_fuel(fuel_per_call.saturating_sub(remaining))
+        .map_err(|e| e.to_string())
+}
+
+fn define_host_functions(linker: &mut Linker<PluginHost>) -> Result<(), wasmi::errors::LinkerError> {
+    linker.func_wrap(
+        "autumn",
+        "spec_read",
+        |mut caller: Caller<'_, PluginHost>, name_ptr: i32, name_len: i32, out_ptr: i32, out_cap: i32| -> Result<i32, Trap> {
+            let memory: Memory = guest_memory(&caller)?;
+            let name: String = read_guest_string(&caller, memory, name_ptr, name_len)?;
+            let host: &PluginHost = caller.data();
+            let json: Vec<u8> = match parse_field(&name) {
+                Some(field) if host.reads.contains(&field) || host.writes.contains(&field) => {
+                    host.spec.field_json(field).to_string().into_bytes()
+                }
+                _ => return Ok(-1),
+            };
+            if json.len() <= usize::try_from(out_cap).unwrap_or_default() {
+                memory
+                    .write(&mut caller, guest_offset(out_ptr), &json)
+                    .map_err(|e| Trap::new(format!("spec_read got an invalid buffer: {}", e)))?;
+            }
+            i32::try_from(json.len()).map_err(|_| Trap::new(format!("{} does not fit the plugin memory", name)))
+        },
+    )?;
+
+    linker.func_wrap(
+        "autumn",
+        "spec_write",
+        |mut caller: Caller<'_, PluginHost>, name_ptr: i32, name_len: i32, json_ptr: i32, json_len: i32| -> Result<i32, Trap> {
+            let memory: Memory = guest_memory(&caller)?;
+            let name: String = read_guest_string(&caller, memory, name_ptr, name_len)?;
+            let json: Vec<u8> = read_guest_bytes(&caller, memory, json_ptr, json_len)?;
+            let host: &mut PluginHost = caller.data_mut();
+            let Some(field) = parse_field(&name).filter(|field| host.writes.contains(field)) else {
+                return Ok(-1);
+            };
+            let written: Result<(), serde_json::Error> = serde_json::from_slice(&json)
+                .and_then(|value: Value| host.spec.set_field_json(field, value));
+            if written.is_err() {
+                return Ok(-2);
+            }
+            // The value no longer comes from a versioned prompt
+            host.spec.prompt_versions.remove(field.name());
+            Ok(0)
+        },
+    )?;
+
+    linker.func_wrap(
+        "autumn",
+        "log",
+        |caller: Caller<'_, PluginHost>, ptr: i32, len: i32| -> Result<(), Trap> {
+            let memory: Memory = guest_memory(&caller)?;
+            let message: String = read_guest_string(&caller, memory, ptr, len)?;
+            emit_message(PrintMessage::Info, &caller.data().position, message);
+            Ok(())
+        },
+    )?;
+    Ok(())
+}
+
+fn guest_memory(caller: &Caller<'_, PluginHost>) -> Result<Memory, Trap> {
+    caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or_else(|| Trap::new("the plugin does not export its memory"))
+}
+
+// Guest pointers are unsigned 32 bit offsets
+fn guest_offset(ptr: i32) -> usize {
+    ptr as u32 as usize
+}
+
+fn read_guest_bytes(caller: &Caller<'_, PluginHost>, memory: Memory, ptr: i32, len: i32) -> Result<Vec<u8>, Trap> {
+    let start: usize = guest_offset(ptr);
+    memory
+        .data(caller)
+        .get(start..start + guest_offset(len))
+        .map(<[u8]>::to_vec)
+        .ok_or_else(|| Trap::new(format!("buffer at {} with {} bytes is outside the plugin memory", start, len)))
+}
+
+fn read_guest_string(caller: &Caller<'_, PluginHost>, memory: Memory, ptr: i32, len: i32) -> Result<String, Trap> {
+    String::from_utf8(read_guest_bytes(caller, memory, ptr, len)?)
+        .map_err(|_| Trap::new("the plugin passed a string that is not UTF-8"))
+}
+
+fn parse_field(name: &str) -> Option<SpecField> {
+    serde_json::from_value(Value::String(name.to_string())).ok()
+}
+
+// What autumn_handle_state gets as JSON
+#[derive(Serialize)]
+struct PluginInput<'a> {
+    state: AgentState,
+    position: &'a str,
+    objective: &'a str,
+    // Settings of the agent in the workflow config
+    settings: &'a Map<String, Value>,
+    // Answer to the prompt of the previous reply, null on the first call of a state
+    llm_response: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PluginReply {
+    NextState(AgentState),
+    LlmRequest(String),
+}
+
+fn host_llm(messages: Vec<Message>) -> BoxFuture<'static, Result<String, String>> {
+    Box::pin(async move { call_gpt(messages).await.map_err(|e| e.to_string()) })
+}
+
+// Agent whose states are handled by a plugin. Only its attributes are checkpointed, a resumed or
+// retried plugin continues from its state with a fresh instance, so plugins keep the results
+// they need later in the project spec.
+#[derive(Debug)]
+pub struct PluginAgent {
+    attributes: AgentAttributes,
+    plugin: PluginModule,
+    settings: Map<String, Value>,
+    llm: LlmProvider,
+    // Instantiated when the agent first handles a state, and again after a trap or a restore.
+    // None while a call runs.
+    runtime: Option<PluginRuntime>,
+}
+
+impl PluginAgent {
+    pub fn new(plugin: PluginModule, config: &AgentConfig) -> Self {
+        let attributes: AgentAttributes = AgentAttributes::new(
+            config.objective_or(&plugin.manifest.objective),
+            config.position_or(&plugin.manifest.position),
+        );
+        Self {
+            attributes,
+            plugin,
+            settings: config.settings.clone(),
+            llm: host_llm,
+            runtime: None,
+        }
+    }
+
+    fn plugin_error(&self, reason: impl Into<String>) -> AgentError {
+        AgentError::Plugin { plugin: self.plugin.manifest.name.clone(), reason: reason.into() }
+    }
+
+    // Calls autumn_handle_state once. The fields the plugin writes are only copied into the spec
+    // when the call succeeds.
+    async fn call_plugin(
+        &mut self,
+        state: AgentState,
+        llm_response: Option<String>,
+        proj_spec: &mut ProjectSpec
+    ) -> Result<PluginReply, AgentError> {
+        let input: Vec<u8> = serde_json::to_vec(&PluginInput {
+            state,
+            position: &self.attributes.position,
+            objective: &self.attributes.objective,
+            settings: &self.settings,
+            llm_response,
+        }).expect("Failed to serialize the plugin input");
+
+        // A plugin runs until it returns or its fuel is used up without ever yielding, so it runs
+        // on a blocking thread with the instance moved in and back out
+        let runtime: Option<PluginRuntime> = self.runtime.take();
+        let plugin: PluginModule = self.plugin.clone();
+        let host: PluginHost = PluginHost::new(
+            self.attributes.position.clone(),
+            self.plugin.manifest.reads.clone(),
+            self.plugin.manifest.writes.clone(),
+        );
+        let spec: ProjectSpec = proj_spec.clone();
+        let call = tokio::task::spawn_blocking(move || {
+            let mut runtime: PluginRuntime = match runtime {
+                Some(runtime) => runtime,
+                None => PluginRuntime::new(&plugin.engine, &plugin.module, host, plugin.fuel_per_call)?,
+            };
+            runtime.store.data_mut().spec = spec;
+            let output: Vec<u8> = runtime.handle_state(&input)?;
+            let plugin_spec: ProjectSpec = std::mem::replace(&mut runtime.store.data_mut().spec, empty_spec());
+            Ok::<_, String>((runtime, output, plugin_spec))
+        });
+
+        // A trapped instance may be left half way through its work, so only a successful call
+        // keeps it
+        let (runtime, output, plugin_spec): (PluginRuntime, Vec<u8>, ProjectSpec) = call
+            .await
+            .map_err(|e| self.plugin_error(e.to_string()))?
+            .map_err(|reason| self.plugin_error(reason))?;
+        self.runtime = Some(runtime);
+
+        proj_spec.copy_fields_from(&plugin_spec, &self.plugin.manifest.writes);
+        serde_json::from_slice(&output)
+            .map_err(|e| self.plugin_error(format!("invalid reply of autumn_handle_state: {}", e)))
+    }
+}
+
+#[async_trait]
+impl SpecialFunctions for PluginAgent {
+    fn get_attributes_from_agent(&self) -> &AgentAttributes {
+        &self.attributes
+    }
+
+    fn reads(&self) -> Vec<SpecField> {
+        self.plugin.manifest.reads.clone()
+    }
+
+    fn writes(&self) -> Vec<SpecField> {
+        self.plugin.manifest.writes.clone()
+    }
+
+    fn save_state(&self) -> Value {
+        serde_json::to_value(&self.attributes).expect("Failed to serialize the plugin agent")
+    }
+
+    fn restore_state(&mut self, state: Value) -> Result<(), serde_json::Error> {
+        self.attributes = serde_json::from_value(state)?;
+        self.runtime = None;
+        Ok(())
+    }
+
+    async fn execute(
+        &mut self,
+        proj_spec: &mut ProjectSpec
+    ) -> Result<(), AgentError> {
+        run_state_machine(self, proj_spec).await
+    }
+}
+
+#[async_trait]
+impl StateHandlers for PluginAgent {
+    // The plugin decides the order of its states, the runner only bounds how many it handles
+    fn state_machine(&self) -> StateMachine {
+        let working: [AgentState; 3] = [AgentState::Discovery, AgentState::Working, AgentState::UnitTesting];
+        let mut machine: StateMachine = StateMachine::new(self.plugin.manifest.max_states);
+        for from in working {
+            for to in working.into_iter().chain([AgentState::Finished]) {
+                machine = machine.transition(from, to);
+            }
+            machine = machine.timeout(from, PLUGIN_STATE_TIMEOUT);
+        }
+        machine
+    }
+
+    fn attributes_mut(&mut self) -> &mut AgentAttributes {
+        &mut self.attributes
+    }
+
+    async fn handle_state(
+        &mut self,
+        state: AgentState,
+        proj_spec: &mut ProjectSpec
+    ) -> Result<AgentState, AgentError> {
+        let mut llm_response: Option<String> = None;
+        for _ in 0..=MAX_LLM_REQUESTS_PER_STATE {
+            match self.call_plugin(state, llm_response.take(), proj_spec).await? {
+                PluginReply::NextState(next) => return Ok(next),
+                PluginReply::LlmRequest(prompt) => {
+                    let messages: Vec<Message> = vec![Message { role: "user".to_string(), content: prompt }];
+                    let function: String = self.plugin.manifest.name.clone();
+                    let answer: String = observe_llm_call(&self.attributes.position, &function, None, messages, self.llm)
+                        .await
+                        .map_err(|reason| AgentError::Llm { function, reason })?;
+                    llm_response = Some(answer);
+                }
+            }
+        }
+        Err(self.plugin_error(format!("sent more than {} LLM requests in {:?}", MAX_LLM_REQUESTS_PER_STATE, state)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::agent_manager::agent_registry::{AgentContext, AgentRegistry};
+    use crate::utils::language::ProjectLanguage;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    // Asks the LLM once, then copies the project description into the backend code
+    const ECHO_PLUGIN: &str = r#"
+        (module
+          (import "autumn" "spec_read" (func $spec_read (param i32 i32 i32 i32) (result i32)))
+          (import "autumn" "spec_write" (func $spec_write (param i32 i32 i32 i32) (result i32)))
+          (import "autumn" "log" (func $log (param i32 i32)))
+          (memory (export "memory") 1)
+          (global $heap (mut i32) (i32.const 4096))
+          (global $calls (mut i32) (i32.const 0))
+          (data (i32.const 0) "{\"name\":\"echo\",\"position\":\"Echo Developer\",\"objective\":\"Copies the description\",\"reads\":[\"project_description\"],\"writes\":[\"backend_code\"]}")
+          (data (i32.const 256) "project_description")
+          (data (i32.const 288) "backend_code")
+          (data (i32.const 320) "{\"llm_request\":\"Say hi\"}")
+          (data (i32.const 352) "{\"next_state\":\"Finished\"}")
+          (data (i32.const 384) "copied")
+          (func $pack (param $ptr i32) (param $len i32) (result i64)
+            (i64.or (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32)) (i64.extend_i32_u (local.get $len))))
+          (func (export "autumn_alloc") (param $len i32) (result i32)
+            (local $ptr i32)
+            (local.set $ptr (global.get $heap))
+            (global.set $heap (i32.add (global.get $heap) (local.get $len)))
+            (local.get $ptr))
+          (func (export "autumn_manifest") (result i64)
+            (call $pack (i32.const 0) (i32.const 138)))
+          (func (export "autumn_handle_state") (param $ptr i32) (param $len i32) (result i64)
+            (local $n i32)
+            (global.set $calls (i32.add (global.get $calls) (i32.const 1)))
+            (if (i32.eq (global.get $calls) (i32.const 1))
+              (then (return (call $pack (i32.const 320) (i32.const 24)))))
+            ;; project_description is only readable
+            (if (i32.ne (call $spec_write (i32.const 256) (i32.const 19) (i32.const 0) (i32.const 2)) (i32.const -1))
+              (then unreachable))
+            (local.set $n (call $spec_read (i32.const 256) (i32.const 19) (i32.const 1024) (i32.const 1024)))
+            (if (i32.ne (call $spec_write (i32.const 288) (i32.const 12) (i32.const 1024) (local.get $n)) (i32.const 0))
+              (then unreachable))
+            (call $log (i32.const 384) (i32.const 6))
+            (call $pack (i32.const 352) (i32.const 25))))
+    "#;
+
+    // Never returns from a state
+    const SPIN_PLUGIN: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (data (i32.const 0) "{\"name\":\"spin\",\"position\":\"Spinner\",\"objective\":\"Loops\"}")
+          (func (export "autumn_alloc") (param $len i32) (result i32) (i32.const 1024))
+          (func (export "autumn_manifest") (result i64) (i64.const 56))
+          (func (export "autumn_handle_state") (param $ptr i32) (param $len i32) (result i64)
+            (loop $forever (br $forever))
+            (i64.const 0)))
+    "#;
+
+    static LLM_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+    fn fake_llm(messages: Vec<Message>) -> BoxFuture<'static, Result<String, String>> {
+        assert_eq!(messages[0].content, "Say hi");
+        LLM_REQUESTS.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async { Ok("hi".to_string()) })
+    }
+
+    fn compile(name: &str, wat: &str) -> PluginModule {
+        PluginModule::from_wasm(Path::new(name), &wat::parse_str(wat).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn tests_plugin_agent_reads_and_writes_spec() {
+        let plugin: PluginModule = compile("echo.wasm", ECHO_PLUGIN);
+        assert_eq!(plugin.manifest.reads, vec![SpecField::ProjectDescription]);
+        assert_eq!(plugin.manifest.max_states, 10);
+
+        let mut agent: PluginAgent = PluginAgent::new(plugin, &AgentConfig::new("echo"));
+        agent.llm = fake_llm;
+        assert_eq!(agent.get_attributes_from_agent().position, "Echo Developer");
+        assert_eq!(agent.writes(), vec![SpecField::BackendCode]);
+
+        let mut spec: ProjectSpec = empty_spec();
+        spec.project_description = Some("A todo app".to_string());
+        spec.record_prompt_version("backend_code", "print_backend_webserver_code@1".to_string());
+        agent.execute(&mut spec).await.unwrap();
+
+        assert_eq!(spec.backend_code.as_deref(), Some("A todo app"));
+        assert!(!spec.prompt_versions.contains_key("backend_code"));
+        assert_eq!(LLM_REQUESTS.load(Ordering::SeqCst), 1);
+        assert_eq!(agent.get_attributes_from_agent().state, AgentState::Finished);
+    }
+
+    #[tokio::test]
+    async fn tests_plugin_agent_runs_out_of_fuel() {
+        let plugin: PluginModule = compile("spin.wasm", SPIN_PLUGIN).with_fuel_per_call(5_000_000);
+        let mut agent: PluginAgent = PluginAgent::new(plugin, &AgentConfig::new("spin"));
+
+        // Other tasks of the single threaded test runtime go on while the plugin spins
+        let ticks: Arc<AtomicUsize> = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks: Arc<AtomicUsize> = Arc::clone(&ticks);
+            async move {
+                loop {
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+
+        let err: AgentError = agent.execute(&mut empty_spec()).await.unwrap_err();
+        ticker.abort();
+        assert!(matches!(&err, AgentError::Plugin { plugin, .. } if plugin == "spin"), "{}", err);
+        assert!(err.to_string().contains("fuel"), "{}", err);
+        assert!(agent.runtime.is_none());
+        assert!(ticks.load(Ordering::SeqCst) > 0);
+    }
+
+    #[test]
+    fn tests_register_plugins_of_directory() {
+        let dir: PathBuf = env::temp_dir().join(format!("autumn_plugins_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("echo.wasm"), wat::parse_str(ECHO_PLUGIN).unwrap()).unwrap();
+        fs::write(dir.join("notes.txt"), "not a plugin").unwrap();
+
+        let mut registry: AgentRegistry = AgentRegistry::with_builtin_agents();
+        assert_eq!(registry.load_plugins(&dir.join("missing")), Ok(Vec::new()));
+        assert_eq!(registry.load_plugins(&dir), Ok(vec!["echo".to_string()]));
+        assert!(registry.load_plugins(&dir).unwrap_err().contains("already a registered agent"));
+
+        let mut config: AgentConfig = AgentConfig::new("echo");
+        config.position = Some("Copy Editor".to_string());
+        let context: AgentContext = AgentContext { language: ProjectLanguage::Rust };
+        let agent: Box<dyn SpecialFunctions> = registry.create(&config, &context).unwrap();
+        assert_eq!(agent.get_attributes_from_agent().position, "Copy Editor");
+        assert_eq!(agent.reads(), vec![SpecField::ProjectDescription]);
+
+        fs::write(dir.join("broken.wasm"), b"\0asm").unwrap();
+        assert!(load_plugins(&dir).unwrap_err().contains("broken.wasm"));
+    }
+}
@@ -1,20 +1,26 @@
 use crate::agents::base::agent_traits::BasicAgentTraits;
+use crate::agents::base::state_machine::StateTransition;
 use crate::models::general::llm::Message;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AgentState {
     Discovery,
     Working,
+    // Waiting on the user to review the generated code
+    Review,
     UnitTesting,
     Finished,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AgentAttributes {
     pub objective: String,
     pub position: String,
     pub state: AgentState,
     pub memory: Vec<Message>,
+    // Every state change made by the state machine runner
+    pub history: Vec<StateTransition>,
 }
 
 impl AgentAttributes {
@@ -24,6 +30,7 @@ impl AgentAttributes {
             position,
             state: AgentState::Discovery,
             memory: Vec::new(),
+            history: Vec::new(),
         }
     }
 }
@@ -0,0 +1,72 @@
+use crate::agents::base::agent_traits::SpecField;
+use crate::agents::base::state_machine::StateMachineError;
+use std::fmt;
+use std::io;
+
+// Why an agent could not finish its work. The manager decides from it, and the failure policy
+// of the agent, whether the workflow goes on.
+#[derive(Debug)]
+pub enum AgentError {
+    // The LLM could not be called or its answer did not match the output of the ai function
+    Llm { function: String, reason: String },
+    // A ProjectSpec field the agent reads has not been filled in by an earlier agent
+    MissingSpecField(SpecField),
+    // A setting the agent needs is missing from .env
+    Config { key: String },
+    // A generated file could not be read or written
+    Io { path: String, source: io::Error },
+    // The backend still fails to build or pass its endpoint tests after the allowed fix attempts
+    FixAttemptsExhausted { attempts: u8, errors: String },
+    // The user did not confirm running the generated code
+    CodeRejected,
+    // A loop guard of the state machine runner stopped the agent
+    StateMachine(StateMachineError),
+    // A WebAssembly plugin agent could not be run, trapped or broke the plugin ABI
+    Plugin { plugin: String, reason: String },
+    // Anything else, e.g. an HTTP client that cannot be created
+    Other(String),
+}
+
+impl AgentError {
+    pub fn config(key: impl Into<String>) -> Self {
+        Self::Config { key: key.into() }
+    }
+
+    pub fn io(path: impl fmt::Display, source: io::Error) -> Self {
+        Self::Io { path: path.to_string(), source }
+    }
+}
+
+impl fmt::Display for AgentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Llm { function, reason } => write!(f, "LLM call of {} failed: {}", function, reason),
+            Self::MissingSpecField(field) => write!(f, "Project spec has no {} yet", field),
+            Self::Config { key } => write!(f, "Could not find {} value from .env", key),
+            Self::Io { path, source } => write!(f, "Could not access {}: {}", path, source),
+            Self::FixAttemptsExhausted { attempts, errors } => {
+                write!(f, "Backend code still fails after {} fix attempts:\n{}", attempts, errors)
+            }
+            Self::CodeRejected => write!(f, "The generated code was rejected"),
+            Self::StateMachine(e) => write!(f, "{}", e),
+            Self::Plugin { plugin, reason } => write!(f, "Plugin {} failed: {}", plugin, reason),
+            Self::Other(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AgentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::StateMachine(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<StateMachineError> for AgentError {
+    fn from(e: StateMachineError) -> Self {
+        Self::StateMachine(e)
+    }
+}
@@ -1,7 +1,15 @@
+use ai_func_proc_macro::JsonSchema;
 use async_trait::async_trait;
-use crate::{agents::base::agent_base::{AgentAttributes, AgentState}, models::general::llm::Message};
+use crate::{
+    agents::{
+        agent_manager::scheduler::SpecPublisher,
+        base::{agent_base::{AgentAttributes, AgentState}, agent_error::AgentError}
+    },
+    models::general::llm::Message
+};
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::collections::BTreeMap;
+use std::fmt::{self, Debug};
 
 pub trait BasicAgentTraits {
     fn print_agent_attributes(&self);
@@ -13,23 +21,80 @@ pub trait BasicAgentTraits {
     fn get_agent_memory(&self) -> &Vec<Message>;
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
 pub struct RouteObject {
+    /// "true" if the route contains path parameters such as /todos/{id}, otherwise "false"
     pub is_route_dynamic: String,
+    /// HTTP method of the route, e.g. "get" or "post"
     pub method: String,
+    /// Example JSON request body, null if the route takes no body
     pub request_body: serde_json::Value,
+    /// Example JSON response
     pub response: serde_json::Value,
+    /// Path of the route, e.g. /todos/{id}
     pub route: String,
 }
 
-#[derive(Debug, Serialize)]
+// Static client of the website, written to FRONTEND_OUTPUT_DIR as index.html, styles.css and app.js
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, JsonSchema)]
+pub struct FrontendCode {
+    /// Complete index.html, loading styles.css and app.js
+    pub html: String,
+    /// Complete styles.css
+    pub css: String,
+    /// Complete app.js calling the backend routes
+    pub js: String,
+}
+
+// A field of the ProjectSpec, which agents declare they read or write so the manager can tell
+// which agents depend on each other
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpecField {
+    ProjectDescription,
+    ProjectScope,
+    ExternalUrls,
+    BackendCode,
+    FrontendCode,
+    ApiEndpointSchema,
+}
+
+impl SpecField {
+    // Name of the ProjectSpec field, also the key of its prompt version
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ProjectDescription => "project_description",
+            Self::ProjectScope => "project_scope",
+            Self::ExternalUrls => "external_urls",
+            Self::BackendCode => "backend_code",
+            Self::FrontendCode => "frontend_code",
+            Self::ApiEndpointSchema => "api_endpoint_schema",
+        }
+    }
+}
+
+impl fmt::Display for SpecField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectSpec {
+    /// Short summarized goal of the website build
     pub project_description: Option<String>,
     pub project_scope: Option<ProjectScope>,
+    /// Verified external API endpoints the website can use
     pub external_urls: Option<Vec<String>>,
     pub backend_code: Option<String>,
-    pub frontend_code: Option<String>,
+    pub frontend_code: Option<FrontendCode>,
     pub api_endpoint_schema: Option<Vec<RouteObject>>,
+    /// Prompt version (`<ai function>@<version>`) that produced each field
+    #[serde(skip)]
+    pub prompt_versions: BTreeMap<String, String>,
+    /// Set while the manager runs agents concurrently, shares fields with the waiting agents
+    #[serde(skip)]
+    pub publisher: Option<SpecPublisher>,
 }
 
 impl ProjectSpec {
@@ -38,7 +103,7 @@ impl ProjectSpec {
         project_scope: Option<ProjectScope>,
         external_urls: Option<Vec<String>>,
         backend_code: Option<String>,
-        frontend_code: Option<String>,
+        frontend_code: Option<FrontendCode>,
         api_endpoint_schema: Option<Vec<RouteObject>>,
     ) -> Self {
         Self {
@@ -48,27 +113,108 @@ impl ProjectSpec {
             backend_code,
             frontend_code,
             api_endpoint_schema,
+            prompt_versions: BTreeMap::new(),
+            publisher: None,
+        }
+    }
+
+    // Keeps track of which prompt version produced a field, so regressions can be traced to a prompt edit
+    pub fn record_prompt_version(&mut self, field: &str, prompt_version: String) {
+        self.prompt_versions.insert(field.to_string(), prompt_version);
+    }
+
+    // Copies the fields, and the prompt versions that produced them, from another spec
+    pub fn copy_fields_from(&mut self, other: &ProjectSpec, fields: &[SpecField]) {
+        for field in fields {
+            match field {
+                SpecField::ProjectDescription => self.project_description = other.project_description.clone(),
+                SpecField::ProjectScope => self.project_scope = other.project_scope,
+                SpecField::ExternalUrls => self.external_urls = other.external_urls.clone(),
+                SpecField::BackendCode => self.backend_code = other.backend_code.clone(),
+                SpecField::FrontendCode => self.frontend_code = other.frontend_code.clone(),
+                SpecField::ApiEndpointSchema => self.api_endpoint_schema = other.api_endpoint_schema.clone(),
+            }
+            match other.prompt_versions.get(field.name()) {
+                Some(version) => self.prompt_versions.insert(field.name().to_string(), version.clone()),
+                None => self.prompt_versions.remove(field.name()),
+            };
+        }
+    }
+
+    // A field as JSON, null when it is not filled in yet
+    pub fn field_json(&self, field: SpecField) -> serde_json::Value {
+        let value: Result<serde_json::Value, serde_json::Error> = match field {
+            SpecField::ProjectDescription => serde_json::to_value(&self.project_description),
+            SpecField::ProjectScope => serde_json::to_value(self.project_scope),
+            SpecField::ExternalUrls => serde_json::to_value(&self.external_urls),
+            SpecField::BackendCode => serde_json::to_value(&self.backend_code),
+            SpecField::FrontendCode => serde_json::to_value(&self.frontend_code),
+            SpecField::ApiEndpointSchema => serde_json::to_value(&self.api_endpoint_schema),
+        };
+        value.expect("Failed to serialize a project spec field")
+    }
+
+    // Sets a field from JSON, leaving it unchanged when the JSON does not match its type
+    pub fn set_field_json(&mut self, field: SpecField, value: serde_json::Value) -> Result<(), serde_json::Error> {
+        match field {
+            SpecField::ProjectDescription => self.project_description = serde_json::from_value(value)?,
+            SpecField::ProjectScope => self.project_scope = serde_json::from_value(value)?,
+            SpecField::ExternalUrls => self.external_urls = serde_json::from_value(value)?,
+            SpecField::BackendCode => self.backend_code = serde_json::from_value(value)?,
+            SpecField::FrontendCode => self.frontend_code = serde_json::from_value(value)?,
+            SpecField::ApiEndpointSchema => self.api_endpoint_schema = serde_json::from_value(value)?,
+        }
+        Ok(())
+    }
+
+    // Lets the agents waiting for a field start before the writing agent has finished, e.g. the
+    // frontend once the backend routes are known. Does nothing when agents run one by one.
+    pub fn publish(&self, field: SpecField) {
+        if let Some(publisher) = &self.publisher {
+            publisher.publish(self, field);
+        }
+    }
+
+    // Saves the state of the agent working on this spec to the run checkpoint. Does nothing
+    // when the run is not checkpointed.
+    pub fn checkpoint(&self, position: &str, agent: serde_json::Value) {
+        if let Some(publisher) = &self.publisher {
+            publisher.checkpoint(self, position, agent);
         }
     }
 }
 
-#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Copy, Clone, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
 pub struct ProjectScope {
+    /// true if site needs CRUD functionality
     pub is_crud_required: bool,
+    /// true if site needs users to be able to log in and log out
     pub is_user_login_and_logout: bool,
+    /// true if site needs to fetch data from third party providers
     pub is_external_urls_required: bool
 }
 
 // TODO: Double check if the async_trait is necessary for this
 #[async_trait]
-pub trait SpecialFunctions: Debug {
+pub trait SpecialFunctions: Debug + Send {
 
     // Manager can get attributes of other agents
     fn get_attributes_from_agent(&self) -> &AgentAttributes;
 
+    // ProjectSpec fields the agent needs before it can start
+    fn reads(&self) -> Vec<SpecField>;
+
+    // ProjectSpec fields the agent fills in
+    fn writes(&self) -> Vec<SpecField>;
+
+    // State written to the run checkpoint, everything the agent needs to continue where it stopped
+    fn save_state(&self) -> serde_json::Value;
+
+    fn restore_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error>;
+
     // The function in which all agents will execute their logic in
     async fn execute(
         &mut self, 
         proj_spec: &mut ProjectSpec
-    ) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<(), AgentError>;
 }
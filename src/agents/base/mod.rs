@@ -1,2 +1,4 @@
 pub mod agent_base;
+pub mod agent_error;
 pub mod agent_traits;
+pub mod state_machine;
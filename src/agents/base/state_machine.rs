@@ -0,0 +1,330 @@
+use async_trait::async_trait;
+use crate::agents::base::{
+    agent_base::{AgentAttributes, AgentState},
+    agent_error::AgentError,
+    agent_traits::{BasicAgentTraits, ProjectSpec, SpecialFunctions}
+};
+use crate::events::workflow_events::{emit, WorkflowEvent};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+// States an agent may move between, how long each state may take and how many states it may
+// handle in total before the runner gives up
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateMachine {
+    transitions: Vec<(AgentState, AgentState)>,
+    timeouts: Vec<(AgentState, Duration)>,
+    max_iterations: usize,
+}
+
+impl StateMachine {
+    pub fn new(max_iterations: usize) -> Self {
+        Self {
+            transitions: Vec::new(),
+            timeouts: Vec::new(),
+            max_iterations,
+        }
+    }
+
+    pub fn transition(mut self, from: AgentState, to: AgentState) -> Self {
+        self.transitions.push((from, to));
+        self
+    }
+
+    pub fn timeout(mut self, state: AgentState, timeout: Duration) -> Self {
+        self.timeouts.push((state, timeout));
+        self
+    }
+
+    fn allows(&self, from: AgentState, to: AgentState) -> bool {
+        self.transitions.contains(&(from, to))
+    }
+
+    pub(crate) fn timeout_for(&self, state: AgentState) -> Option<Duration> {
+        self.timeouts
+            .iter()
+            .find(|(timeout_state, _)| *timeout_state == state)
+            .map(|(_, timeout)| *timeout)
+    }
+}
+
+// A state change made by the runner, kept in the agent's history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateTransition {
+    pub from: AgentState,
+    pub to: AgentState,
+    // Time the handler of `from` took
+    pub elapsed: Duration,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StateMachineError {
+    MaxIterations { agent: String, max_iterations: usize },
+    Timeout { agent: String, state: AgentState, timeout: Duration },
+    InvalidTransition { agent: String, from: AgentState, to: AgentState },
+}
+
+impl fmt::Display for StateMachineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaxIterations { agent, max_iterations } => {
+                write!(f, "{} did not finish within {} states", agent, max_iterations)
+            }
+            Self::Timeout { agent, state, timeout } => {
+                write!(f, "{} spent more than {:?} in {:?}", agent, timeout, state)
+            }
+            Self::InvalidTransition { agent, from, to } => {
+                write!(f, "{} may not move from {:?} to {:?}", agent, from, to)
+            }
+        }
+    }
+}
+
+impl std::error::Error for StateMachineError {}
+
+// Implemented by agents whose work is driven by `run_state_machine`
+#[async_trait]
+pub trait StateHandlers: Send {
+    fn state_machine(&self) -> StateMachine;
+
+    fn attributes_mut(&mut self) -> &mut AgentAttributes;
+
+    // Does the work of a state and returns the state to move to
+    async fn handle_state(
+        &mut self,
+        state: AgentState,
+        proj_spec: &mut ProjectSpec
+    ) -> Result<AgentState, AgentError>;
+}
+
+// Calls the handler of the current state until the agent is Finished, recording every
+// transition and checkpointing the agent after it. A failing handler, a timeout, an undeclared
+// transition or too many iterations finish the agent with an error.
+pub async fn run_state_machine<A: StateHandlers + SpecialFunctions + ?Sized>(
+    agent: &mut A,
+    proj_spec: &mut ProjectSpec
+) -> Result<(), AgentError> {
+    let machine: StateMachine = agent.state_machine();
+    let result: Result<(), AgentError> = run_states(agent, &machine, proj_spec).await;
+    if result.is_err() {
+        agent.attributes_mut().update_agent_state(AgentState::Finished);
+    }
+    result
+}
+
+async fn run_states<A: StateHandlers + SpecialFunctions + ?Sized>(
+    agent: &mut A,
+    machine: &StateMachine,
+    proj_spec: &mut ProjectSpec
+) -> Result<(), AgentError> {
+    // A resumed agent keeps counting from its earlier transitions
+    let mut iterations: usize = agent.attributes_mut().history.len();
+
+    while agent.attributes_mut().state != AgentState::Finished {
+        let state: AgentState = agent.attributes_mut().state;
+        let position: String = agent.attributes_mut().position.clone();
+        if iterations == machine.max_iterations {
+            return Err(StateMachineError::MaxIterations {
+                agent: position,
+                max_iterations: machine.max_iterations,
+            }.into());
+        }
+        iterations += 1;
+
+        let started: Instant = Instant::now();
+        let next: AgentState = match machine.timeout_for(state) {
+            Some(timeout) => tokio::time::timeout(timeout, agent.handle_state(state, proj_spec))
+                .await
+                .map_err(|_| StateMachineError::Timeout { agent: position.clone(), state, timeout })??,
+            None => agent.handle_state(state, proj_spec).await?,
+        };
+
+        if !machine.allows(state, next) {
+            return Err(StateMachineError::InvalidTransition { agent: position, from: state, to: next }.into());
+        }
+
+        let elapsed: Duration = started.elapsed();
+        let attributes: &mut AgentAttributes = agent.attributes_mut();
+        attributes.history.push(StateTransition { from: state, to: next, elapsed });
+        attributes.update_agent_state(next);
+        emit(WorkflowEvent::StateChanged {
+            agent: position.clone(),
+            from: state,
+            to: next,
+            elapsed_ms: elapsed.as_millis() as u64,
+        });
+        proj_spec.checkpoint(&position, agent.save_state());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::base::agent_traits::SpecField;
+    use crate::utils::general::run_project_commands;
+    use crate::utils::language::ProjectCommand;
+    use std::path::Path;
+
+    // Goes through the given states, sleeping in each and running the command, if any, like a
+    // build of the backend. Review takes `review` instead, like a person reading the code.
+    #[derive(Debug)]
+    struct ScriptedAgent {
+        attributes: AgentAttributes,
+        next_states: Vec<AgentState>,
+        sleep: Duration,
+        review: Duration,
+        command: Option<ProjectCommand>,
+    }
+
+    impl ScriptedAgent {
+        fn new(next_states: &[AgentState], sleep: Duration) -> Self {
+            Self {
+                attributes: AgentAttributes::new("test".to_string(), "Scripted Agent".to_string()),
+                next_states: next_states.to_vec(),
+                sleep,
+                review: Duration::ZERO,
+                command: None,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl SpecialFunctions for ScriptedAgent {
+        fn get_attributes_from_agent(&self) -> &AgentAttributes {
+            &self.attributes
+        }
+
+        fn reads(&self) -> Vec<SpecField> {
+            Vec::new()
+        }
+
+        fn writes(&self) -> Vec<SpecField> {
+            Vec::new()
+        }
+
+        fn save_state(&self) -> serde_json::Value {
+            serde_json::to_value(&self.attributes).unwrap()
+        }
+
+        fn restore_state(&mut self, state: serde_json::Value) -> Result<(), serde_json::Error> {
+            self.attributes = serde_json::from_value(state)?;
+            Ok(())
+        }
+
+        async fn execute(&mut self, proj_spec: &mut ProjectSpec) -> Result<(), AgentError> {
+            run_state_machine(self, proj_spec).await
+        }
+    }
+
+    #[async_trait]
+    impl StateHandlers for ScriptedAgent {
+        fn state_machine(&self) -> StateMachine {
+            StateMachine::new(5)
+                .transition(AgentState::Discovery, AgentState::Working)
+                .transition(AgentState::Working, AgentState::UnitTesting)
+                .transition(AgentState::Working, AgentState::Review)
+                .transition(AgentState::Review, AgentState::UnitTesting)
+                .transition(AgentState::UnitTesting, AgentState::Working)
+                .transition(AgentState::UnitTesting, AgentState::Finished)
+                .timeout(AgentState::Working, Duration::from_millis(50))
+                .timeout(AgentState::UnitTesting, Duration::from_millis(50))
+        }
+
+        fn attributes_mut(&mut self) -> &mut AgentAttributes {
+            &mut self.attributes
+        }
+
+        async fn handle_state(
+            &mut self,
+            state: AgentState,
+            _proj_spec: &mut ProjectSpec
+        ) -> Result<AgentState, AgentError> {
+            match state {
+                AgentState::Review => tokio::time::sleep(self.review).await,
+                _ => tokio::time::sleep(self.sleep).await,
+            }
+            if let Some(command) = &self.command {
+                run_project_commands(Path::new("."), std::slice::from_ref(command)).await;
+            }
+            Ok(self.next_states.remove(0))
+        }
+    }
+
+    fn spec() -> ProjectSpec {
+        ProjectSpec::new(None, None, None, None, None, None)
+    }
+
+    #[tokio::test]
+    async fn tests_run_state_machine_records_history() {
+        let mut agent: ScriptedAgent = ScriptedAgent::new(
+            &[AgentState::Working, AgentState::UnitTesting, AgentState::Finished],
+            Duration::ZERO
+        );
+        run_state_machine(&mut agent, &mut spec()).await.unwrap();
+
+        let history: Vec<(AgentState, AgentState)> = agent.attributes.history
+            .iter()
+            .map(|transition| (transition.from, transition.to))
+            .collect();
+        assert_eq!(history, vec![
+            (AgentState::Discovery, AgentState::Working),
+            (AgentState::Working, AgentState::UnitTesting),
+            (AgentState::UnitTesting, AgentState::Finished),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn tests_run_state_machine_loop_guards() {
+        // Keeps failing its tests
+        let mut looping: ScriptedAgent = ScriptedAgent::new(
+            &[AgentState::Working, AgentState::UnitTesting, AgentState::Working, AgentState::UnitTesting, AgentState::Working],
+            Duration::ZERO
+        );
+        let err = run_state_machine(&mut looping, &mut spec()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Scripted Agent did not finish within 5 states");
+        assert_eq!(looping.attributes.state, AgentState::Finished);
+
+        let mut slow: ScriptedAgent = ScriptedAgent::new(&[AgentState::Working, AgentState::UnitTesting], Duration::from_millis(100));
+        slow.attributes.state = AgentState::Working;
+        let err = run_state_machine(&mut slow, &mut spec()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Scripted Agent spent more than 50ms in Working");
+
+        // A resumed agent has used up part of its states already
+        let mut resumed: ScriptedAgent = ScriptedAgent::new(&[AgentState::UnitTesting, AgentState::Working], Duration::ZERO);
+        resumed.attributes.history = looping.attributes.history[..3].to_vec();
+        resumed.attributes.state = AgentState::Working;
+        let err = run_state_machine(&mut resumed, &mut spec()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Scripted Agent did not finish within 5 states");
+        assert_eq!(resumed.attributes.history.len(), 5);
+
+        // A build that never returns is stopped by the timeout of its state
+        let mut building: ScriptedAgent = ScriptedAgent::new(&[AgentState::UnitTesting], Duration::ZERO);
+        building.attributes.state = AgentState::Working;
+        building.command = Some(ProjectCommand {
+            program: "sleep",
+            args: vec!["30".to_string()],
+            success_codes: &[],
+        });
+        let started: Instant = Instant::now();
+        let err = run_state_machine(&mut building, &mut spec()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Scripted Agent spent more than 50ms in Working");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Only the states around an untimed review are timed, however long the reviewer takes
+        let mut reviewed: ScriptedAgent = ScriptedAgent::new(
+            &[AgentState::Review, AgentState::UnitTesting, AgentState::Finished],
+            Duration::ZERO
+        );
+        reviewed.attributes.state = AgentState::Working;
+        reviewed.review = Duration::from_millis(200);
+        run_state_machine(&mut reviewed, &mut spec()).await.unwrap();
+        assert!(reviewed.attributes.history[1].elapsed >= Duration::from_millis(200));
+
+        let mut skipping: ScriptedAgent = ScriptedAgent::new(&[AgentState::Finished], Duration::ZERO);
+        let err = run_state_machine(&mut skipping, &mut spec()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Scripted Agent may not move from Discovery to Finished");
+        assert!(skipping.attributes.history.is_empty());
+    }
+}
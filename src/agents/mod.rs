@@ -2,4 +2,5 @@ pub mod base;
 pub mod agent_architect;
 pub mod agent_backend;
 pub mod agent_frontend;
-pub mod agent_manager;
\ No newline at end of file
+pub mod agent_manager;
+pub mod agent_plugin;
\ No newline at end of file
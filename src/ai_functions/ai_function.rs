@@ -0,0 +1,244 @@
+use crate::ai_functions::json_schema::{validate, JsonSchema, SchemaError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt;
+
+// A function the LLM is asked to "print" the result of.
+//
+// Implemented by the `#[ai_function]` attribute, which turns the annotated function into a
+// unit struct of the same name. The name, prompt, input type and output type are therefore
+// always carried together, and calling the function through `request_task_llm` returns
+// `Output` directly. Functions with several inputs take them as a tuple in declaration order.
+pub trait AiFunction {
+    type Input<'a>;
+    type Output;
+
+    fn name(&self) -> &'static str;
+    fn prompt(&self) -> &'static str;
+    // Version of the built-in prompt, recorded with everything the function produces
+    fn version(&self) -> &'static str;
+    // Whether the output is source code of the backend project, see `#[ai_function(prints_code)]`
+    fn prints_code(&self) -> bool;
+    fn input_names(&self) -> &'static [&'static str];
+    fn input_type(&self) -> &'static str;
+    fn output_type(&self) -> &'static str;
+
+    // JSON Schema of the declared output type, `None` for plain text outputs
+    fn output_schema(&self) -> Option<serde_json::Value>;
+
+    // Renders every input as a (name, value) section the way it is shown to the LLM
+    fn render_input(&self, input: Self::Input<'_>) -> Vec<(&'static str, String)>;
+
+    // Converts the raw LLM response into the declared output type
+    fn parse_output(&self, res: &str) -> Result<Self::Output, AiFunctionError>;
+
+    // Few-shot examples registered with `#[ai_function(examples = ...)]`
+    fn examples(&self) -> Vec<FewShotExample<Self::Output>>;
+
+    // The examples with their output rendered the way the LLM should print it
+    fn rendered_examples(&self) -> Vec<FewShotExample<String>>;
+}
+
+// A worked example of an ai function, shown to the LLM together with the prompt. The output is
+// a typed value, so an example that no longer fits the output type fails to compile and the
+// generated tests check it against the output schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FewShotExample<O> {
+    pub input: String,
+    pub output: O,
+}
+
+impl<O> FewShotExample<O> {
+    pub fn new(input: &str, output: O) -> Self {
+        Self {
+            input: input.to_string(),
+            output,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AiFunctionError {
+    InvalidJson(serde_json::Error),
+    SchemaMismatch(Vec<SchemaError>),
+}
+
+impl fmt::Display for AiFunctionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidJson(e) => write!(f, "LLM response is not valid JSON: {}", e),
+            Self::SchemaMismatch(errors) => {
+                write!(f, "LLM response does not match the output schema:")?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for AiFunctionError {}
+
+// Validates the response against the output schema before deserializing it, so a bad
+// response is reported field by field instead of as the first serde error
+pub fn parse_json_output<T: DeserializeOwned + JsonSchema>(res: &str) -> Result<T, AiFunctionError> {
+    let value: serde_json::Value = serde_json::from_str(res).map_err(AiFunctionError::InvalidJson)?;
+    validate(&T::json_schema(), &value).map_err(AiFunctionError::SchemaMismatch)?;
+    serde_json::from_value(value).map_err(AiFunctionError::InvalidJson)
+}
+
+// Strings are shown to the LLM as is, everything else as pretty printed JSON
+pub fn prompt_value<T: Serialize + ?Sized>(value: &T) -> String {
+    match serde_json::to_value(value).expect("Failed to serialize ai function input") {
+        serde_json::Value::String(value) => value,
+        value => serde_json::to_string_pretty(&value).expect("Failed to serialize ai function input"),
+    }
+}
+
+// Joins the input sections into the labeled block the LLM receives, e.g.
+//
+//   PROJECT_DESCRIPTION: build a website that ...
+//
+//   CODE_TEMPLATE: use actix_web::...
+pub fn format_input_sections(sections: &[(&str, String)]) -> String {
+    sections
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name.to_uppercase(), value))
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+// Picks the examples closest to the request, by the Jaccard similarity of their words.
+// Equally close examples keep their registration order.
+pub fn select_examples<O>(examples: Vec<FewShotExample<O>>, request: &str, limit: usize) -> Vec<FewShotExample<O>> {
+    let request_words: BTreeSet<String> = words(request);
+    let mut scored: Vec<(f64, FewShotExample<O>)> = examples
+        .into_iter()
+        .map(|example| (jaccard_similarity(&request_words, &words(&example.input)), example))
+        .collect();
+
+    // Stable sort, so ties stay in registration order
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().take(limit).map(|(_, example)| example).collect()
+}
+
+// Renders the examples as the EXAMPLES block of the instruction, e.g.
+//
+//   Example 1:
+//     INPUT: I need a simple TODO app
+//     OUTPUT: {"is_crud_required": true, ...}
+pub fn format_examples(examples: &[FewShotExample<String>]) -> String {
+    examples
+        .iter()
+        .enumerate()
+        .map(|(i, example)| {
+            format!(
+                "Example {}:\n  INPUT: {}\n  OUTPUT: {}",
+                i + 1,
+                example.input,
+                example.output.replace('\n', "\n  ")
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn words(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn jaccard_similarity(a: &BTreeSet<String>, b: &BTreeSet<String>) -> f64 {
+    let union: usize = a.union(b).count();
+    if union == 0 {
+        0.0
+    } else {
+        a.intersection(b).count() as f64 / union as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::base::agent_traits::RouteObject;
+
+    #[test]
+    fn tests_prompt_value() {
+        assert_eq!(prompt_value("build a todo app"), "build a todo app");
+
+        let routes: Vec<RouteObject> = vec![RouteObject {
+            is_route_dynamic: "false".to_string(),
+            method: "get".to_string(),
+            request_body: serde_json::Value::Null,
+            response: serde_json::json!({ "id": 1 }),
+            route: "/todos".to_string(),
+        }];
+        let rendered: String = prompt_value(routes.as_slice());
+        assert!(rendered.starts_with("[\n"));
+        assert!(rendered.contains("\"route\": \"/todos\""));
+    }
+
+    #[test]
+    fn tests_parse_json_output() {
+        let urls: Vec<String> = parse_json_output(r#"["https://api.binance.com"]"#).unwrap();
+        assert_eq!(urls, vec!["https://api.binance.com".to_string()]);
+
+        let err: AiFunctionError = parse_json_output::<Vec<String>>(r#"["https://api.binance.com", 3]"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "LLM response does not match the output schema:\n  $[1]: expected string, found number"
+        );
+
+        let err: AiFunctionError = parse_json_output::<Vec<String>>("Sure! Here are the urls").unwrap_err();
+        assert!(matches!(err, AiFunctionError::InvalidJson(_)));
+    }
+
+    #[test]
+    fn tests_format_input_sections() {
+        let sections: Vec<(&str, String)> = vec![
+            ("project_description", "build a todo app".to_string()),
+            ("code_template", "fn main() {}".to_string()),
+        ];
+        assert_eq!(
+            format_input_sections(&sections),
+            "PROJECT_DESCRIPTION: build a todo app\n\nCODE_TEMPLATE: fn main() {}"
+        );
+    }
+
+    #[test]
+    fn tests_select_examples() {
+        let examples: Vec<FewShotExample<&str>> = vec![
+            FewShotExample::new("I need a simple TODO app", "todo"),
+            FewShotExample::new("A website that shows stock price data", "stocks"),
+            FewShotExample::new("A blog with user login", "blog"),
+        ];
+
+        let selected: Vec<&str> = select_examples(examples.clone(), "Show me crypto price data on a website", 2)
+            .into_iter()
+            .map(|example| example.output)
+            .collect();
+        assert_eq!(selected, vec!["stocks", "blog"]);
+
+        // Nothing in common keeps the registration order
+        let selected: Vec<&str> = select_examples(examples, "xyz", 3)
+            .into_iter()
+            .map(|example| example.output)
+            .collect();
+        assert_eq!(selected, vec!["todo", "stocks", "blog"]);
+    }
+
+    #[test]
+    fn tests_format_examples() {
+        let examples: Vec<FewShotExample<String>> = vec![
+            FewShotExample::new("I need a simple TODO app", "{\n  \"is_crud_required\": true\n}".to_string()),
+        ];
+        assert_eq!(
+            format_examples(&examples),
+            "Example 1:\n  INPUT: I need a simple TODO app\n  OUTPUT: {\n    \"is_crud_required\": true\n  }"
+        );
+    }
+}
@@ -1,8 +1,30 @@
-use ai_func_proc_macro::ai_function_to_string;
-// use ai_functions::ai_function;
+use ai_func_proc_macro::ai_function;
+use crate::agents::base::agent_traits::{FrontendCode, ProjectScope, ProjectSpec, RouteObject};
+use crate::ai_functions::ai_function::FewShotExample;
 
 /* Architect AI Functions*/
-#[ai_function_to_string]
+fn project_scope_examples() -> Vec<FewShotExample<ProjectScope>> {
+    vec![
+        FewShotExample::new(
+            "I need a full stack website that accepts users and gets stock price data",
+            ProjectScope {
+                is_crud_required: true,
+                is_user_login_and_logout: true,
+                is_external_urls_required: true,
+            },
+        ),
+        FewShotExample::new(
+            "I need a simple TODO app",
+            ProjectScope {
+                is_crud_required: true,
+                is_user_login_and_logout: false,
+                is_external_urls_required: false,
+            },
+        ),
+    ]
+}
+
+#[ai_function(output = ProjectScope, version = "2", examples = project_scope_examples)]
 pub fn print_project_scope(_project_description: &str) {
     /// Input: Takes in a user request to build a website project description
     /// Function: Converts user request into JSON response of information items required for a website build.
@@ -10,29 +32,13 @@ pub fn print_project_scope(_project_description: &str) {
     /// Output: Prints an object response in the following format:
     ///   {
     ///     "is_crud_required": bool, // true if site needs CRUD functionality
-    ///     "is_user_login_and_logout": bool // true if site needs users to be able to log in and log out
+    ///     "is_user_login_and_logout": bool, // true if site needs users to be able to log in and log out
     ///     "is_external_urls_required": bool // true if site needs to fetch data from third part providers
     ///   }
-    /// Example 1:
-    ///   user_request = "I need a full stack website that accepts users and gets stock price data"
-    ///   prints:
-    ///   {
-    ///     "is_crud_required": true
-    ///     "is_user_login_and_logout": true
-    ///     "is_external_urls_required": bool true
-    ///   }
-    /// Example 2:
-    ///   user_request = "I need a simple TODO app"
-    ///   prints:
-    ///   {
-    ///     "is_crud_required": true
-    ///     "is_user_login_and_logout": false
-    ///     "is_external_urls_required": bool false
-    ///   }
     println!(OUTPUT)
 }
 
-#[ai_function_to_string]
+#[ai_function(output = Vec<String>)]
 pub fn print_site_urls(_project_description: &str) {
     /// Input: Takes in a project description of a website build
     /// Function: Outputs a list of external public API endpoints that should be used in the building of the website
@@ -47,57 +53,176 @@ pub fn print_site_urls(_project_description: &str) {
 }
 
 /* Project Manager AI Function */
-#[ai_function_to_string]
+fn user_input_to_goal_examples() -> Vec<FewShotExample<String>> {
+    vec![
+        FewShotExample::new(
+            "I need a website that lets users login and logout. It needs to look fancy and accept payments.",
+            "build a website that handles users logging in and logging out and accepts payments".to_string(),
+        ),
+        FewShotExample::new(
+            "Create something that stores crypto price data in a database using supabase and retrieves prices on the frontend.",
+            "build a website that fetches and stores crypto price data within a supabase setup including a frontend UI to fetch the data.".to_string(),
+        ),
+    ]
+}
+
+#[ai_function(version = "2", examples = user_input_to_goal_examples)]
 pub fn convert_user_input_to_goal(_usr_req: &str) {
     /// Input: Takes in a user request
     /// Function: Converts user request into a short summarized goal
     /// Output: Prints goal. All outputs start with "build a website that ..."
-    /// Example 1:
-    ///   user_request = "I need a website that lets users login and logout. It needs to look fancy and accept payments."
-    ///   OUTPUT = "build a website that handles users logging in and logging out and accepts payments"
-    /// Example 2:
-    ///   user_request = "Create something that stores crypto price data in a database using supabase and retrieves prices on the frontend."
-    ///   OUTPUT = "build a website that fetches and stores crypto price data within a supabase setup including a frontend UI to fetch the data."
     println!(OUTPUT)
 }
 
 /* Backend Developer AI Functions */
-#[ai_function_to_string]
-pub fn print_backend_webserver_code(_project_description_and_template: &str) {
-    /// INPUT: Takes in a PROJECT_DESCRIPTION and CODE_TEMPLATE for a website backend build
+#[ai_function(version = "3", prints_code)]
+pub fn print_backend_webserver_code(_project_description: &str, _code_template: &str, _language: &str, _allowed_libraries: &str) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION and CODE_TEMPLATE for a website backend build written in LANGUAGE
     /// IMPORTANT: The backend code is ONLY an example. If the Project Description requires it, make as many changes as you like.
     /// IMPORTANT: You do not need to follow the backend code exactly. Write functions that make sense for the users request if required.
     /// FUNCTION: Takes an existing set of code marked as CODE_TEMPLATE and updates or re-writes it to work for the purpose in the PROJECT_DESCRIPTION
-    /// IMPORTANT: The following libraries are already installed
-    ///   reqwest, serde, serde_json, tokio, actix-web, async-trait, actix_cors
+    /// IMPORTANT: The code stays in LANGUAGE and is one single file like the CODE_TEMPLATE. Only the ALLOWED_LIBRARIES are installed.
     /// No other external libraries should be used. Write functions that fit with the description from the PROJECT_DESCRIPTION
+    /// IMPORTANT: The server binds to 127.0.0.1 on the port in the PORT environment variable, defaulting to 8080
     /// OUTPUT: Print ONLY the code, nothing else. This function ONLY prints code.
     println!(OUTPUT)
 }
 
-#[ai_function_to_string]
-pub fn print_improved_webserver_code(_project_description_and_template: &str) {
-    /// INPUT: Takes in a PROJECT_DESCRIPTION and CODE_TEMPLATE for a website backend build
+#[ai_function(version = "3", prints_code)]
+pub fn print_improved_webserver_code(_project_spec: &ProjectSpec, _code_template: &str, _language: &str, _allowed_libraries: &str) {
+    /// INPUT: Takes in a PROJECT_SPEC and CODE_TEMPLATE for a website backend build written in LANGUAGE
     /// FUNCTION: Performs the following tasks:
     ///   1. Removes any bugs in the code and adds minor additional functionality
     ///   2. Makes sure everything requested in the spec from a backend standpoint was followed. If not, add the feature. No code should be implemented later. Everything should be written now.
     ///   3. ONLY writes the code. No commentary.
-    /// IMPORTANT: The following libraries are already installed. Does not use ANY libraries other than what was provided in the template
-    ///   reqwest, serde, serde_json, tokio, actix-web, async-trait
+    /// IMPORTANT: The code stays in LANGUAGE. Only the ALLOWED_LIBRARIES are installed, does not use ANY other libraries
+    /// IMPORTANT: The server binds to 127.0.0.1 on the port in the PORT environment variable, defaulting to 8080
+    /// OUTPUT: Print ONLY the code, nothing else. This function ONLY prints code.
     println!(OUTPUT)
-  }
+}
 
+#[ai_function(version = "3", prints_code)]
+pub fn print_fixed_code(_broken_code: &str, _error_bugs: &str, _language: &str) {
+    /// INPUT: Takes in BROKEN_CODE written in LANGUAGE and the ERROR_BUGS reported by its build and checks or found by calling the routes of the running server
+    /// FUNCTION: Removes the bugs from the code so it builds and every route responds as expected
+    ///   1. Fixes every error of ERROR_BUGS at its source, changing as little of the rest of the code as possible
+    ///   2. Keeps every route and all functionality of the BROKEN_CODE
+    ///   3. Keeps binding to the port in the PORT environment variable
+    /// IMPORTANT: Does not use ANY libraries other than the ones the BROKEN_CODE already uses
+    /// OUTPUT: Print ONLY the fixed code, nothing else. This function ONLY prints code.
+    println!(OUTPUT)
+}
 
+#[ai_function(output = Vec<RouteObject>)]
+pub fn print_rest_api_endpoints(_backend_code: &str) {
+    /// INPUT: Takes in the BACKEND_CODE of a web server
+    /// FUNCTION: Lists every REST API endpoint the server exposes, with an example request body and an example response
+    /// IMPORTANT: Dynamic routes use {name} for their path parameters, e.g. /todos/{id}
+    /// IMPORTANT: request_body is null for routes that take no body
+    /// OUTPUT: Prints a JSON list of the endpoints, nothing else. For example:
+    ///   prints:
+    ///   [
+    ///     {
+    ///       "is_route_dynamic": "false",
+    ///       "method": "get",
+    ///       "request_body": null,
+    ///       "response": [{ "id": 1, "title": "Buy milk", "completed": false }],
+    ///       "route": "/todos"
+    ///     },
+    ///     {
+    ///       "is_route_dynamic": "true",
+    ///       "method": "put",
+    ///       "request_body": { "title": "Buy milk", "completed": true },
+    ///       "response": { "id": 1, "title": "Buy milk", "completed": true },
+    ///       "route": "/todos/{id}"
+    ///     }
+    ///   ]
+    println!(OUTPUT)
+}
+
+/* Frontend Developer AI Functions */
+#[ai_function(output = FrontendCode)]
+pub fn print_frontend_code(_project_description: &str, _api_endpoint_schema: &[RouteObject]) {
+    /// INPUT: Takes in a PROJECT_DESCRIPTION and the API_ENDPOINT_SCHEMA of the website backend
+    /// FUNCTION: Writes a static client for the website in plain HTML, CSS and JavaScript
+    ///   1. index.html loads styles.css and app.js and contains everything the PROJECT_DESCRIPTION asks for
+    ///   2. app.js calls every route of the API_ENDPOINT_SCHEMA with fetch, using the method, request body and response shown there
+    ///   3. Routes are called relative to `const API_BASE_URL = window.API_BASE_URL || "http://localhost:8080";`
+    ///   4. Dynamic routes such as /todos/{id} fill in their path parameters from the page
+    ///   5. If the API_ENDPOINT_SCHEMA is empty, the website is fully static
+    /// IMPORTANT: No frameworks, bundlers or external libraries. Everything is written now, no placeholders.
+    /// OUTPUT: Prints an object with the complete files, nothing else:
+    ///   {
+    ///     "html": "<!DOCTYPE html>...",
+    ///     "css": "body { ... }",
+    ///     "js": "const API_BASE_URL = ..."
+    ///   }
+    println!(OUTPUT)
+}
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::ai_functions::ai_function::AiFunction;
 
     #[test]
     fn tests_ai_function_proc_macros() {
-        let output1 = print_project_scope("build a project");
+        let output1 = print_project_scope.prompt();
 
         dbg!(output1);
+        assert_eq!(print_project_scope.name(), "print_project_scope");
+        assert_eq!(print_project_scope.input_type(), "&str");
+        assert_eq!(print_project_scope.output_type(), "ProjectScope");
+        assert_eq!(print_site_urls.output_type(), "Vec<String>");
+        assert_eq!(convert_user_input_to_goal.output_type(), "String");
+        assert_eq!(print_backend_webserver_code.input_names(), &["project_description", "code_template", "language", "allowed_libraries"]);
+        assert_eq!(print_improved_webserver_code.input_type(), "(&ProjectSpec, &str, &str, &str)");
+    }
+
+    #[test]
+    fn tests_ai_function_prompt_snapshots() {
+        insta::assert_snapshot!("print_project_scope", print_project_scope.prompt());
+        insta::assert_snapshot!("print_site_urls", print_site_urls.prompt());
+        insta::assert_snapshot!("convert_user_input_to_goal", convert_user_input_to_goal.prompt());
+        insta::assert_snapshot!("print_backend_webserver_code", print_backend_webserver_code.prompt());
+        insta::assert_snapshot!("print_improved_webserver_code", print_improved_webserver_code.prompt());
+        insta::assert_snapshot!("print_fixed_code", print_fixed_code.prompt());
+        insta::assert_snapshot!("print_rest_api_endpoints", print_rest_api_endpoints.prompt());
+        insta::assert_snapshot!("print_frontend_code", print_frontend_code.prompt());
+    }
+
+    #[test]
+    fn tests_ai_function_typed_output() {
+        let scope: ProjectScope = print_project_scope
+            .parse_output(r#"{"is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false}"#)
+            .unwrap();
+        assert!(scope.is_crud_required);
+
+        let urls: Vec<String> = print_site_urls
+            .parse_output(r#"["https://api.binance.com/api/v3/exchangeInfo"]"#)
+            .unwrap();
+        assert_eq!(urls.len(), 1);
+
+        let goal: String = convert_user_input_to_goal
+            .parse_output("build a website that tracks todos")
+            .unwrap();
+        assert_eq!(goal, "build a website that tracks todos");
+    }
+
+    #[test]
+    fn tests_ai_function_few_shot_examples() {
+        let examples: Vec<FewShotExample<ProjectScope>> = print_project_scope.examples();
+        assert_eq!(examples.len(), 2);
+        assert!(examples[0].output.is_external_urls_required);
+
+        // Structured outputs are shown as JSON the LLM can copy the shape of
+        let rendered: Vec<FewShotExample<String>> = print_project_scope.rendered_examples();
+        assert_eq!(print_project_scope.parse_output(&rendered[1].output).unwrap(), examples[1].output);
+
+        for goal in convert_user_input_to_goal.rendered_examples() {
+            assert!(goal.output.starts_with("build a website that"));
+        }
+        assert!(print_site_urls.examples().is_empty());
     }
 }
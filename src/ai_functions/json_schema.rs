@@ -0,0 +1,299 @@
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+// Types that can describe themselves to the LLM as a JSON Schema.
+// Derive it with `#[derive(JsonSchema)]` from `ai_func_proc_macro`.
+pub trait JsonSchema {
+    fn json_schema() -> Value;
+}
+
+impl JsonSchema for bool {
+    fn json_schema() -> Value {
+        json!({ "type": "boolean" })
+    }
+}
+
+impl JsonSchema for String {
+    fn json_schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+impl JsonSchema for str {
+    fn json_schema() -> Value {
+        json!({ "type": "string" })
+    }
+}
+
+macro_rules! impl_json_schema_number {
+    ($schema_type: literal, $($ty: ty),*) => {
+        $(
+            impl JsonSchema for $ty {
+                fn json_schema() -> Value {
+                    json!({ "type": $schema_type })
+                }
+            }
+        )*
+    };
+}
+
+impl_json_schema_number!("integer", i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+impl_json_schema_number!("number", f32, f64);
+
+// Any JSON value is accepted
+impl JsonSchema for Value {
+    fn json_schema() -> Value {
+        json!({})
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Option<T> {
+    fn json_schema() -> Value {
+        json!({ "anyOf": [T::json_schema(), { "type": "null" }] })
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Vec<T> {
+    fn json_schema() -> Value {
+        json!({ "type": "array", "items": T::json_schema() })
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for [T] {
+    fn json_schema() -> Value {
+        json!({ "type": "array", "items": T::json_schema() })
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for HashMap<String, T> {
+    fn json_schema() -> Value {
+        json!({ "type": "object", "additionalProperties": T::json_schema() })
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for BTreeMap<String, T> {
+    fn json_schema() -> Value {
+        json!({ "type": "object", "additionalProperties": T::json_schema() })
+    }
+}
+
+impl<T: JsonSchema + ?Sized> JsonSchema for &T {
+    fn json_schema() -> Value {
+        T::json_schema()
+    }
+}
+
+// A single mismatch between a value and its schema, located by a JSON path like `$.routes[0].method`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaError {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+// Validates a value against the subset of JSON Schema produced by `JsonSchema`:
+// `type`, `enum`, `anyOf`, `properties`, `required`, `additionalProperties` and `items`
+pub fn validate(schema: &Value, value: &Value) -> Result<(), Vec<SchemaError>> {
+    let mut errors: Vec<SchemaError> = Vec::new();
+    validate_at("$", schema, value, &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn validate_at(path: &str, schema: &Value, value: &Value, errors: &mut Vec<SchemaError>) {
+    if let Some(any_of) = schema.get("anyOf").and_then(Value::as_array) {
+        let mut branch_errors: Vec<Vec<SchemaError>> = Vec::new();
+        for branch in any_of {
+            let mut errs: Vec<SchemaError> = Vec::new();
+            validate_at(path, branch, value, &mut errs);
+            if errs.is_empty() {
+                return;
+            }
+            branch_errors.push(errs);
+        }
+
+        // Report the branch that got the furthest, null branches are rarely the intended one
+        if let Some(best) = branch_errors
+            .into_iter()
+            .min_by_key(|errs| errs.iter().filter(|e| e.path == path).count())
+        {
+            errors.extend(best);
+        }
+        return;
+    }
+
+    if let Some(expected) = schema.get("type") {
+        let type_names: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !type_names.iter().any(|name| matches_type(name, value)) {
+            errors.push(SchemaError {
+                path: path.to_string(),
+                message: format!(
+                    "expected {}, found {}",
+                    type_names.join(" or "),
+                    value_type_name(value)
+                ),
+            });
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            errors.push(SchemaError {
+                path: path.to_string(),
+                message: format!(
+                    "expected one of {}, found {}",
+                    Value::Array(allowed.clone()),
+                    value
+                ),
+            });
+        }
+    }
+
+    if let Value::Object(fields) = value {
+        let properties = schema.get("properties").and_then(Value::as_object);
+
+        if let Some(required) = schema.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    errors.push(SchemaError {
+                        path: format!("{}.{}", path, name),
+                        message: "missing required field".to_string(),
+                    });
+                }
+            }
+        }
+
+        for (name, field_value) in fields {
+            let field_path: String = format!("{}.{}", path, name);
+            match (properties.and_then(|props| props.get(name)), schema.get("additionalProperties")) {
+                (Some(field_schema), _) => validate_at(&field_path, field_schema, field_value, errors),
+                (None, Some(Value::Bool(false))) => errors.push(SchemaError {
+                    path: field_path,
+                    message: "unexpected field".to_string(),
+                }),
+                (None, Some(extra_schema @ Value::Object(_))) => {
+                    validate_at(&field_path, extra_schema, field_value, errors)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_at(&format!("{}[{}]", path, i), item_schema, item, errors);
+        }
+    }
+}
+
+fn matches_type(name: &str, value: &Value) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+fn value_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::base::agent_traits::{ProjectScope, ProjectSpec, RouteObject};
+
+    #[test]
+    fn tests_derived_schema() {
+        let schema: Value = ProjectScope::json_schema();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["properties"]["is_crud_required"]["type"], "boolean");
+        assert_eq!(schema["required"].as_array().unwrap().len(), 3);
+        assert!(schema["properties"]["is_crud_required"]["description"].is_string());
+
+        let spec_schema: Value = ProjectSpec::json_schema();
+        assert_eq!(spec_schema["required"], json!([]));
+        assert_eq!(
+            spec_schema["properties"]["api_endpoint_schema"]["anyOf"][0]["items"],
+            RouteObject::json_schema()
+        );
+    }
+
+    #[test]
+    fn tests_validate_accepts_matching_value() {
+        let value: Value = json!({
+            "is_crud_required": true,
+            "is_user_login_and_logout": false,
+            "is_external_urls_required": false
+        });
+        assert_eq!(validate(&ProjectScope::json_schema(), &value), Ok(()));
+    }
+
+    #[test]
+    fn tests_validate_reports_field_errors() {
+        let value: Value = json!({
+            "is_crud_required": "yes",
+            "is_external_urls_required": false,
+            "is_fancy": true
+        });
+
+        let errors: Vec<String> = validate(&ProjectScope::json_schema(), &value)
+            .unwrap_err()
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "$.is_user_login_and_logout: missing required field",
+                "$.is_crud_required: expected boolean, found string",
+                "$.is_fancy: unexpected field",
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_validate_nested_arrays_and_options() {
+        let value: Value = json!({
+            "api_endpoint_schema": [{
+                "is_route_dynamic": "false",
+                "method": 1,
+                "request_body": null,
+                "response": {},
+                "route": "/todos"
+            }]
+        });
+
+        let errors: Vec<SchemaError> = validate(&ProjectSpec::json_schema(), &value).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "$.api_endpoint_schema[0].method");
+        assert_eq!(errors[0].message, "expected string, found number");
+    }
+}
@@ -1 +1,4 @@
+pub mod ai_function;
 pub mod ai_functions;
+pub mod json_schema;
+pub mod prompt_library;
@@ -0,0 +1,335 @@
+use crate::utils::language::ProjectLanguage;
+use dotenv::dotenv;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+// Prompt overrides loaded from a directory with one `<ai function name>.txt` file per function.
+// Overrides may use `{{variable}}` placeholders, which are filled in from the function input
+// and from the library variables. Without an override the built-in prompt is used.
+// Named prompt versions, e.g. for A/B comparisons, live next to them as `<name>@<version>.txt`.
+#[derive(Debug)]
+pub struct PromptLibrary {
+    prompt_dir: Option<PathBuf>,
+    // PROMPT_HOT_RELOAD, for long-lived modes re-reading an override whenever its file changes
+    hot_reload: bool,
+    variables: HashMap<String, String>,
+    // ALLOWED_CRATES, replacing the allowed libraries of Rust projects
+    allowed_crates: Option<String>,
+    cache: HashMap<String, CachedPrompt>,
+}
+
+// A rendered prompt and the version that produced it, `<version>` of the built-in or named
+// version, or `override-<hash>` for an unversioned override file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedPrompt {
+    pub text: String,
+    pub version: String,
+}
+
+#[derive(Debug)]
+struct CachedPrompt {
+    modified: Option<SystemTime>,
+    text: Option<String>,
+}
+
+impl PromptLibrary {
+    pub fn new(prompt_dir: Option<PathBuf>, hot_reload: bool, allowed_crates: Option<String>) -> Self {
+        let mut library: Self = Self {
+            prompt_dir,
+            hot_reload,
+            variables: HashMap::new(),
+            allowed_crates,
+            cache: HashMap::new(),
+        };
+        library.set_language(ProjectLanguage::Rust);
+        library
+    }
+
+    // Reads PROMPT_DIR, PROMPT_HOT_RELOAD and ALLOWED_CRATES from the environment
+    pub fn from_env() -> Self {
+        dotenv().ok();
+
+        let prompt_dir: Option<PathBuf> = env::var("PROMPT_DIR").ok().map(PathBuf::from);
+        let hot_reload: bool = env::var("PROMPT_HOT_RELOAD")
+            .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false);
+
+        Self::new(prompt_dir, hot_reload, env::var("ALLOWED_CRATES").ok())
+    }
+
+    // Fills `{{allowed_crates}}` with the libraries the generated code of the language may use
+    pub fn set_language(&mut self, language: ProjectLanguage) {
+        let allowed_libraries: String = match (language, &self.allowed_crates) {
+            (ProjectLanguage::Rust, Some(allowed_crates)) => allowed_crates.clone(),
+            _ => language.allowed_libraries().to_string(),
+        };
+        self.set_variable("allowed_crates", &allowed_libraries);
+    }
+
+    pub fn set_variable(&mut self, name: &str, value: &str) {
+        self.variables.insert(name.to_string(), value.to_string());
+    }
+
+    // Returns the text of a prompt file (`<name>` or `<name>@<version>`), if there is one
+    pub fn template(&mut self, name: &str) -> Option<String> {
+        let prompt_dir: &PathBuf = self.prompt_dir.as_ref()?;
+
+        if let Some(cached) = self.cache.get(name) {
+            if !self.hot_reload {
+                return cached.text.clone();
+            }
+        }
+
+        let path: PathBuf = prompt_dir.join(format!("{}.txt", name));
+        let modified: Option<SystemTime> = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .ok();
+
+        match self.cache.get(name) {
+            Some(cached) if cached.modified == modified => cached.text.clone(),
+            _ => {
+                let text: Option<String> = fs::read_to_string(&path).ok();
+                self.cache.insert(
+                    name.to_string(),
+                    CachedPrompt {
+                        modified,
+                        text: text.clone(),
+                    },
+                );
+                text
+            }
+        }
+    }
+
+    // Prompt for an ai function with `{{variable}}` placeholders filled in, input variables win
+    // over library variables. Without a requested version the override is used if present,
+    // otherwise the built-in text. Other versions than the built-in one come from their files.
+    pub fn render(
+        &mut self,
+        name: &str,
+        builtin: &str,
+        builtin_version: &str,
+        version: Option<&str>,
+        input_vars: &[(&str, &str)],
+    ) -> Result<ResolvedPrompt, String> {
+        let (template, resolved_version): (String, String) = match version {
+            None => match self.template(name) {
+                Some(text) => {
+                    let version: String = format!("override-{:08x}", fnv1a_hash(&text) >> 32);
+                    (text, version)
+                }
+                None => (builtin.to_string(), builtin_version.to_string()),
+            },
+            Some(version) if version == builtin_version => {
+                (builtin.to_string(), builtin_version.to_string())
+            }
+            Some(version) => match self.template(&format!("{}@{}", name, version)) {
+                Some(text) => (text, version.to_string()),
+                None => {
+                    return Err(format!(
+                        "No prompt version {} of {}, expected {}@{}.txt in the prompt directory",
+                        version, name, name, version
+                    ))
+                }
+            },
+        };
+
+        let text: String = substitute_variables(&template, |var| {
+            input_vars
+                .iter()
+                .find(|(input_name, _)| *input_name == var)
+                .map(|(_, value)| value.to_string())
+                .or_else(|| self.variables.get(var).cloned())
+        });
+
+        Ok(ResolvedPrompt {
+            text,
+            version: resolved_version,
+        })
+    }
+}
+
+// Stable across builds and platforms, unlike `DefaultHasher`
+fn fnv1a_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// Replaces `{{ name }}` placeholders, unknown placeholders are left untouched
+fn substitute_variables(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut rendered: String = String::with_capacity(template.len());
+    let mut rest: &str = template;
+
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after_open: &str = &rest[start + 2..];
+
+        match after_open.find("}}") {
+            Some(end) => {
+                let placeholder: &str = &rest[start..start + 2 + end + 2];
+                match lookup(after_open[..end].trim()) {
+                    Some(value) => rendered.push_str(&value),
+                    None => rendered.push_str(placeholder),
+                }
+                rest = &after_open[end + 2..];
+            }
+            None => {
+                rest = &rest[start..];
+                break;
+            }
+        }
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+fn prompt_library() -> &'static Mutex<PromptLibrary> {
+    static PROMPT_LIBRARY: OnceLock<Mutex<PromptLibrary>> = OnceLock::new();
+    PROMPT_LIBRARY.get_or_init(|| Mutex::new(PromptLibrary::from_env()))
+}
+
+pub fn render_prompt(
+    name: &str,
+    builtin: &str,
+    builtin_version: &str,
+    version: Option<&str>,
+    input_vars: &[(&str, &str)],
+) -> Result<ResolvedPrompt, String> {
+    prompt_library()
+        .lock()
+        .expect("Prompt library lock poisoned")
+        .render(name, builtin, builtin_version, version, input_vars)
+}
+
+pub fn set_prompt_variable(name: &str, value: &str) {
+    prompt_library()
+        .lock()
+        .expect("Prompt library lock poisoned")
+        .set_variable(name, value);
+}
+
+pub fn set_prompt_language(language: ProjectLanguage) {
+    prompt_library()
+        .lock()
+        .expect("Prompt library lock poisoned")
+        .set_language(language);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn temp_prompt_dir(test_name: &str) -> PathBuf {
+        let dir: PathBuf = env::temp_dir().join(format!("autumn_prompts_{}_{}", test_name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn tests_substitute_variables() {
+        let rendered: String = substitute_variables(
+            "Build {{ project_description }} with {{allowed_crates}} {{unknown}} {{",
+            |var| match var {
+                "project_description" => Some("a todo app".to_string()),
+                "allowed_crates" => Some("actix-web".to_string()),
+                _ => None,
+            },
+        );
+        assert_eq!(rendered, "Build a todo app with actix-web {{unknown}} {{");
+    }
+
+    #[test]
+    fn tests_prompt_falls_back_to_builtin() {
+        let mut library: PromptLibrary = PromptLibrary::new(Some(temp_prompt_dir("fallback")), false, None);
+        let rendered: ResolvedPrompt = library
+            .render("print_site_urls", "Built-in with {{allowed_crates}}", "1", None, &[])
+            .unwrap();
+        assert_eq!(rendered.text, format!("Built-in with {}", ProjectLanguage::Rust.allowed_libraries()));
+        assert_eq!(rendered.version, "1");
+    }
+
+    #[test]
+    fn tests_allowed_crates_of_language() {
+        let dir: PathBuf = temp_prompt_dir("language");
+        fs::write(dir.join("print_site_urls.txt"), "Use {{allowed_crates}}").unwrap();
+        let mut library: PromptLibrary = PromptLibrary::new(Some(dir), false, Some("axum, serde".to_string()));
+        assert_eq!(library.render("print_site_urls", "Built-in", "1", None, &[]).unwrap().text, "Use axum, serde");
+
+        library.set_language(ProjectLanguage::Python);
+        let rendered: ResolvedPrompt = library.render("print_site_urls", "Built-in", "1", None, &[]).unwrap();
+        assert_eq!(rendered.text, format!("Use {}", ProjectLanguage::Python.allowed_libraries()));
+
+        library.set_language(ProjectLanguage::Rust);
+        let rendered: ResolvedPrompt = library.render("print_site_urls", "Built-in", "1", None, &[]).unwrap();
+        assert_eq!(rendered.text, "Use axum, serde");
+    }
+
+    #[test]
+    fn tests_prompt_override_with_variables() {
+        let dir: PathBuf = temp_prompt_dir("override");
+        fs::write(dir.join("print_project_scope.txt"), "Scope of {{project_description}} using {{code_template}}").unwrap();
+
+        let mut library: PromptLibrary = PromptLibrary::new(Some(dir), false, None);
+        library.set_variable("code_template", "main.rs");
+        let rendered: ResolvedPrompt = library
+            .render(
+                "print_project_scope",
+                "Built-in",
+                "1",
+                None,
+                &[("project_description", "a todo app")],
+            )
+            .unwrap();
+        assert_eq!(rendered.text, "Scope of a todo app using main.rs");
+        assert!(rendered.version.starts_with("override-"));
+    }
+
+    #[test]
+    fn tests_prompt_versions() {
+        let dir: PathBuf = temp_prompt_dir("versions");
+        fs::write(dir.join("print_backend_webserver_code@2.txt"), "Version 2 for {{project_description}}").unwrap();
+
+        let mut library: PromptLibrary = PromptLibrary::new(Some(dir), false, None);
+        let vars: [(&str, &str); 1] = [("project_description", "a todo app")];
+
+        let v1: ResolvedPrompt = library
+            .render("print_backend_webserver_code", "Built-in", "1", Some("1"), &vars)
+            .unwrap();
+        assert_eq!(v1, ResolvedPrompt { text: "Built-in".to_string(), version: "1".to_string() });
+
+        let v2: ResolvedPrompt = library
+            .render("print_backend_webserver_code", "Built-in", "1", Some("2"), &vars)
+            .unwrap();
+        assert_eq!(v2, ResolvedPrompt { text: "Version 2 for a todo app".to_string(), version: "2".to_string() });
+
+        assert!(library
+            .render("print_backend_webserver_code", "Built-in", "1", Some("3"), &vars)
+            .is_err());
+    }
+
+    #[test]
+    fn tests_prompt_hot_reload() {
+        let dir: PathBuf = temp_prompt_dir("hot_reload");
+        let path: PathBuf = dir.join("convert_user_input_to_goal.txt");
+        fs::write(&path, "Version 1").unwrap();
+
+        let mut library: PromptLibrary = PromptLibrary::new(Some(dir), false, None);
+        assert_eq!(library.template("convert_user_input_to_goal").as_deref(), Some("Version 1"));
+
+        // Bump the modified time explicitly so the change is seen regardless of timestamp resolution
+        fs::write(&path, "Version 2").unwrap();
+        let file: fs::File = fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+
+        assert_eq!(library.template("convert_user_input_to_goal").as_deref(), Some("Version 1"));
+        library.hot_reload = true;
+        assert_eq!(library.template("convert_user_input_to_goal").as_deref(), Some("Version 2"));
+    }
+}
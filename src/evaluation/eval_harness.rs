@@ -0,0 +1,927 @@
+use crate::ai_functions::ai_function::{prompt_value, AiFunction};
+use crate::ai_functions::prompt_library::set_prompt_language;
+use crate::ai_functions::ai_functions::{
+    convert_user_input_to_goal, print_backend_webserver_code, print_fixed_code, print_frontend_code,
+    print_improved_webserver_code, print_project_scope, print_rest_api_endpoints, print_site_urls,
+};
+use crate::models::general::llm::{APIUsage, LLMCompletion, Message};
+use crate::utils::command_line::PrintMessage;
+use crate::utils::general::{build_code_in_copy, BuildOutput};
+use crate::utils::language::ProjectLanguage;
+use crate::utils::llm_apis::{ai_function_message, call_gpt_with_model};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+// A golden case: an input for the ai function plus the expected output, the expected
+// properties of the output, or both. Stored as one JSON file per case, e.g.
+//
+//   {
+//     "input": "I need a simple TODO app",
+//     "expected": { "is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false },
+//     "expected_properties": { "is_crud_required": true }
+//   }
+//
+// `input` is a string for single input functions or an object keyed by input name.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct EvalCase {
+    #[serde(default)]
+    pub name: String,
+    pub input: Value,
+    #[serde(default)]
+    pub expected: Option<Value>,
+    #[serde(default)]
+    pub expected_properties: Option<BTreeMap<String, Value>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EvalOptions {
+    pub cases_dir: PathBuf,
+    pub ai_function: String,
+    pub model: String,
+    // Replays the responses recorded by a previous online run instead of calling the LLM
+    pub offline: bool,
+    pub report_path: Option<PathBuf>,
+    // Prompt version to evaluate, the active prompt when not set
+    pub version: Option<String>,
+    // Builds the generated code of code printing functions and counts the compile successes
+    pub compile: bool,
+    // Language the generated code is built as
+    pub language: ProjectLanguage,
+}
+
+// Options of `autumn eval compare`, running the same cases against two prompt versions
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    pub eval: EvalOptions,
+    pub version_a: String,
+    pub version_b: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldMismatch {
+    pub field: String,
+    pub expected: Value,
+    pub actual: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CaseResult {
+    pub name: String,
+    pub passed: bool,
+    pub error: Option<String>,
+    pub mismatches: Vec<FieldMismatch>,
+    pub prompt_version: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub usage: Option<APIUsage>,
+    pub compiled: Option<bool>,
+}
+
+// Stored next to a recorded response, so offline runs still report the version, tokens and latency
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct RecordingMeta {
+    prompt_version: String,
+    latency_ms: u64,
+    usage: Option<APIUsage>,
+}
+
+struct CaseRun {
+    output: Result<Value, String>,
+    prompt_version: Option<String>,
+    latency_ms: Option<u64>,
+    usage: Option<APIUsage>,
+    compiled: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldAccuracy {
+    pub correct: usize,
+    pub total: usize,
+    pub accuracy: f64,
+}
+
+// Written as pretty JSON with sorted keys and cases, so reports of two prompt versions diff cleanly
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EvalReport {
+    pub ai_function: String,
+    pub output_type: String,
+    pub model: String,
+    pub offline: bool,
+    pub prompt_version: Option<String>,
+    pub cases: usize,
+    pub passed: usize,
+    pub pass_rate: f64,
+    pub compile_success_rate: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub total_tokens: u32,
+    pub field_accuracy: BTreeMap<String, FieldAccuracy>,
+    pub results: Vec<CaseResult>,
+}
+
+impl EvalReport {
+    pub fn print_summary(&self) {
+        let position: &str = "Evaluation";
+        PrintMessage::Info.print_agent_msg(
+            position,
+            &format!(
+                "{} on {}: {}/{} cases passed ({:.1}%)",
+                self.ai_function,
+                self.model,
+                self.passed,
+                self.cases,
+                self.pass_rate * 100.0
+            ),
+        );
+        PrintMessage::Info.print_agent_msg(position, &format!("  prompt version: {}", display_metric(&self.prompt_version)));
+        if let Some(rate) = self.compile_success_rate {
+            PrintMessage::Info.print_agent_msg(position, &format!("  compile success: {:.1}%", rate * 100.0));
+        }
+
+        for (field, accuracy) in &self.field_accuracy {
+            PrintMessage::Info.print_agent_msg(
+                position,
+                &format!(
+                    "  {}: {}/{} ({:.1}%)",
+                    field,
+                    accuracy.correct,
+                    accuracy.total,
+                    accuracy.accuracy * 100.0
+                ),
+            );
+        }
+
+        for result in self.results.iter().filter(|result| !result.passed) {
+            let reason: String = match &result.error {
+                Some(error) => error.clone(),
+                None => result
+                    .mismatches
+                    .iter()
+                    .map(|m| format!("{} expected {} got {}", m.field, m.expected, m.actual))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            };
+            PrintMessage::Error.print_agent_msg(position, &format!("  FAILED {}: {}", result.name, reason));
+        }
+    }
+
+    fn version_summary(&self) -> VersionSummary {
+        VersionSummary {
+            prompt_version: self.prompt_version.clone(),
+            cases: self.cases,
+            pass_rate: self.pass_rate,
+            compile_success_rate: self.compile_success_rate,
+            avg_latency_ms: self.avg_latency_ms,
+            total_tokens: self.total_tokens,
+            avg_tokens_per_case: (self.cases > 0).then(|| self.total_tokens as f64 / self.cases as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VersionSummary {
+    pub prompt_version: Option<String>,
+    pub cases: usize,
+    pub pass_rate: f64,
+    pub compile_success_rate: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub total_tokens: u32,
+    pub avg_tokens_per_case: Option<f64>,
+}
+
+// Side by side metrics of two prompt versions of the same ai function
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PromptComparison {
+    pub ai_function: String,
+    pub model: String,
+    pub a: VersionSummary,
+    pub b: VersionSummary,
+}
+
+impl PromptComparison {
+    pub fn print_summary(&self) {
+        let position: &str = "Evaluation";
+        PrintMessage::Info.print_agent_msg(
+            position,
+            &format!(
+                "{} on {}: {} vs {}",
+                self.ai_function,
+                self.model,
+                display_metric(&self.a.prompt_version),
+                display_metric(&self.b.prompt_version)
+            ),
+        );
+
+        let percent = |rate: Option<f64>| rate.map(|rate| format!("{:.1}%", rate * 100.0));
+        let rows: [(&str, Option<String>, Option<String>); 5] = [
+            ("pass rate", percent(Some(self.a.pass_rate)), percent(Some(self.b.pass_rate))),
+            ("compile success", percent(self.a.compile_success_rate), percent(self.b.compile_success_rate)),
+            ("avg latency ms", self.a.avg_latency_ms.map(|ms| format!("{:.0}", ms)), self.b.avg_latency_ms.map(|ms| format!("{:.0}", ms))),
+            ("total tokens", Some(self.a.total_tokens.to_string()), Some(self.b.total_tokens.to_string())),
+            ("avg tokens per case", self.a.avg_tokens_per_case.map(|t| format!("{:.1}", t)), self.b.avg_tokens_per_case.map(|t| format!("{:.1}", t))),
+        ];
+        for (metric, a, b) in rows {
+            PrintMessage::Info.print_agent_msg(
+                position,
+                &format!("  {:<20} {:>12} {:>12}", metric, display_metric(&a), display_metric(&b)),
+            );
+        }
+    }
+}
+
+fn display_metric(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("-")
+}
+
+// Usage: autumn eval <cases_dir> --function <ai_function> [--model <model>] [--version <version>]
+//                   [--compile] [--language <language>] [--offline] [--report <path>]
+pub fn parse_eval_args(args: &[String]) -> Result<EvalOptions, String> {
+    let mut cases_dir: Option<PathBuf> = None;
+    let mut ai_function: Option<String> = None;
+    let mut model: Option<String> = std::env::var("LLM_MODEL").ok();
+    let mut offline: bool = false;
+    let mut report_path: Option<PathBuf> = None;
+    let mut version: Option<String> = None;
+    let mut compile: bool = false;
+    let mut language: ProjectLanguage = ProjectLanguage::Rust;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("Missing value for {}", flag))
+        };
+        match arg.as_str() {
+            "--function" => ai_function = Some(value("--function")?),
+            "--model" => model = Some(value("--model")?),
+            "--report" => report_path = Some(PathBuf::from(value("--report")?)),
+            "--version" => version = Some(value("--version")?),
+            "--offline" => offline = true,
+            "--compile" => compile = true,
+            "--language" => language = value("--language")?.parse()?,
+            flag if flag.starts_with("--") => return Err(format!("Unknown option {}", flag)),
+            dir => cases_dir = Some(PathBuf::from(dir)),
+        }
+    }
+
+    let usage: &str =
+        "Usage: autumn eval <cases_dir> --function <ai_function> [--model <model>] [--version <version>] [--compile] [--language <language>] [--offline] [--report <path>]";
+    Ok(EvalOptions {
+        cases_dir: cases_dir.ok_or(usage)?,
+        ai_function: ai_function.ok_or(usage)?,
+        model: model.unwrap_or_else(|| "recorded".to_string()),
+        offline,
+        report_path,
+        version,
+        compile,
+        language,
+    })
+}
+
+// Usage: autumn eval compare <cases_dir> --function <ai_function> --a <version> --b <version>
+//                   [--model <model>] [--compile] [--language <language>] [--offline] [--report <path>]
+pub fn parse_compare_args(args: &[String]) -> Result<CompareOptions, String> {
+    let mut version_a: Option<String> = None;
+    let mut version_b: Option<String> = None;
+    let mut eval_args: Vec<String> = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--a" => version_a = Some(args.next().cloned().ok_or("Missing value for --a")?),
+            "--b" => version_b = Some(args.next().cloned().ok_or("Missing value for --b")?),
+            "--version" => return Err("Use --a and --b to choose the compared versions".to_string()),
+            _ => eval_args.push(arg.clone()),
+        }
+    }
+
+    let usage: &str =
+        "Usage: autumn eval compare <cases_dir> --function <ai_function> --a <version> --b <version> [--model <model>] [--compile] [--language <language>] [--offline] [--report <path>]";
+    Ok(CompareOptions {
+        // The usage of `autumn eval` is replaced by the one of `autumn eval compare`, other errors
+        // such as an unknown language are kept
+        eval: parse_eval_args(&eval_args).map_err(|e| match e.starts_with("Usage:") {
+            true => usage.to_string(),
+            false => format!("{}\n{}", e, usage),
+        })?,
+        version_a: version_a.ok_or(usage)?,
+        version_b: version_b.ok_or(usage)?,
+    })
+}
+
+// Runs every case of the cases directory against the chosen ai function and writes the report
+pub async fn run_eval(options: &EvalOptions) -> Result<EvalReport, String> {
+    let cases: Vec<EvalCase> = load_cases(&options.cases_dir)?;
+    set_prompt_language(options.language);
+
+    let report: EvalReport = match options.ai_function.as_str() {
+        "print_project_scope" => eval_ai_function(print_project_scope, &cases, options).await,
+        "print_site_urls" => eval_ai_function(print_site_urls, &cases, options).await,
+        "convert_user_input_to_goal" => eval_ai_function(convert_user_input_to_goal, &cases, options).await,
+        "print_backend_webserver_code" => eval_ai_function(print_backend_webserver_code, &cases, options).await,
+        "print_improved_webserver_code" => eval_ai_function(print_improved_webserver_code, &cases, options).await,
+        "print_fixed_code" => eval_ai_function(print_fixed_code, &cases, options).await,
+        "print_rest_api_endpoints" => eval_ai_function(print_rest_api_endpoints, &cases, options).await,
+        "print_frontend_code" => eval_ai_function(print_frontend_code, &cases, options).await,
+        name => return Err(format!("Unknown ai function: {}", name)),
+    };
+
+    let report_path: PathBuf = options
+        .report_path
+        .clone()
+        .unwrap_or_else(|| options.cases_dir.join(format!("report_{}.json", report_name(options))));
+    write_json(&report_path, &report)?;
+
+    Ok(report)
+}
+
+// Runs the cases against both prompt versions, keeping a report per version, and writes the comparison
+pub async fn run_compare(options: &CompareOptions) -> Result<PromptComparison, String> {
+    let mut summaries: Vec<VersionSummary> = Vec::new();
+    for version in [&options.version_a, &options.version_b] {
+        let version_options: EvalOptions = EvalOptions {
+            version: Some(version.clone()),
+            report_path: None,
+            ..options.eval.clone()
+        };
+        summaries.push(run_eval(&version_options).await?.version_summary());
+    }
+
+    let b: VersionSummary = summaries.pop().expect("Summary of version b");
+    let a: VersionSummary = summaries.pop().expect("Summary of version a");
+    let comparison: PromptComparison = PromptComparison {
+        ai_function: options.eval.ai_function.clone(),
+        model: options.eval.model.clone(),
+        a,
+        b,
+    };
+
+    let compare_path: PathBuf = options.eval.report_path.clone().unwrap_or_else(|| {
+        options.eval.cases_dir.join(format!(
+            "compare_{}_{}_vs_{}.json",
+            options.eval.ai_function, options.version_a, options.version_b
+        ))
+    });
+    write_json(&compare_path, &comparison)?;
+
+    Ok(comparison)
+}
+
+// `<ai function>` or `<ai function>@<version>` when a version was requested
+fn report_name(options: &EvalOptions) -> String {
+    match &options.version {
+        Some(version) => format!("{}@{}", options.ai_function, version),
+        None => options.ai_function.clone(),
+    }
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json: String = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    fs::write(path, json + "\n").map_err(|e| format!("Could not write {}: {}", path.display(), e))
+}
+
+pub fn load_cases(cases_dir: &Path) -> Result<Vec<EvalCase>, String> {
+    let entries = fs::read_dir(cases_dir)
+        .map_err(|e| format!("Could not read cases from {}: {}", cases_dir.display(), e))?;
+
+    let mut cases: Vec<EvalCase> = Vec::new();
+    for entry in entries.flatten() {
+        let path: PathBuf = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json")
+            || path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .map(|name| name.starts_with("report_") || name.starts_with("compare_"))
+                .unwrap_or(false)
+        {
+            continue;
+        }
+
+        let content: String = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        let mut case: EvalCase = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid case {}: {}", path.display(), e))?;
+        if case.name.is_empty() {
+            case.name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .unwrap_or_default()
+                .to_string();
+        }
+        cases.push(case);
+    }
+
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+async fn eval_ai_function<F>(ai_func: F, cases: &[EvalCase], options: &EvalOptions) -> EvalReport
+where
+    F: AiFunction,
+    F::Output: Serialize,
+{
+    let mut results: Vec<CaseResult> = Vec::new();
+    let mut field_counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    // Generated code is built in a copy of the backend project, shared by the cases of the run
+    let build_dir: PathBuf = env::temp_dir().join(format!("autumn_eval_build_{}", process::id()));
+
+    for case in cases {
+        let run: CaseRun = run_case(&ai_func, case, options, &build_dir).await;
+        let mut result: CaseResult = score_case(case, run.output);
+        result.prompt_version = run.prompt_version;
+        result.latency_ms = run.latency_ms;
+        result.usage = run.usage;
+        result.compiled = run.compiled;
+        // Code that does not build fails the case whatever its expectations
+        if result.compiled == Some(false) {
+            result.passed = false;
+        }
+
+        for field in expected_fields(case) {
+            let counts: &mut (usize, usize) = field_counts.entry(field.clone()).or_insert((0, 0));
+            counts.1 += 1;
+            if result.error.is_none() && !result.mismatches.iter().any(|m| m.field == field) {
+                counts.0 += 1;
+            }
+        }
+        results.push(result);
+    }
+    let _ = fs::remove_dir_all(&build_dir);
+
+    let passed: usize = results.iter().filter(|result| result.passed).count();
+    let compiled: Vec<bool> = results.iter().filter_map(|result| result.compiled).collect();
+    let latencies: Vec<u64> = results.iter().filter_map(|result| result.latency_ms).collect();
+    EvalReport {
+        ai_function: ai_func.name().to_string(),
+        output_type: ai_func.output_type().to_string(),
+        model: options.model.clone(),
+        offline: options.offline,
+        prompt_version: results.iter().find_map(|result| result.prompt_version.clone()),
+        cases: results.len(),
+        passed,
+        pass_rate: ratio(passed, results.len()),
+        compile_success_rate: (!compiled.is_empty())
+            .then(|| ratio(compiled.iter().filter(|ok| **ok).count(), compiled.len())),
+        avg_latency_ms: (!latencies.is_empty())
+            .then(|| latencies.iter().sum::<u64>() as f64 / latencies.len() as f64),
+        total_tokens: results
+            .iter()
+            .filter_map(|result| result.usage)
+            .map(|usage| usage.total_tokens)
+            .sum(),
+        field_accuracy: field_counts
+            .into_iter()
+            .map(|(field, (correct, total))| {
+                (
+                    field,
+                    FieldAccuracy {
+                        correct,
+                        total,
+                        accuracy: ratio(correct, total),
+                    },
+                )
+            })
+            .collect(),
+        results,
+    }
+}
+
+async fn run_case<F>(ai_func: &F, case: &EvalCase, options: &EvalOptions, build_dir: &Path) -> CaseRun
+where
+    F: AiFunction,
+    F::Output: Serialize,
+{
+    // Every prompt version gets its own recordings, so both sides of a comparison can be replayed
+    let recordings_dir: PathBuf = options.cases_dir.join("recordings").join(match &options.version {
+        Some(version) => format!("{}@{}", ai_func.name(), version),
+        None => ai_func.name().to_string(),
+    });
+    let recording: PathBuf = recordings_dir.join(format!("{}.txt", case.name));
+    let meta_path: PathBuf = recordings_dir.join(format!("{}.meta.json", case.name));
+
+    let recorded: Result<(String, Option<RecordingMeta>), String> = if options.offline {
+        fs::read_to_string(&recording)
+            .map_err(|e| format!("No recorded response at {}: {}", recording.display(), e))
+            .map(|response| {
+                let meta: Option<RecordingMeta> = fs::read_to_string(&meta_path)
+                    .ok()
+                    .and_then(|meta| serde_json::from_str(&meta).ok());
+                (response, meta)
+            })
+    } else {
+        record_case(ai_func, case, options, &recording, &meta_path).await
+    };
+
+    let (response, meta): (String, Option<RecordingMeta>) = match recorded {
+        Ok(recorded) => recorded,
+        Err(error) => {
+            return CaseRun {
+                output: Err(error),
+                prompt_version: None,
+                latency_ms: None,
+                usage: None,
+                compiled: None,
+            }
+        }
+    };
+
+    let prompt_version: String = match &meta {
+        Some(meta) => meta.prompt_version.clone(),
+        None => format!(
+            "{}@{}",
+            ai_func.name(),
+            options.version.as_deref().unwrap_or(ai_func.version())
+        ),
+    };
+    let output: Result<Value, String> = ai_func
+        .parse_output(&response)
+        .map_err(|e| e.to_string())
+        .and_then(|output| serde_json::to_value(output).map_err(|e| e.to_string()));
+
+    // Only code printing functions produce something to build
+    let compiled: Option<bool> = match &output {
+        Ok(Value::String(code)) if options.compile && ai_func.prints_code() => {
+            let build: BuildOutput = match options.language.code_output_filepath() {
+                Ok(output_file) => build_code_in_copy(code, options.language, &output_file, build_dir).await,
+                Err(e) => BuildOutput { success: false, stdout: String::new(), stderr: e.to_string() },
+            };
+            Some(build.success)
+        }
+        _ => None,
+    };
+
+    CaseRun {
+        output,
+        prompt_version: Some(prompt_version),
+        latency_ms: meta.as_ref().map(|meta| meta.latency_ms),
+        usage: meta.and_then(|meta| meta.usage),
+        compiled,
+    }
+}
+
+// Calls the LLM for a case and records the response so the case can be replayed offline
+async fn record_case<F: AiFunction>(
+    ai_func: &F,
+    case: &EvalCase,
+    options: &EvalOptions,
+    recording: &Path,
+    meta_path: &Path,
+) -> Result<(String, Option<RecordingMeta>), String> {
+    let sections: Vec<(&str, String)> = input_sections(ai_func.input_names(), &case.input)?;
+    let (message, prompt_version): (Message, String) =
+        ai_function_message(ai_func, &sections, options.version.as_deref())?;
+    let completion: LLMCompletion = call_gpt_with_model(vec![message], &options.model)
+        .await
+        .map_err(|e| format!("Error calling the LLM: {}", e))?;
+
+    let meta: RecordingMeta = RecordingMeta {
+        prompt_version,
+        latency_ms: completion.latency_ms as u64,
+        usage: completion.usage,
+    };
+    if let Some(dir) = recording.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(recording, &completion.content).map_err(|e| e.to_string())?;
+    write_json(meta_path, &meta)?;
+
+    Ok((completion.content, Some(meta)))
+}
+
+// Maps the JSON input of a case onto the named inputs of the ai function
+fn input_sections(input_names: &'static [&'static str], input: &Value) -> Result<Vec<(&'static str, String)>, String> {
+    match (input, input_names) {
+        (Value::String(value), [name]) => Ok(vec![(*name, value.clone())]),
+        (Value::Object(fields), names) => names
+            .iter()
+            .map(|name| {
+                fields
+                    .get(*name)
+                    .map(|value| (*name, prompt_value(value)))
+                    .ok_or_else(|| format!("Case input is missing `{}`", name))
+            })
+            .collect(),
+        _ => Err(format!("Case input must be an object with the inputs {:?}", input_names)),
+    }
+}
+
+// Fields checked for a case: `$` for whole non-object outputs, otherwise the top level fields
+fn expected_fields(case: &EvalCase) -> Vec<String> {
+    let mut fields: Vec<String> = Vec::new();
+    match &case.expected {
+        Some(Value::Object(expected)) => fields.extend(expected.keys().cloned()),
+        Some(_) => fields.push("$".to_string()),
+        None => {}
+    }
+    if let Some(properties) = &case.expected_properties {
+        fields.extend(properties.keys().cloned());
+    }
+
+    fields.sort();
+    fields.dedup();
+    fields
+}
+
+fn score_case(case: &EvalCase, output: Result<Value, String>) -> CaseResult {
+    let actual: Value = match output {
+        Ok(actual) => actual,
+        Err(error) => {
+            return CaseResult {
+                name: case.name.clone(),
+                passed: false,
+                error: Some(error),
+                mismatches: Vec::new(),
+                prompt_version: None,
+                latency_ms: None,
+                usage: None,
+                compiled: None,
+            }
+        }
+    };
+
+    let mut expectations: BTreeMap<String, Value> = BTreeMap::new();
+    match &case.expected {
+        Some(Value::Object(expected)) => {
+            expectations.extend(expected.iter().map(|(k, v)| (k.clone(), v.clone())))
+        }
+        Some(expected) => {
+            expectations.insert("$".to_string(), expected.clone());
+        }
+        None => {}
+    }
+    if let Some(properties) = &case.expected_properties {
+        expectations.extend(properties.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    let mismatches: Vec<FieldMismatch> = expectations
+        .into_iter()
+        .filter_map(|(field, expected)| {
+            let actual_field: Value = if field == "$" {
+                actual.clone()
+            } else {
+                actual.get(&field).cloned().unwrap_or(Value::Null)
+            };
+            (actual_field != expected).then_some(FieldMismatch {
+                field,
+                expected,
+                actual: actual_field,
+            })
+        })
+        .collect();
+
+    CaseResult {
+        name: case.name.clone(),
+        passed: mismatches.is_empty(),
+        error: None,
+        mismatches,
+        prompt_version: None,
+        latency_ms: None,
+        usage: None,
+        compiled: None,
+    }
+}
+
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn temp_cases_dir(test_name: &str) -> PathBuf {
+        let dir: PathBuf = std::env::temp_dir().join(format!("autumn_eval_{}_{}", test_name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("recordings").join("print_project_scope")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn tests_parse_eval_args() {
+        let args: Vec<String> = ["cases", "--function", "print_project_scope", "--model", "gpt-4", "--offline"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let options: EvalOptions = parse_eval_args(&args).unwrap();
+        assert_eq!(options.cases_dir, PathBuf::from("cases"));
+        assert_eq!(options.ai_function, "print_project_scope");
+        assert_eq!(options.model, "gpt-4");
+        assert!(options.offline);
+
+        assert!(parse_eval_args(&["cases".to_string()]).is_err());
+    }
+
+    #[test]
+    fn tests_score_case() {
+        let case: EvalCase = EvalCase {
+            name: "todo".to_string(),
+            input: json!("I need a simple TODO app"),
+            expected: Some(json!({ "is_crud_required": true, "is_user_login_and_logout": false })),
+            expected_properties: Some(BTreeMap::from([("is_external_urls_required".to_string(), json!(false))])),
+        };
+
+        let result: CaseResult = score_case(
+            &case,
+            Ok(json!({ "is_crud_required": true, "is_user_login_and_logout": true, "is_external_urls_required": false })),
+        );
+        assert!(!result.passed);
+        assert_eq!(
+            result.mismatches,
+            vec![FieldMismatch {
+                field: "is_user_login_and_logout".to_string(),
+                expected: json!(false),
+                actual: json!(true),
+            }]
+        );
+    }
+
+    #[test]
+    fn tests_input_sections() {
+        assert_eq!(
+            input_sections(&["project_description"], &json!("a todo app")).unwrap(),
+            vec![("project_description", "a todo app".to_string())]
+        );
+        assert_eq!(
+            input_sections(&["project_description", "code_template"], &json!({ "project_description": "a todo app", "code_template": "fn main() {}" })).unwrap(),
+            vec![("project_description", "a todo app".to_string()), ("code_template", "fn main() {}".to_string())]
+        );
+        assert!(input_sections(&["project_description", "code_template"], &json!("a todo app")).is_err());
+    }
+
+    #[tokio::test]
+    async fn tests_run_eval_offline() {
+        let dir: PathBuf = temp_cases_dir("offline");
+        fs::write(
+            dir.join("todo.json"),
+            json!({
+                "input": "I need a simple TODO app",
+                "expected": { "is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("stocks.json"),
+            json!({
+                "input": "I need a website that accepts users and gets stock price data",
+                "expected_properties": { "is_user_login_and_logout": true, "is_external_urls_required": true }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(
+            dir.join("recordings/print_project_scope/todo.txt"),
+            r#"{"is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("recordings/print_project_scope/stocks.txt"),
+            r#"{"is_crud_required": true, "is_user_login_and_logout": true, "is_external_urls_required": false}"#,
+        )
+        .unwrap();
+
+        let options: EvalOptions = EvalOptions {
+            cases_dir: dir.clone(),
+            ai_function: "print_project_scope".to_string(),
+            model: "recorded".to_string(),
+            offline: true,
+            report_path: None,
+            version: None,
+            compile: false,
+            language: ProjectLanguage::Rust,
+        };
+        let report: EvalReport = run_eval(&options).await.unwrap();
+
+        assert_eq!(report.cases, 2);
+        assert_eq!(report.passed, 1);
+        assert_eq!(report.pass_rate, 0.5);
+        assert_eq!(report.field_accuracy["is_external_urls_required"].correct, 1);
+        assert_eq!(report.field_accuracy["is_external_urls_required"].total, 2);
+        assert_eq!(report.field_accuracy["is_crud_required"].accuracy, 1.0);
+        assert_eq!(report.results[0].name, "stocks");
+
+        let written: EvalReport =
+            serde_json::from_str(&fs::read_to_string(dir.join("report_print_project_scope.json")).unwrap()).unwrap();
+        assert_eq!(written, report);
+
+        // A rerun must not pick the report up as a case
+        assert_eq!(run_eval(&options).await.unwrap().cases, 2);
+    }
+
+    #[tokio::test]
+    async fn tests_run_eval_compiles_only_code() {
+        let dir: PathBuf = temp_cases_dir("compile");
+        fs::create_dir_all(dir.join("recordings/convert_user_input_to_goal")).unwrap();
+        fs::write(dir.join("todo.json"), json!({ "input": "I need a simple TODO app" }).to_string()).unwrap();
+        fs::write(
+            dir.join("recordings/convert_user_input_to_goal/todo.txt"),
+            "build a website that tracks todos",
+        )
+        .unwrap();
+
+        let options: EvalOptions = EvalOptions {
+            cases_dir: dir.clone(),
+            ai_function: "convert_user_input_to_goal".to_string(),
+            model: "recorded".to_string(),
+            offline: true,
+            report_path: None,
+            version: None,
+            compile: true,
+            language: ProjectLanguage::Rust,
+        };
+        let report: EvalReport = run_eval(&options).await.unwrap();
+        assert_eq!(report.results[0].compiled, None);
+        assert_eq!(report.compile_success_rate, None);
+        assert!(report.results[0].passed);
+
+        assert!(!convert_user_input_to_goal.prints_code());
+        assert!(print_backend_webserver_code.prints_code());
+        assert!(print_improved_webserver_code.prints_code());
+        assert!(print_fixed_code.prints_code());
+    }
+
+    #[test]
+    fn tests_parse_compare_args() {
+        let args: Vec<String> = ["compare", "cases", "--function", "print_site_urls", "--a", "1", "--b", "2", "--compile", "--language", "python"]
+            .iter()
+            .skip(1)
+            .map(|arg| arg.to_string())
+            .collect();
+        let options: CompareOptions = parse_compare_args(&args).unwrap();
+        assert_eq!(options.eval.ai_function, "print_site_urls");
+        assert_eq!((options.version_a.as_str(), options.version_b.as_str()), ("1", "2"));
+        assert!(options.eval.compile);
+        assert_eq!(options.eval.language, ProjectLanguage::Python);
+
+        assert!(parse_compare_args(&args[..4]).is_err());
+        assert!(parse_compare_args(&args[..1]).unwrap_err().starts_with("Usage: autumn eval compare"));
+
+        let mut unknown_language: Vec<String> = args.clone();
+        unknown_language[9] = "cobol".to_string();
+        let err: String = parse_compare_args(&unknown_language).unwrap_err();
+        assert!(err.contains("cobol"), "{}", err);
+        assert!(err.ends_with("[--report <path>]"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn tests_run_compare_offline() {
+        let dir: PathBuf = temp_cases_dir("compare");
+        fs::write(
+            dir.join("todo.json"),
+            json!({ "input": "I need a simple TODO app", "expected_properties": { "is_crud_required": true } }).to_string(),
+        )
+        .unwrap();
+
+        let responses: [(&str, &str, u64, u32); 2] = [
+            ("1", r#"{"is_crud_required": false, "is_user_login_and_logout": false, "is_external_urls_required": false}"#, 900, 120),
+            ("2", r#"{"is_crud_required": true, "is_user_login_and_logout": false, "is_external_urls_required": false}"#, 600, 80),
+        ];
+        for (version, response, latency_ms, total_tokens) in responses {
+            let recordings: PathBuf = dir.join("recordings").join(format!("print_project_scope@{}", version));
+            fs::create_dir_all(&recordings).unwrap();
+            fs::write(recordings.join("todo.txt"), response).unwrap();
+            let meta: RecordingMeta = RecordingMeta {
+                prompt_version: format!("print_project_scope@{}", version),
+                latency_ms,
+                usage: Some(APIUsage { completion_tokens: 20, prompt_tokens: total_tokens - 20, total_tokens }),
+            };
+            write_json(&recordings.join("todo.meta.json"), &meta).unwrap();
+        }
+
+        let options: CompareOptions = CompareOptions {
+            eval: EvalOptions {
+                cases_dir: dir.clone(),
+                ai_function: "print_project_scope".to_string(),
+                model: "recorded".to_string(),
+                offline: true,
+                report_path: None,
+                version: None,
+                compile: false,
+                language: ProjectLanguage::Rust,
+            },
+            version_a: "1".to_string(),
+            version_b: "2".to_string(),
+        };
+        let comparison: PromptComparison = run_compare(&options).await.unwrap();
+
+        assert_eq!(comparison.a.prompt_version.as_deref(), Some("print_project_scope@1"));
+        assert_eq!(comparison.a.pass_rate, 0.0);
+        assert_eq!(comparison.b.pass_rate, 1.0);
+        assert_eq!(comparison.a.avg_latency_ms, Some(900.0));
+        assert_eq!(comparison.b.total_tokens, 80);
+        assert_eq!(comparison.b.compile_success_rate, None);
+
+        assert!(dir.join("report_print_project_scope@1.json").exists());
+        let written: PromptComparison =
+            serde_json::from_str(&fs::read_to_string(dir.join("compare_print_project_scope_1_vs_2.json")).unwrap()).unwrap();
+        assert_eq!(written, comparison);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod subscribers;
+pub mod workflow_events;
@@ -0,0 +1,251 @@
+use crate::agents::agent_manager::workflow_report::{AgentOutcome, AgentSummary, WorkflowStatus};
+use crate::events::workflow_events::{EventSubscriber, WorkflowEvent};
+use crate::utils::command_line::PrintMessage;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// Prints the progress of the workflow as colored agent messages
+pub struct CliRenderer;
+
+impl EventSubscriber for CliRenderer {
+    fn on_event(&mut self, event: &WorkflowEvent) {
+        let agent: &str = event.agent();
+        match event {
+            WorkflowEvent::AgentStarted { attempt, .. } if *attempt > 1 => {
+                PrintMessage::Info.print_agent_msg(agent, &format!("Starting attempt {}", attempt));
+            }
+            WorkflowEvent::AgentStarted { .. } => PrintMessage::Info.print_agent_msg(agent, "Starting"),
+            // Kept in the transcript, too fine grained for the terminal
+            WorkflowEvent::StateChanged { .. } => {}
+            WorkflowEvent::LlmRequest { function, .. } => PrintMessage::Info.print_agent_msg(agent, function),
+            WorkflowEvent::LlmResponse { function, error: Some(error), .. } => {
+                PrintMessage::Error.print_agent_msg(agent, &format!("LLM call of {} failed: {}", function, error));
+            }
+            WorkflowEvent::LlmResponse { .. } => {}
+            WorkflowEvent::FileWritten { path, .. } => PrintMessage::Info.print_agent_msg(agent, &format!("Wrote {}", path)),
+            WorkflowEvent::BuildResult { success: true, .. } => PrintMessage::Testing.print_agent_msg(agent, "Build succeeded"),
+            WorkflowEvent::BuildResult { success: false, .. } => PrintMessage::Error.print_agent_msg(agent, "Build failed"),
+            WorkflowEvent::TestResult { suite, passed, failures, .. } => {
+                if failures.is_empty() {
+                    PrintMessage::Testing.print_agent_msg(agent, &format!("{}: all {} passed", suite, passed));
+                }
+                for failure in failures {
+                    PrintMessage::Error.print_agent_msg(agent, &format!("{}: {}", suite, failure));
+                }
+            }
+            WorkflowEvent::WorkflowFinished { run_id, status, agents } => render_summary(run_id, *status, agents),
+            WorkflowEvent::Message { level, text, .. } => level.print_agent_msg(agent, text),
+        }
+    }
+}
+
+fn render_summary(run_id: &str, status: WorkflowStatus, agents: &[AgentSummary]) {
+    let position: &str = "Project Manager";
+    let summary: String = match status {
+        WorkflowStatus::Succeeded => format!("Run {} succeeded", run_id),
+        WorkflowStatus::CompletedWithFailures => format!("Run {} completed with failures", run_id),
+        WorkflowStatus::Aborted => format!("Run {} was aborted", run_id),
+    };
+    let message: PrintMessage = match status {
+        WorkflowStatus::Succeeded => PrintMessage::Info,
+        _ => PrintMessage::Error,
+    };
+    message.print_agent_msg(position, &summary);
+
+    for agent in agents {
+        let outcome: &str = match agent.outcome {
+            AgentOutcome::Finished => "finished",
+            AgentOutcome::Failed => "failed",
+            AgentOutcome::NotRun => "not run",
+        };
+        PrintMessage::Info.print_agent_msg(
+            position,
+            &format!("  {}: {} after {} attempts (policy {})", agent.position, outcome, agent.attempts, agent.policy),
+        );
+        for (attempt, error) in agent.errors.iter().enumerate() {
+            PrintMessage::Error.print_agent_msg(&agent.position, &format!("attempt {}: {}", attempt + 1, error));
+        }
+    }
+}
+
+// Appends every event as a JSON line with the time it was emitted in milliseconds, `at_ms`,
+// e.g. to runs/<run id>/transcript.jsonl. A resumed run appends to the transcript it started.
+pub struct TranscriptLogger {
+    path: PathBuf,
+    file: File,
+}
+
+impl TranscriptLogger {
+    pub fn create(path: &Path) -> io::Result<Self> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file: File = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { path: path.to_path_buf(), file })
+    }
+}
+
+impl EventSubscriber for TranscriptLogger {
+    fn on_event(&mut self, event: &WorkflowEvent) {
+        let mut line: serde_json::Value = serde_json::to_value(event).expect("Failed to serialize a workflow event");
+        line["at_ms"] = serde_json::json!(SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or_default());
+        if let Err(e) = writeln!(self.file, "{}", line) {
+            PrintMessage::Error.print_agent_msg(
+                "Project Manager",
+                &format!("Could not write to the transcript {}: {}", self.path.display(), e)
+            );
+        }
+    }
+}
+
+// Totals of a run, counted from its events
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WorkflowMetrics {
+    pub llm_requests: usize,
+    pub llm_failures: usize,
+    // Time spent waiting on the LLM, summed over concurrent agents
+    pub llm_ms: u64,
+    pub builds: usize,
+    pub failed_builds: usize,
+    pub tests_passed: usize,
+    pub tests_failed: usize,
+    pub files_written: usize,
+    pub state_changes: usize,
+}
+
+impl WorkflowMetrics {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} LLM requests ({} failed, {:.1}s), {} builds ({} failed), {} tests passed, {} failed, {} files written",
+            self.llm_requests,
+            self.llm_failures,
+            self.llm_ms as f64 / 1000.0,
+            self.builds,
+            self.failed_builds,
+            self.tests_passed,
+            self.tests_failed,
+            self.files_written
+        )
+    }
+}
+
+impl EventSubscriber for WorkflowMetrics {
+    fn on_event(&mut self, event: &WorkflowEvent) {
+        match event {
+            WorkflowEvent::StateChanged { .. } => self.state_changes += 1,
+            WorkflowEvent::LlmRequest { .. } => self.llm_requests += 1,
+            WorkflowEvent::LlmResponse { elapsed_ms, error, .. } => {
+                self.llm_ms += elapsed_ms;
+                if error.is_some() {
+                    self.llm_failures += 1;
+                }
+            }
+            WorkflowEvent::FileWritten { .. } => self.files_written += 1,
+            WorkflowEvent::BuildResult { success, .. } => {
+                self.builds += 1;
+                if !success {
+                    self.failed_builds += 1;
+                }
+            }
+            WorkflowEvent::TestResult { passed, failures, .. } => {
+                self.tests_passed += passed;
+                self.tests_failed += failures.len();
+            }
+            WorkflowEvent::AgentStarted { .. } | WorkflowEvent::WorkflowFinished { .. } | WorkflowEvent::Message { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::base::agent_base::AgentState;
+
+    fn events() -> Vec<WorkflowEvent> {
+        let agent: String = "Backend Developer".to_string();
+        vec![
+            WorkflowEvent::StateChanged { agent: agent.clone(), from: AgentState::Discovery, to: AgentState::Working, elapsed_ms: 3 },
+            WorkflowEvent::LlmRequest {
+                agent: agent.clone(),
+                function: "print_fixed_code".to_string(),
+                prompt_version: Some("print_fixed_code@1".to_string()),
+                prompt: "Fix the code".to_string(),
+            },
+            WorkflowEvent::LlmResponse {
+                agent: agent.clone(),
+                function: "print_fixed_code".to_string(),
+                elapsed_ms: 1500,
+                response: None,
+                error: Some("timed out".to_string()),
+            },
+            WorkflowEvent::BuildResult { agent: agent.clone(), success: false, errors: Some("E0425".to_string()) },
+            WorkflowEvent::BuildResult { agent: agent.clone(), success: true, errors: None },
+            WorkflowEvent::TestResult {
+                agent: agent.clone(),
+                suite: "backend routes".to_string(),
+                passed: 3,
+                failures: vec!["GET /todos returned 500".to_string()],
+            },
+            WorkflowEvent::FileWritten { agent, path: "web_template/src/main.rs".to_string() },
+        ]
+    }
+
+    #[test]
+    fn tests_workflow_metrics() {
+        let mut metrics: WorkflowMetrics = WorkflowMetrics::default();
+        for event in events() {
+            metrics.on_event(&event);
+        }
+        assert_eq!(
+            metrics,
+            WorkflowMetrics {
+                llm_requests: 1,
+                llm_failures: 1,
+                llm_ms: 1500,
+                builds: 2,
+                failed_builds: 1,
+                tests_passed: 3,
+                tests_failed: 1,
+                files_written: 1,
+                state_changes: 1,
+            }
+        );
+        assert_eq!(
+            metrics.summary(),
+            "1 LLM requests (1 failed, 1.5s), 2 builds (1 failed), 3 tests passed, 1 failed, 1 files written"
+        );
+    }
+
+    #[test]
+    fn tests_transcript_logger() {
+        let path: PathBuf = std::env::temp_dir()
+            .join(format!("autumn_transcript_{}", std::process::id()))
+            .join("transcript.jsonl");
+        let _ = fs::remove_file(&path);
+
+        let mut logger: TranscriptLogger = TranscriptLogger::create(&path).unwrap();
+        for event in events() {
+            logger.on_event(&event);
+        }
+        // A resumed run appends
+        let mut logger: TranscriptLogger = TranscriptLogger::create(&path).unwrap();
+        logger.on_event(&events()[0]);
+
+        let lines: Vec<serde_json::Value> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(lines[1]["event"], "llm_request");
+        assert_eq!(lines[1]["prompt"], "Fix the code");
+        assert_eq!(lines[7]["event"], "state_changed");
+        assert!(lines[7]["at_ms"].as_u64().unwrap() >= lines[0]["at_ms"].as_u64().unwrap());
+    }
+}
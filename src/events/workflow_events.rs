@@ -0,0 +1,151 @@
+use crate::agents::agent_manager::workflow_report::{AgentSummary, WorkflowStatus};
+use crate::agents::base::agent_base::AgentState;
+use crate::utils::command_line::PrintMessage;
+use serde::Serialize;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::mpsc::UnboundedSender;
+
+// Progress of a workflow. Agents emit these instead of printing, the CLI renderer, the transcript
+// of the run, its metrics and external integrations subscribe to them.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WorkflowEvent {
+    // An agent began working, `attempt` counts the runs its failure policy allowed
+    AgentStarted { agent: String, attempt: u8 },
+    StateChanged { agent: String, from: AgentState, to: AgentState, elapsed_ms: u64 },
+    // `prompt_version` is the `<ai function>@<version>` of the prompt, None for plugin prompts
+    LlmRequest { agent: String, function: String, prompt_version: Option<String>, prompt: String },
+    // `response` when the call succeeded, `error` when it failed
+    LlmResponse {
+        agent: String,
+        function: String,
+        elapsed_ms: u64,
+        response: Option<String>,
+        error: Option<String>,
+    },
+    FileWritten { agent: String, path: String },
+    // `errors` are the ones sent back to the LLM when the build failed
+    BuildResult { agent: String, success: bool, errors: Option<String> },
+    // One check of the output of an agent, e.g. calling the backend endpoints
+    TestResult { agent: String, suite: String, passed: usize, failures: Vec<String> },
+    WorkflowFinished { run_id: String, status: WorkflowStatus, agents: Vec<AgentSummary> },
+    // Progress none of the other events describe
+    Message { agent: String, level: PrintMessage, text: String },
+}
+
+impl WorkflowEvent {
+    // Agent the event is about, the manager's position for workflow events
+    pub fn agent(&self) -> &str {
+        match self {
+            Self::AgentStarted { agent, .. }
+            | Self::StateChanged { agent, .. }
+            | Self::LlmRequest { agent, .. }
+            | Self::LlmResponse { agent, .. }
+            | Self::FileWritten { agent, .. }
+            | Self::BuildResult { agent, .. }
+            | Self::TestResult { agent, .. }
+            | Self::Message { agent, .. } => agent,
+            Self::WorkflowFinished { .. } => "Project Manager",
+        }
+    }
+}
+
+// Receives every event emitted after it subscribed. Called while the bus is locked, so a
+// subscriber must not emit events itself.
+pub trait EventSubscriber: Send {
+    fn on_event(&mut self, event: &WorkflowEvent);
+}
+
+// Lets the subscriber be read after the run, e.g. its metrics
+impl<S: EventSubscriber> EventSubscriber for Arc<Mutex<S>> {
+    fn on_event(&mut self, event: &WorkflowEvent) {
+        self.lock().expect("Event subscriber lock poisoned").on_event(event);
+    }
+}
+
+// Typed stream of events for an external integration running in its own task, e.g.
+//
+//   let (sender, mut events) = unbounded_channel();
+//   subscribe(sender);
+//   tokio::spawn(async move { while let Some(event) = events.recv().await { ... } });
+impl EventSubscriber for UnboundedSender<WorkflowEvent> {
+    fn on_event(&mut self, event: &WorkflowEvent) {
+        // A closed stream only means the integration stopped listening
+        let _ = self.send(event.clone());
+    }
+}
+
+pub type SubscriptionId = u64;
+
+#[derive(Default)]
+struct EventBus {
+    next_id: SubscriptionId,
+    subscribers: Vec<(SubscriptionId, Box<dyn EventSubscriber>)>,
+}
+
+fn event_bus() -> &'static Mutex<EventBus> {
+    static EVENT_BUS: OnceLock<Mutex<EventBus>> = OnceLock::new();
+    EVENT_BUS.get_or_init(|| Mutex::new(EventBus::default()))
+}
+
+pub fn subscribe(subscriber: impl EventSubscriber + 'static) -> SubscriptionId {
+    let mut bus = event_bus().lock().expect("Event bus lock poisoned");
+    bus.next_id += 1;
+    let id: SubscriptionId = bus.next_id;
+    bus.subscribers.push((id, Box::new(subscriber)));
+    id
+}
+
+pub fn unsubscribe(id: SubscriptionId) {
+    event_bus()
+        .lock()
+        .expect("Event bus lock poisoned")
+        .subscribers
+        .retain(|(subscriber_id, _)| *subscriber_id != id);
+}
+
+pub fn emit(event: WorkflowEvent) {
+    let mut bus = event_bus().lock().expect("Event bus lock poisoned");
+    for (_, subscriber) in bus.subscribers.iter_mut() {
+        subscriber.on_event(&event);
+    }
+}
+
+pub fn emit_message(level: PrintMessage, agent: &str, text: impl Into<String>) {
+    emit(WorkflowEvent::Message { agent: agent.to_string(), level, text: text.into() });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+    #[test]
+    fn tests_event_stream() {
+        let (sender, mut events): (UnboundedSender<WorkflowEvent>, UnboundedReceiver<WorkflowEvent>) = unbounded_channel();
+        let id: SubscriptionId = subscribe(sender);
+        emit_message(PrintMessage::Info, "Event Stream Tester", "hello");
+        unsubscribe(id);
+        emit_message(PrintMessage::Info, "Event Stream Tester", "after unsubscribing");
+
+        // Other tests emit concurrently on the same bus
+        let mut received: Vec<WorkflowEvent> = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if event.agent() == "Event Stream Tester" {
+                received.push(event);
+            }
+        }
+        assert_eq!(
+            received,
+            vec![WorkflowEvent::Message {
+                agent: "Event Stream Tester".to_string(),
+                level: PrintMessage::Info,
+                text: "hello".to_string(),
+            }]
+        );
+        assert_eq!(
+            serde_json::to_value(&received[0]).unwrap(),
+            serde_json::json!({"event": "message", "agent": "Event Stream Tester", "level": "info", "text": "hello"})
+        );
+    }
+}
@@ -1,22 +1,97 @@
-#[macro_export]
-macro_rules! get_function_string {
-    ($func: ident) => {{
-        stringify!($func)
-    }};
-}
-
-#[macro_use]
 mod ai_functions;
 mod agents;
+mod evaluation;
+mod events;
 mod models;
 mod utils;
 
-use utils::command_line::get_user_input;
+use std::env;
+use std::path::Path;
+use std::process::exit;
+
+use evaluation::eval_harness::{parse_compare_args, parse_eval_args, run_compare, run_eval};
+use utils::command_line::{get_user_input, PrintMessage};
 
-use crate::agents::agent_manager::manager_agent::ManagerAgent;
+use crate::agents::agent_manager::agent_registry::AgentRegistry;
+use crate::agents::agent_manager::manager_agent::{ManagerAgent, ProjectStack};
+use crate::agents::agent_manager::workflow_config::WorkflowConfig;
+use crate::agents::agent_manager::workflow_report::WorkflowStatus;
+use crate::agents::agent_plugin::plugin_agent::plugins_dir;
+use crate::events::subscribers::CliRenderer;
+use crate::events::workflow_events::subscribe;
+use crate::utils::language::ProjectLanguage;
 
 #[tokio::main]
 async fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    // Evaluate an ai function against a directory of golden cases instead of building a website
+    // `autumn eval compare` runs the cases against two prompt versions side by side
+    if args.get(1).map(String::as_str) == Some("eval") && args.get(2).map(String::as_str) == Some("compare") {
+        let result = match parse_compare_args(&args[3..]) {
+            Ok(options) => run_compare(&options).await,
+            Err(usage) => Err(usage),
+        };
+        match result {
+            Ok(comparison) => comparison.print_summary(),
+            Err(e) => {
+                PrintMessage::Error.print_agent_msg("Evaluation", &e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if args.get(1).map(String::as_str) == Some("eval") {
+        let result = match parse_eval_args(&args[2..]) {
+            Ok(options) => run_eval(&options).await,
+            Err(usage) => Err(usage),
+        };
+        match result {
+            Ok(report) => report.print_summary(),
+            Err(e) => {
+                PrintMessage::Error.print_agent_msg("Evaluation", &e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    // Continue an interrupted run from its last checkpoint
+    if args.get(1).map(String::as_str) == Some("resume") {
+        let Some(run_id) = args.get(2) else {
+            PrintMessage::Error.print_agent_msg("Project Manager", "Usage: autumn resume <run-id>");
+            exit(1);
+        };
+        match ManagerAgent::resume(run_id, &agent_registry()) {
+            Ok(mut project_manager) => run_workflow(&mut project_manager).await,
+            Err(e) => {
+                PrintMessage::Error.print_agent_msg("Project Manager", &e.to_string());
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    // `autumn --workflow workflows/fullstack.toml` runs the agents of a workflow config instead of
+    // the ones of the stack
+    let workflow: Option<WorkflowConfig> = match args.iter().position(|arg| arg == "--workflow") {
+        Some(index) => {
+            let loaded: Result<WorkflowConfig, String> = match args.get(index + 1) {
+                Some(path) => WorkflowConfig::load(Path::new(path)),
+                None => Err("Usage: autumn --workflow <workflow.toml|workflow.yaml>".to_string()),
+            };
+            match loaded {
+                Ok(workflow) => Some(workflow),
+                Err(e) => {
+                    PrintMessage::Error.print_agent_msg("Project Manager", &e);
+                    exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
     println!(
         "Welcome to Autumn!\n
         =====================================
@@ -30,16 +105,53 @@ async fn main() {
         get_user_input("Are we building [backend], [frontend], or [fullstack]?", 3);
     let _ = get_user_input("Exit", 4);
 
-    if let Ok(mut project_manager) = ManagerAgent::new() {
-        project_manager.execute_workflow().await;
-    } else {
-        panic!("Error creating Project Manager Agent");
+    let answers: Result<(ProjectLanguage, ProjectStack), String> = prompt_language
+        .parse::<ProjectLanguage>()
+        .and_then(|language| Ok((language, prompt_fullstack.parse::<ProjectStack>()?)));
+    let (language, stack): (ProjectLanguage, ProjectStack) = match answers {
+        Ok(answers) => answers,
+        Err(e) => {
+            PrintMessage::Error.print_agent_msg("Project Manager", &e);
+            exit(1);
+        }
+    };
+
+    let user_req: String = prompt_project.trim().to_string();
+    let manager: Result<ManagerAgent, Box<dyn std::error::Error>> = match workflow {
+        Some(workflow) => ManagerAgent::with_workflow(user_req, language, stack, workflow, &agent_registry()),
+        None => ManagerAgent::new(user_req, language, stack),
+    };
+    match manager {
+        Ok(mut project_manager) => run_workflow(&mut project_manager).await,
+        Err(e) => {
+            PrintMessage::Error.print_agent_msg("Project Manager", &e.to_string());
+            exit(1);
+        }
     }
-    
+}
 
-    println!(
-        "{} {} {}",
-        prompt_project, prompt_language, prompt_fullstack
-    );
+// Built-in agents and the plugins of PLUGINS_DIR, which workflow configs name like built-in agents
+fn agent_registry() -> AgentRegistry {
+    let mut registry: AgentRegistry = AgentRegistry::with_builtin_agents();
+    if let Err(e) = registry.load_plugins(&plugins_dir()) {
+        PrintMessage::Error.print_agent_msg("Project Manager", &e);
+        exit(1);
+    }
+    registry
+}
 
+// Shows the progress of the workflow, exiting with 1 unless every agent finished
+async fn run_workflow(project_manager: &mut ManagerAgent) {
+    subscribe(CliRenderer);
+    match project_manager.execute_workflow().await {
+        Ok(report) => {
+            if report.status() != WorkflowStatus::Succeeded {
+                exit(1);
+            }
+        }
+        Err(e) => {
+            PrintMessage::Error.print_agent_msg("Project Manager", &e.to_string());
+            exit(1);
+        }
+    }
 }
@@ -10,19 +10,28 @@ pub struct APIChoice {
     pub message: APIMessage
 }
 
-// #[derive(Debug, Deserialize)]
-// pub struct APIUsage {
-//     pub completion_tokens: u16,
-//     pub prompt_tokens: u16,
-//     pub total_tokens: u16
-// }
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq)]
+pub struct APIUsage {
+    pub completion_tokens: u32,
+    pub prompt_tokens: u32,
+    pub total_tokens: u32
+}
 
 #[derive(Debug, Deserialize)]
 pub struct APIResponse {
-    pub choices: Vec<APIChoice>
+    pub choices: Vec<APIChoice>,
+    pub usage: Option<APIUsage>
+}
+
+// Content of a completion together with what it cost
+#[derive(Debug, Clone)]
+pub struct LLMCompletion {
+    pub content: String,
+    pub usage: Option<APIUsage>,
+    pub latency_ms: u128
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     pub role: String,
     pub content: String
@@ -0,0 +1,397 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Diagnostics shown to the LLM per fix attempt, the most severe first
+const MAX_DIAGNOSTICS: usize = 10;
+// Lines of code shown around each diagnostic
+const SNIPPET_CONTEXT_LINES: usize = 2;
+
+// Ordered by severity, errors first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+    Note,
+    Help,
+}
+
+impl DiagnosticLevel {
+    fn from_rustc(level: &str) -> Self {
+        match level {
+            "error" | "error: internal compiler error" => Self::Error,
+            "warning" => Self::Warning,
+            "help" => Self::Help,
+            _ => Self::Note,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Note => "note",
+            Self::Help => "help",
+        }
+    }
+}
+
+// Two diagnostics are duplicates when these match
+type DiagnosticKey = (DiagnosticLevel, Option<String>, String, Option<String>, usize);
+
+// A replacement rustc suggests for a byte range of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub file: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub replacement: String,
+    // Only machine applicable suggestions are safe to apply without review
+    pub machine_applicable: bool,
+}
+
+// A compiler message of `cargo build --message-format=json`, located at its primary span
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub level: DiagnosticLevel,
+    pub code: Option<String>,
+    pub message: String,
+    pub file: Option<String>,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub suggestions: Vec<Suggestion>,
+    // The message as rustc prints it, with the code excerpt
+    pub rendered: Option<String>,
+}
+
+// The parts of the cargo and rustc JSON messages that are used
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<RustcMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcMessage {
+    message: String,
+    code: Option<RustcCode>,
+    level: String,
+    #[serde(default)]
+    spans: Vec<RustcSpan>,
+    #[serde(default)]
+    children: Vec<RustcMessage>,
+    #[serde(default)]
+    rendered: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcCode {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RustcSpan {
+    file_name: String,
+    byte_start: usize,
+    byte_end: usize,
+    line_start: usize,
+    line_end: usize,
+    is_primary: bool,
+    suggested_replacement: Option<String>,
+    suggestion_applicability: Option<String>,
+}
+
+impl RustcMessage {
+    // Suggestions of the message itself and of its `help` children
+    fn collect_suggestions(&self, suggestions: &mut Vec<Suggestion>) {
+        for span in &self.spans {
+            if let Some(replacement) = &span.suggested_replacement {
+                suggestions.push(Suggestion {
+                    file: span.file_name.clone(),
+                    byte_start: span.byte_start,
+                    byte_end: span.byte_end,
+                    replacement: replacement.clone(),
+                    machine_applicable: span.suggestion_applicability.as_deref() == Some("MachineApplicable"),
+                });
+            }
+        }
+        for child in &self.children {
+            child.collect_suggestions(suggestions);
+        }
+    }
+}
+
+// Parses the JSON lines cargo prints to stdout into diagnostics, without duplicates and ranked
+// by severity, then by location. Summaries like "aborting due to 2 previous errors" are dropped.
+pub fn parse_diagnostics(cargo_stdout: &str) -> Vec<Diagnostic> {
+    let mut seen: HashSet<DiagnosticKey> = HashSet::new();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    for line in cargo_stdout.lines() {
+        let Ok(cargo_message) = serde_json::from_str::<CargoMessage>(line) else {
+            continue;
+        };
+        let Some(message) = cargo_message.message.filter(|_| cargo_message.reason == "compiler-message") else {
+            continue;
+        };
+
+        let primary: Option<&RustcSpan> = message
+            .spans
+            .iter()
+            .find(|span| span.is_primary)
+            .or(message.spans.first());
+        if primary.is_none() && message.message.starts_with("aborting due to") {
+            continue;
+        }
+
+        let mut suggestions: Vec<Suggestion> = Vec::new();
+        message.collect_suggestions(&mut suggestions);
+
+        let diagnostic: Diagnostic = Diagnostic {
+            level: DiagnosticLevel::from_rustc(&message.level),
+            code: message.code.map(|code| code.code),
+            message: message.message,
+            file: primary.map(|span| span.file_name.clone()),
+            line_start: primary.map(|span| span.line_start).unwrap_or_default(),
+            line_end: primary.map(|span| span.line_end).unwrap_or_default(),
+            suggestions,
+            rendered: message.rendered,
+        };
+
+        let key: DiagnosticKey = (
+            diagnostic.level,
+            diagnostic.code.clone(),
+            diagnostic.message.clone(),
+            diagnostic.file.clone(),
+            diagnostic.line_start,
+        );
+        if seen.insert(key) {
+            diagnostics.push(diagnostic);
+        }
+    }
+
+    diagnostics.sort_by(|a, b| {
+        (a.level, &a.file, a.line_start).cmp(&(b.level, &b.file, b.line_start))
+    });
+    diagnostics
+}
+
+// Applies the machine applicable suggestions to the files of the project, returning how many
+// were applied. Files outside the project and overlapping suggestions are left alone.
+pub fn apply_machine_applicable(project_dir: &Path, diagnostics: &[Diagnostic]) -> io::Result<usize> {
+    let mut by_file: BTreeMap<&str, Vec<&Suggestion>> = BTreeMap::new();
+    for suggestion in diagnostics.iter().flat_map(|diagnostic| &diagnostic.suggestions) {
+        if suggestion.machine_applicable && Path::new(&suggestion.file).is_relative() {
+            by_file.entry(suggestion.file.as_str()).or_default().push(suggestion);
+        }
+    }
+
+    let mut applied: usize = 0;
+    for (file, mut suggestions) in by_file {
+        let path = project_dir.join(file);
+        let mut source: String = fs::read_to_string(&path)?;
+
+        // Replace back to front so the earlier byte offsets stay valid
+        suggestions.sort_by_key(|suggestion| std::cmp::Reverse(suggestion.byte_start));
+        suggestions.dedup_by(|a, b| a == b);
+        let mut applied_from: usize = source.len() + 1;
+        for suggestion in suggestions {
+            if suggestion.byte_end > applied_from
+                || suggestion.byte_end > source.len()
+                || !source.is_char_boundary(suggestion.byte_start)
+                || !source.is_char_boundary(suggestion.byte_end)
+            {
+                continue;
+            }
+            source.replace_range(suggestion.byte_start..suggestion.byte_end, &suggestion.replacement);
+            applied_from = suggestion.byte_start;
+            applied += 1;
+        }
+
+        fs::write(&path, source)?;
+    }
+    Ok(applied)
+}
+
+// Renders the diagnostics for the bug-fix prompt: only errors when there are any, each with
+// the lines of code around it, or as rustc rendered it when the file cannot be read, e.g.
+//
+//   error[E0425]: cannot find value `todos` in this scope
+//     --> src/main.rs:12
+//      11 |     let state = data.lock().unwrap();
+//      12 |     HttpResponse::Ok().json(todos)
+pub fn diagnostic_snippets(project_dir: &Path, diagnostics: &[Diagnostic]) -> String {
+    let has_errors: bool = diagnostics.iter().any(|diagnostic| diagnostic.level == DiagnosticLevel::Error);
+    let mut sources: BTreeMap<&str, Option<String>> = BTreeMap::new();
+
+    diagnostics
+        .iter()
+        .filter(|diagnostic| !has_errors || diagnostic.level == DiagnosticLevel::Error)
+        .take(MAX_DIAGNOSTICS)
+        .map(|diagnostic| {
+            let mut snippet: String = match &diagnostic.code {
+                Some(code) => format!("{}[{}]: {}", diagnostic.level.as_str(), code, diagnostic.message),
+                None => format!("{}: {}", diagnostic.level.as_str(), diagnostic.message),
+            };
+
+            if let Some(file) = &diagnostic.file {
+                let source: &Option<String> = sources
+                    .entry(file.as_str())
+                    .or_insert_with(|| fs::read_to_string(project_dir.join(file)).ok());
+                match (source, &diagnostic.rendered) {
+                    (Some(source), _) => {
+                        snippet.push_str(&format!("\n  --> {}:{}", file, diagnostic.line_start));
+                        let first: usize = diagnostic.line_start.saturating_sub(SNIPPET_CONTEXT_LINES).max(1);
+                        let last: usize = diagnostic.line_end + SNIPPET_CONTEXT_LINES;
+                        for (i, line) in source.lines().enumerate().skip(first - 1).take(last + 1 - first) {
+                            snippet.push_str(&format!("\n  {:>4} | {}", i + 1, line));
+                        }
+                    }
+                    (None, Some(rendered)) => snippet = rendered.trim_end().to_string(),
+                    (None, None) => snippet.push_str(&format!("\n  --> {}:{}", file, diagnostic.line_start)),
+                }
+            }
+            snippet
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+// The diagnostics as rustc rendered them, for builds whose diagnostics gave no snippets
+pub fn rendered_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .filter_map(|diagnostic| diagnostic.rendered.as_deref())
+        .take(MAX_DIAGNOSTICS)
+        .map(str::trim_end)
+        .collect::<Vec<&str>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn compiler_message(level: &str, code: Option<&str>, message: &str, line: usize, byte_start: usize, byte_end: usize, replacement: Option<&str>) -> String {
+        json!({
+            "reason": "compiler-message",
+            "message": {
+                "message": message,
+                "code": code.map(|code| json!({ "code": code })),
+                "level": level,
+                "spans": [{
+                    "file_name": "src/main.rs",
+                    "byte_start": byte_start,
+                    "byte_end": byte_end,
+                    "line_start": line,
+                    "line_end": line,
+                    "is_primary": true,
+                    "suggested_replacement": null,
+                    "suggestion_applicability": null
+                }],
+                "children": [{
+                    "message": "a suggestion",
+                    "code": null,
+                    "level": "help",
+                    "spans": replacement.map(|replacement| vec![json!({
+                        "file_name": "src/main.rs",
+                        "byte_start": byte_start,
+                        "byte_end": byte_end,
+                        "line_start": line,
+                        "line_end": line,
+                        "is_primary": true,
+                        "suggested_replacement": replacement,
+                        "suggestion_applicability": "MachineApplicable"
+                    })]).unwrap_or_default(),
+                    "children": []
+                }],
+                "rendered": format!("{}: {}\n --> src/main.rs:{}:1\n", level, message, line)
+            }
+        })
+        .to_string()
+    }
+
+    fn cargo_stdout() -> String {
+        [
+            json!({ "reason": "compiler-artifact", "target": { "name": "serde" } }).to_string(),
+            compiler_message("warning", Some("unused_mut"), "variable does not need to be mutable", 2, 16, 24, Some("count")),
+            compiler_message("error", Some("E0425"), "cannot find value `todo` in this scope", 3, 40, 44, Some("todos")),
+            compiler_message("error", Some("E0425"), "cannot find value `todo` in this scope", 3, 40, 44, Some("todos")),
+            json!({ "reason": "compiler-message", "message": { "message": "aborting due to 1 previous error", "code": null, "level": "error", "spans": [], "children": [] } }).to_string(),
+            json!({ "reason": "build-finished", "success": false }).to_string(),
+        ]
+        .join("\n")
+    }
+
+    fn temp_project(test_name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("autumn_diagnostics_{}_{}", test_name, std::process::id()));
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(
+            dir.join("src/main.rs"),
+            "fn main() {\n    let mut count = 0;\n    let todos = vec![count];\n    println!(\"{:?}\", todo);\n}\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn tests_parse_diagnostics() {
+        let diagnostics: Vec<Diagnostic> = parse_diagnostics(&cargo_stdout());
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].level, DiagnosticLevel::Error);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("E0425"));
+        assert_eq!(diagnostics[0].file.as_deref(), Some("src/main.rs"));
+        assert_eq!(diagnostics[0].suggestions[0].replacement, "todos");
+        assert_eq!(diagnostics[1].level, DiagnosticLevel::Warning);
+    }
+
+    #[test]
+    fn tests_apply_machine_applicable() {
+        let dir = temp_project("apply");
+        let source: String = fs::read_to_string(dir.join("src/main.rs")).unwrap();
+        let mut stdout: String = String::new();
+        for (level, word, replacement) in [("warning", "mut count", "count"), ("error", "todo)", "todos)")] {
+            let start: usize = source.find(word).unwrap();
+            stdout.push_str(&compiler_message(level, None, "fix me", 1, start, start + word.len(), Some(replacement)));
+            stdout.push('\n');
+        }
+
+        let applied: usize = apply_machine_applicable(&dir, &parse_diagnostics(&stdout)).unwrap();
+        assert_eq!(applied, 2);
+        assert_eq!(
+            fs::read_to_string(dir.join("src/main.rs")).unwrap(),
+            "fn main() {\n    let count = 0;\n    let todos = vec![count];\n    println!(\"{:?}\", todos);\n}\n"
+        );
+    }
+
+    #[test]
+    fn tests_diagnostic_snippets() {
+        let dir = temp_project("snippets");
+        let snippets: String = diagnostic_snippets(&dir, &parse_diagnostics(&cargo_stdout()));
+        assert_eq!(
+            snippets,
+            [
+                "error[E0425]: cannot find value `todo` in this scope",
+                "  --> src/main.rs:3",
+                "     1 | fn main() {",
+                "     2 |     let mut count = 0;",
+                "     3 |     let todos = vec![count];",
+                "     4 |     println!(\"{:?}\", todo);",
+                "     5 | }",
+            ]
+            .join("\n")
+        );
+
+        // Files that cannot be read are shown as rustc rendered them
+        let diagnostics: Vec<Diagnostic> = parse_diagnostics(&cargo_stdout());
+        let snippets: String = diagnostic_snippets(&dir.join("missing"), &diagnostics);
+        assert_eq!(snippets, "error: cannot find value `todo` in this scope\n --> src/main.rs:3:1");
+        assert_eq!(
+            rendered_diagnostics(&diagnostics),
+            "error: cannot find value `todo` in this scope\n --> src/main.rs:3:1\n\nwarning: variable does not need to be mutable\n --> src/main.rs:2:1"
+        );
+    }
+}
@@ -0,0 +1,187 @@
+use similar::TextDiff;
+use std::fmt;
+
+// Lines of unchanged code shown around every change of the diff
+const DIFF_CONTEXT_LINES: usize = 3;
+
+// Outcome of the human review of generated code. Accepting or revising carries the code as the
+// reviewer left it, which differs from the generated code when it was edited during the review.
+#[derive(Debug, PartialEq)]
+pub enum ReviewDecision {
+    Accept(String),
+    Reject,
+    // Comments are sent back to the agent for another revision of the code
+    Revise { code: String, comments: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RiskKind {
+    ProcessCommand,
+    Unsafe,
+    FileDeletion,
+    OutboundUrl,
+}
+
+impl fmt::Display for RiskKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ProcessCommand => write!(f, "runs external commands"),
+            Self::Unsafe => write!(f, "unsafe code"),
+            Self::FileDeletion => write!(f, "deletes files"),
+            Self::OutboundUrl => write!(f, "outbound URL"),
+        }
+    }
+}
+
+// A line of the code the reviewer should look at closely. `text` is the URL for outbound URLs
+// and the trimmed line otherwise.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskyConstruct {
+    pub line: usize,
+    pub kind: RiskKind,
+    pub text: String,
+}
+
+impl fmt::Display for RiskyConstruct {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {} - {}", self.line, self.kind, self.text)
+    }
+}
+
+// Constructs of the supported languages, matched as substrings of a line
+const RISKY_PATTERNS: [(&str, RiskKind); 15] = [
+    ("std::process::Command", RiskKind::ProcessCommand),
+    ("Command::new", RiskKind::ProcessCommand),
+    ("subprocess.", RiskKind::ProcessCommand),
+    ("os.system(", RiskKind::ProcessCommand),
+    ("exec.Command(", RiskKind::ProcessCommand),
+    ("child_process", RiskKind::ProcessCommand),
+    ("remove_file", RiskKind::FileDeletion),
+    ("remove_dir", RiskKind::FileDeletion),
+    ("os.remove(", RiskKind::FileDeletion),
+    ("os.unlink(", RiskKind::FileDeletion),
+    ("shutil.rmtree(", RiskKind::FileDeletion),
+    ("os.Remove(", RiskKind::FileDeletion),
+    ("os.RemoveAll(", RiskKind::FileDeletion),
+    ("fs.unlink", RiskKind::FileDeletion),
+    ("rm -rf", RiskKind::FileDeletion),
+];
+
+// Hosts the generated server may call, e.g. its own routes
+const LOCAL_HOSTS: [&str; 4] = ["localhost", "127.0.0.1", "0.0.0.0", "[::1]"];
+
+// Risky constructs of the code, at most one of every kind per line
+pub fn risky_constructs(code: &str) -> Vec<RiskyConstruct> {
+    let mut constructs: Vec<RiskyConstruct> = Vec::new();
+
+    for (index, line) in code.lines().enumerate() {
+        let mut kinds: Vec<RiskKind> = RISKY_PATTERNS
+            .iter()
+            .filter(|(pattern, _)| line.contains(pattern))
+            .map(|(_, kind)| *kind)
+            .collect();
+        if contains_word(line, "unsafe") {
+            kinds.push(RiskKind::Unsafe);
+        }
+        kinds.dedup();
+        for kind in kinds {
+            constructs.push(RiskyConstruct { line: index + 1, kind, text: line.trim().to_string() });
+        }
+
+        for url in outbound_urls(line) {
+            constructs.push(RiskyConstruct { line: index + 1, kind: RiskKind::OutboundUrl, text: url });
+        }
+    }
+    constructs
+}
+
+fn contains_word(line: &str, word: &str) -> bool {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    line.match_indices(word).any(|(start, _)| {
+        let before: Option<char> = line[..start].chars().next_back();
+        let after: Option<char> = line[start + word.len()..].chars().next();
+        !before.is_some_and(is_ident) && !after.is_some_and(is_ident)
+    })
+}
+
+// URLs of the line whose host is not the local machine
+fn outbound_urls(line: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for scheme in ["http://", "https://"] {
+        for (start, _) in line.match_indices(scheme) {
+            let url: &str = line[start..]
+                .split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '`' | ')' | '>' | ','))
+                .next()
+                .unwrap_or_default();
+            let host: &str = url[scheme.len()..]
+                .split(['/', '?', '#'])
+                .next()
+                .unwrap_or_default();
+            let host: &str = match host.rsplit_once(':') {
+                Some((name, port)) if port.chars().all(|c| c.is_ascii_digit()) => name,
+                _ => host,
+            };
+            if !host.is_empty() && !LOCAL_HOSTS.contains(&host) {
+                urls.push(url.to_string());
+            }
+        }
+    }
+    urls
+}
+
+// Unified diff from the previous to the new code, empty when they are the same
+pub fn unified_diff(previous: &str, code: &str) -> String {
+    TextDiff::from_lines(previous, code)
+        .unified_diff()
+        .context_radius(DIFF_CONTEXT_LINES)
+        .header("previous", "new")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_risky_constructs() {
+        let code: &str = r#"use std::process::Command;
+use std::fs;
+
+async fn cleanup() {
+    let _ = Command::new("rm").arg("-rf").arg("/tmp/data").status();
+    fs::remove_file("db.json").unwrap();
+    let unsafe_name = "not a block";
+    unsafe { libc::abort() };
+    let _ = reqwest::get("https://api.example.com/v1/data?key=1").await;
+    let _ = reqwest::get("http://localhost:8080/todos").await;
+}
+"#;
+        let constructs: Vec<RiskyConstruct> = risky_constructs(code);
+        let found: Vec<(usize, RiskKind)> = constructs.iter().map(|construct| (construct.line, construct.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, RiskKind::ProcessCommand),
+                (5, RiskKind::ProcessCommand),
+                (6, RiskKind::FileDeletion),
+                (8, RiskKind::Unsafe),
+                (9, RiskKind::OutboundUrl),
+            ]
+        );
+        assert_eq!(constructs[4].text, "https://api.example.com/v1/data?key=1");
+        assert_eq!(constructs[2].to_string(), r#"line 6: deletes files - fs::remove_file("db.json").unwrap();"#);
+    }
+
+    #[test]
+    fn tests_unified_diff() {
+        let previous: &str = "fn main() {\n    println!(\"hello\");\n}\n";
+        let code: &str = "fn main() {\n    println!(\"hello, world\");\n}\n";
+
+        let diff: String = unified_diff(previous, code);
+        assert!(diff.starts_with("--- previous\n+++ new\n"));
+        assert!(diff.contains("-    println!(\"hello\");\n"));
+        assert!(diff.contains("+    println!(\"hello, world\");\n"));
+        assert!(diff.contains(" fn main() {\n"));
+        assert_eq!(unified_diff(code, code), "");
+    }
+}
@@ -2,9 +2,15 @@ use crossterm::{
     style::{Color, ResetColor, SetForegroundColor},
     ExecutableCommand,
 };
-use std::io::{stdin, stdout};
+use crate::utils::code_review::{risky_constructs, unified_diff, ReviewDecision, RiskyConstruct};
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::io::{stdin, stdout, BufRead};
+use std::process::{Command, ExitStatus};
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PrintMessage {
     Info,
     Testing,
@@ -88,49 +94,152 @@ pub fn get_user_input(question: &str, question_num: u8) -> String {
     user_input
 }
 
-pub fn confirm_safe_code() -> bool {
+// Answers may be typed with or without the brackets of the question, e.g. "[Rust]" or "rust"
+pub fn normalize_answer(input: &str) -> String {
+    input.trim().trim_start_matches('[').trim_end_matches(']').trim().to_lowercase()
+}
+
+// Shows the reviewer what changed in the code at `code_path` since the `previous` version, and
+// which risky constructs it contains, until they accept, reject or comment on it
+pub fn confirm_safe_code(previous: &str, code: &str, code_path: &str) -> ReviewDecision {
+    review_code(&mut stdin().lock(), previous, code, code_path)
+}
+
+fn review_code(input: &mut impl BufRead, previous: &str, code: &str, code_path: &str) -> ReviewDecision {
     let mut stdout: std::io::Stdout = stdout();
+    let mut code: String = code.to_string();
+    let mut show_code: bool = true;
     loop {
-        stdout.execute(SetForegroundColor(Color::Blue)).unwrap();
-        println!();
-        print!("WARNING: The code is AI generated. ");
-        println!("Review the code before proceeding.");
-        println!();
-
-        // Reset color
-        stdout.execute(ResetColor).unwrap();
+        if show_code {
+            print_code_review(previous, &code, code_path);
+            show_code = false;
+        }
 
         // Present options
         stdout.execute(SetForegroundColor(Color::Green)).unwrap();
         println!("[1][yes][y] Looks good!");
         stdout.execute(SetForegroundColor(Color::DarkRed)).unwrap();
         println!("[2][no][n] Let's drop this project.");
+        stdout.execute(SetForegroundColor(Color::Blue)).unwrap();
+        println!("[3][edit][e] Open the code in $EDITOR.");
+        println!("[4][comment][c] Send comments back to the agent.");
 
         // Reset color
         stdout.execute(ResetColor).unwrap();
 
-        let mut user_resp: String = String::new();
-        stdin()
-            .read_line(&mut user_resp)
-            .expect("Failed to read user response");
-
-        // Trim whitespace and convert to lowercase
-        let user_resp: String = user_resp.trim().to_lowercase();
+        // Nobody is left to answer once stdin is closed
+        let Some(user_resp) = read_input_line(input) else {
+            println!("No response, dropping the project.");
+            return ReviewDecision::Reject;
+        };
 
-        match user_resp.as_str() {
-            "1" | "yes" | "y" => return true,
-            "2" | "no" | "n" => return false,
+        match normalize_answer(&user_resp).as_str() {
+            "1" | "yes" | "y" => return ReviewDecision::Accept(code),
+            "2" | "no" | "n" => return ReviewDecision::Reject,
+            "3" | "edit" | "e" => match edit_in_editor(code_path) {
+                Ok(edited) => {
+                    code = edited;
+                    show_code = true;
+                }
+                Err(e) => println!("Could not edit {}: {}", code_path, e),
+            },
+            "4" | "comment" | "c" => {
+                println!("Comments for the agent, end with an empty line:");
+                let mut comments: Vec<String> = Vec::new();
+                while let Some(line) = read_input_line(input) {
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    comments.push(line.trim_end().to_string());
+                }
+                if comments.is_empty() {
+                    println!("No comments given.");
+                } else {
+                    return ReviewDecision::Revise { code, comments: comments.join("\n") };
+                }
+            }
             _ => {
-                println!(r#"Invalid input. Please select "1" or "2""#);
+                println!(r#"Invalid input. Please select "1", "2", "3" or "4""#);
             }
         }
     }
 }
 
+// None once the input is closed
+fn read_input_line(input: &mut impl BufRead) -> Option<String> {
+    let mut line: String = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line),
+    }
+}
+
+fn print_code_review(previous: &str, code: &str, code_path: &str) {
+    let mut stdout: std::io::Stdout = stdout();
+    stdout.execute(SetForegroundColor(Color::Blue)).unwrap();
+    println!();
+    print!("WARNING: The code is AI generated. ");
+    println!("Review the changes to {} before proceeding.", code_path);
+    println!();
+
+    let diff: String = unified_diff(previous, code);
+    if diff.is_empty() {
+        stdout.execute(ResetColor).unwrap();
+        println!("The code did not change.");
+    }
+    for line in diff.lines() {
+        let line_color: Color = if line.starts_with("+++") || line.starts_with("---") || line.starts_with("@@") {
+            Color::Cyan
+        } else if line.starts_with('+') {
+            Color::Green
+        } else if line.starts_with('-') {
+            Color::Red
+        } else {
+            Color::Reset
+        };
+        stdout.execute(SetForegroundColor(line_color)).unwrap();
+        println!("{}", line);
+    }
+    println!();
+
+    let constructs: Vec<RiskyConstruct> = risky_constructs(code);
+    if constructs.is_empty() {
+        stdout.execute(SetForegroundColor(Color::Green)).unwrap();
+        println!("No risky constructs found.");
+    } else {
+        stdout.execute(SetForegroundColor(Color::Yellow)).unwrap();
+        println!("Risky constructs:");
+        for construct in &constructs {
+            println!("  {}", construct);
+        }
+    }
+    println!();
+
+    // Reset color
+    stdout.execute(ResetColor).unwrap();
+}
+
+// Opens the file in $VISUAL or $EDITOR, falling back to vi, and reads it back once the editor exits
+fn edit_in_editor(code_path: &str) -> std::io::Result<String> {
+    let editor: String = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Editors like `code --wait` come with arguments
+    let mut editor_args = editor.split_whitespace();
+    let program: &str = editor_args.next().unwrap_or("vi");
+
+    let status: ExitStatus = Command::new(program).args(editor_args).arg(code_path).status()?;
+    if !status.success() {
+        return Err(std::io::Error::other(format!("{} exited with {}", editor, status)));
+    }
+    fs::read_to_string(code_path)
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use std::io::Cursor;
 
     #[test]
     fn tests_command_line_messages() {
@@ -147,6 +256,26 @@ mod tests {
 
     #[test]
     fn tests_confirm_test_code() {
-        println!("{}", confirm_safe_code());
+        println!("{:?}", confirm_safe_code("fn main() {}\n", "fn main() {\n    unsafe {}\n}\n", "main.rs"));
+    }
+
+    #[test]
+    fn tests_review_code_answers() {
+        let previous: &str = "fn main() {}\n";
+        let code: &str = "fn main() {\n    println!(\"hello\");\n}\n";
+        let review = |answers: &str| review_code(&mut Cursor::new(answers.to_string()), previous, code, "main.rs");
+
+        assert_eq!(review("maybe\n[y]\n"), ReviewDecision::Accept(code.to_string()));
+        assert_eq!(review("n\n"), ReviewDecision::Reject);
+        // A closed stdin rejects instead of asking again forever
+        assert_eq!(review(""), ReviewDecision::Reject);
+        assert_eq!(review("c\n"), ReviewDecision::Reject);
+        assert_eq!(
+            review("c\n\ncomment\nLog every request\nand return 404 for unknown ids\n\n"),
+            ReviewDecision::Revise {
+                code: code.to_string(),
+                comments: "Log every request\nand return 404 for unknown ids".to_string(),
+            }
+        );
     }
 }
\ No newline at end of file
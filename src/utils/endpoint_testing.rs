@@ -0,0 +1,293 @@
+use crate::agents::base::agent_traits::RouteObject;
+use crate::utils::language::ProjectCommand;
+use reqwest::{Client, Method};
+use serde_json::Value;
+use std::fmt;
+use std::io;
+use std::net::TcpListener;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
+
+// Value filled in for path parameters such as `{id}` or `:id`
+const PATH_PARAMETER_VALUE: &str = "1";
+
+// A route that did not respond the way its `RouteObject` describes
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointFailure {
+    pub route: String,
+    pub reason: String,
+}
+
+impl fmt::Display for EndpointFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.route, self.reason)
+    }
+}
+
+// Asks the OS for a port nobody listens on. The listener is dropped right away, so there is a
+// small window in which another process could take it.
+pub fn free_port() -> io::Result<u16> {
+    Ok(TcpListener::bind("127.0.0.1:0")?.local_addr()?.port())
+}
+
+// Starts the built backend with the run command of its language, passing the port in the PORT
+// environment variable. The child is killed when dropped, so a failing test run never leaves a
+// server behind.
+pub fn launch_backend_server(project_dir: &Path, run_command: &ProjectCommand, port: u16) -> io::Result<Child> {
+    Command::new(run_command.program)
+        .args(&run_command.args)
+        .env("PORT", port.to_string())
+        .current_dir(project_dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+}
+
+// Waits until the server accepts connections, or gives up after the timeout
+pub async fn wait_until_ready(port: u16, timeout: Duration) -> bool {
+    let started: Instant = Instant::now();
+    while started.elapsed() < timeout {
+        if TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+            return true;
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    false
+}
+
+// Calls every route once with its example request body and checks the status and the shape of
+// the JSON response. Dynamic routes may answer 404, the filled in id does not have to exist.
+pub async fn test_endpoints(client: &Client, base_url: &str, routes: &[RouteObject]) -> Vec<EndpointFailure> {
+    let mut failures: Vec<EndpointFailure> = Vec::new();
+
+    for route in routes {
+        let route_name: String = format!("{} {}", route.method.to_uppercase(), route.route);
+        let fail = |reason: String| EndpointFailure { route: route_name.clone(), reason };
+
+        let method: Method = match Method::from_bytes(route.method.to_uppercase().as_bytes()) {
+            Ok(method) => method,
+            Err(_) => {
+                failures.push(fail(format!("unknown HTTP method {}", route.method)));
+                continue;
+            }
+        };
+
+        let url: String = format!("{}{}", base_url.trim_end_matches('/'), fill_path_parameters(&route.route));
+        let mut request = client.request(method, &url);
+        if !route.request_body.is_null() {
+            request = request.json(&route.request_body);
+        }
+
+        let res: reqwest::Response = match request.send().await {
+            Ok(res) => res,
+            Err(e) => {
+                failures.push(fail(format!("request failed: {}", e)));
+                continue;
+            }
+        };
+
+        let status: u16 = res.status().as_u16();
+        let is_dynamic: bool = route.is_route_dynamic == "true";
+        if status == 404 && is_dynamic {
+            continue;
+        }
+        if !res.status().is_success() {
+            failures.push(fail(format!("responded with status code {}", status)));
+            continue;
+        }
+
+        if route.response.is_null() {
+            continue;
+        }
+        let body: String = res.text().await.unwrap_or_default();
+        match serde_json::from_str::<Value>(&body) {
+            Ok(actual) => {
+                let mismatches: Vec<String> = shape_mismatches("$", &route.response, &actual);
+                if !mismatches.is_empty() {
+                    failures.push(fail(format!("response does not match the expected shape: {}", mismatches.join(", "))));
+                }
+            }
+            Err(_) => failures.push(fail(format!("response is not JSON: {}", body.chars().take(200).collect::<String>()))),
+        }
+    }
+
+    failures
+}
+
+// `/todos/{id}` and `/todos/:id` become `/todos/1`
+fn fill_path_parameters(route: &str) -> String {
+    route
+        .split('/')
+        .map(|segment| {
+            if (segment.starts_with('{') && segment.ends_with('}')) || segment.starts_with(':') {
+                PATH_PARAMETER_VALUE
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<&str>>()
+        .join("/")
+}
+
+// Compares the structure of a response with the example response: objects need the same fields,
+// arrays are compared by their first items and other values by their JSON type. A null in the
+// example accepts anything, even a missing field.
+fn shape_mismatches(path: &str, expected: &Value, actual: &Value) -> Vec<String> {
+    match (expected, actual) {
+        (Value::Null, _) => Vec::new(),
+        (Value::Object(expected_fields), Value::Object(actual_fields)) => expected_fields
+            .iter()
+            .flat_map(|(name, expected_field)| {
+                let field_path: String = format!("{}.{}", path, name);
+                match actual_fields.get(name) {
+                    Some(actual_field) => shape_mismatches(&field_path, expected_field, actual_field),
+                    None if expected_field.is_null() => Vec::new(),
+                    None => vec![format!("{} is missing", field_path)],
+                }
+            })
+            .collect(),
+        (Value::Array(expected_items), Value::Array(actual_items)) => {
+            match (expected_items.first(), actual_items.first()) {
+                (Some(expected_item), Some(actual_item)) => {
+                    shape_mismatches(&format!("{}[0]", path), expected_item, actual_item)
+                }
+                _ => Vec::new(),
+            }
+        }
+        (Value::Bool(_), Value::Bool(_)) | (Value::Number(_), Value::Number(_)) | (Value::String(_), Value::String(_)) => {
+            Vec::new()
+        }
+        _ => vec![format!("{} should be {}, found {}", path, json_type(expected), json_type(actual))],
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::route_extraction::{extract_actix_routes, merge_responses};
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn route(method: &str, path: &str, response: Value) -> RouteObject {
+        RouteObject {
+            is_route_dynamic: path.contains('{').to_string(),
+            method: method.to_string(),
+            request_body: Value::Null,
+            response,
+            route: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn tests_fill_path_parameters() {
+        assert_eq!(fill_path_parameters("/todos/{id}/items/:item"), "/todos/1/items/1");
+        assert_eq!(fill_path_parameters("/todos"), "/todos");
+    }
+
+    #[test]
+    fn tests_shape_mismatches() {
+        let expected: Value = json!({ "id": 1, "title": "Buy milk", "tags": ["home"], "owner": null });
+        assert!(shape_mismatches("$", &expected, &json!({ "id": 7, "title": "x", "tags": [], "owner": { "id": 1 } })).is_empty());
+        assert_eq!(
+            shape_mismatches("$", &expected, &json!({ "id": "7", "tags": [3] })),
+            vec!["$.id should be a number, found a string", "$.tags[0] should be a string, found a number", "$.title is missing"]
+        );
+    }
+
+    // Serves one canned HTTP response per connection
+    async fn serve(listener: tokio::net::TcpListener, status: &'static str, body: &'static str) {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                return;
+            };
+            let mut buf: [u8; 4096] = [0; 4096];
+            let _ = stream.read(&mut buf).await;
+            let response: String = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn tests_endpoint_testing_against_server() {
+        let listener: tokio::net::TcpListener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port: u16 = listener.local_addr().unwrap().port();
+        tokio::spawn(serve(listener, "200 OK", r#"[{"id": 1, "title": "Buy milk", "completed": "no"}]"#));
+
+        assert!(wait_until_ready(port, Duration::from_secs(5)).await);
+
+        let routes: Vec<RouteObject> = vec![
+            route("get", "/todos", json!([{ "id": 1, "title": "Buy milk", "completed": false }])),
+            route("get", "/todos/{id}", Value::Null),
+        ];
+        let failures: Vec<EndpointFailure> =
+            test_endpoints(&Client::new(), &format!("http://127.0.0.1:{}", port), &routes).await;
+        assert_eq!(
+            failures,
+            vec![EndpointFailure {
+                route: "GET /todos".to_string(),
+                reason: "response does not match the expected shape: $[0].completed should be a boolean, found a string".to_string(),
+            }]
+        );
+    }
+
+    // The routes of a Rust backend are parsed from its code and get their responses from the LLM
+    #[tokio::test]
+    async fn tests_endpoint_testing_of_extracted_rust_routes() {
+        let code: &str = r#"
+            #[get("/todos")]
+            async fn list_todos() -> impl Responder { HttpResponse::Ok().finish() }
+
+            #[actix_web::main]
+            async fn main() -> std::io::Result<()> {
+                HttpServer::new(|| App::new().service(web::scope("/api").service(list_todos)))
+                    .bind(("127.0.0.1", 8080))?
+                    .run()
+                    .await
+            }
+        "#;
+        let mut routes: Vec<RouteObject> = extract_actix_routes(code).unwrap();
+        let described: Vec<RouteObject> = vec![route("get", "/api/todos", json!([{ "id": 1, "completed": false }]))];
+        assert_eq!(merge_responses(&mut routes, &described), 1);
+
+        let listener: tokio::net::TcpListener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port: u16 = listener.local_addr().unwrap().port();
+        tokio::spawn(serve(listener, "200 OK", r#"[{"id": "1", "completed": false}]"#));
+        assert!(wait_until_ready(port, Duration::from_secs(5)).await);
+
+        let failures: Vec<EndpointFailure> =
+            test_endpoints(&Client::new(), &format!("http://127.0.0.1:{}", port), &routes).await;
+        assert_eq!(
+            failures,
+            vec![EndpointFailure {
+                route: "GET /api/todos".to_string(),
+                reason: "response does not match the expected shape: $[0].id should be a number, found a string".to_string(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn tests_wait_until_ready_times_out() {
+        let port: u16 = free_port().unwrap();
+        assert!(!wait_until_ready(port, Duration::from_millis(300)).await);
+    }
+}
@@ -1,27 +1,206 @@
+use crate::utils::language::{ProjectCommand, ProjectLanguage};
 use reqwest::Client;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
 
 pub async fn check_status_code(client: &Client, url: &str) -> Result<u16, reqwest::Error> {
     let res: reqwest::Response = client.get(url).send().await?;
     Ok(res.status().as_u16())
 }
 
-pub fn save_code_to_file(filepath: &str, content: &str) {
+pub fn save_code_to_file(filepath: &str, content: &str) -> std::io::Result<()> {
     fs::write(filepath, content)
-        .expect(format!("Could not write to filepath: {}", filepath).as_str());
 }
 
-// Read code from filepath
-pub fn read_code_template(filepath: &str) -> String {
-    fs::read_to_string(filepath).expect("Failed to read code template from filepath")
+// Directory of the generated backend project. The code is written to CODE_OUTPUT_FILEPATH,
+// usually `<project>/src/main.rs`, so this is the parent of its `src` folder.
+pub fn backend_project_dir(code_filepath: &str) -> PathBuf {
+    let code_dir: &Path = Path::new(code_filepath).parent().unwrap_or(Path::new("."));
+    match code_dir.file_name().and_then(|name| name.to_str()) {
+        Some("src") => code_dir.parent().unwrap_or(Path::new(".")).to_path_buf(),
+        _ => code_dir.to_path_buf(),
+    }
+}
+
+// File name of the generated code, which the build and run commands of some languages take
+pub fn code_file_name(code_filepath: &str) -> &str {
+    Path::new(code_filepath)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+// Outcome of the build commands of a generated project. The output is the one of the first
+// failing command, or of the last command if all succeeded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildOutput {
+    pub success: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+// Builds the code in a copy of the generated backend project at `build_dir`, leaving the project
+// and the code the agents wrote untouched. The copy is made by the first build, without the
+// `target` directory, and reused by the later ones.
+pub async fn build_code_in_copy(code: &str, language: ProjectLanguage, output_file: &str, build_dir: &Path) -> BuildOutput {
+    let project_dir: PathBuf = backend_project_dir(output_file);
+    let code_path: &Path = Path::new(output_file)
+        .strip_prefix(&project_dir)
+        .unwrap_or(Path::new(code_file_name(output_file)));
+
+    let copied: std::io::Result<()> = if build_dir.exists() {
+        Ok(())
+    } else {
+        copy_project(&project_dir, build_dir)
+    };
+    if let Err(e) = copied.and_then(|_| fs::write(build_dir.join(code_path), code)) {
+        return BuildOutput {
+            success: false,
+            stdout: String::new(),
+            stderr: format!("Could not copy the project {} to {}: {}", project_dir.display(), build_dir.display(), e),
+        };
+    }
+    run_project_commands(build_dir, &language.build_commands(build_dir, code_file_name(output_file))).await
+}
+
+// Copies the project without its build artifacts and git history
+fn copy_project(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry: fs::DirEntry = entry?;
+        let path: PathBuf = entry.path();
+        if entry.file_type()?.is_dir() {
+            if matches!(entry.file_name().to_str(), Some("target" | ".git")) {
+                continue;
+            }
+            copy_project(&path, &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+// Builds and checks the generated backend project with the commands of its language. For Rust
+// this is `cargo build`, printing the diagnostics to stdout as JSON lines, see
+// `cargo_diagnostics::parse_diagnostics`.
+pub async fn build_backend_project(language: ProjectLanguage, output_file: &str) -> BuildOutput {
+    let project_dir: PathBuf = backend_project_dir(output_file);
+    run_project_commands(&project_dir, &language.build_commands(&project_dir, code_file_name(output_file))).await
+}
+
+// Runs the commands without blocking the other agents. A command still running when the future
+// is dropped, e.g. by the timeout of the agent state, is killed.
+pub async fn run_project_commands(project_dir: &Path, commands: &[ProjectCommand]) -> BuildOutput {
+    let mut build: BuildOutput = BuildOutput {
+        success: true,
+        stdout: String::new(),
+        stderr: String::new(),
+    };
+
+    for command in commands {
+        build = match Command::new(command.program)
+            .args(&command.args)
+            .current_dir(project_dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .output()
+            .await
+        {
+            Ok(output) => BuildOutput {
+                success: output.status.success()
+                    || output.status.code().is_some_and(|code| command.success_codes.contains(&code)),
+                stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            },
+            Err(e) => BuildOutput {
+                success: false,
+                stdout: String::new(),
+                stderr: format!("Could not run `{}`: {}", command, e),
+            },
+        };
+        if !build.success {
+            break;
+        }
+    }
+    build
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn tests_backend_project_dir() {
+        assert_eq!(backend_project_dir("/web_template/src/main.rs"), PathBuf::from("/web_template"));
+        assert_eq!(backend_project_dir("/web_template/main.py"), PathBuf::from("/web_template"));
+        assert_eq!(code_file_name("/web_template/main.py"), "main.py");
+    }
+
+    #[tokio::test]
+    async fn tests_run_project_commands() {
+        let version: ProjectCommand = ProjectCommand {
+            program: "cargo",
+            args: vec!["--version".to_string()],
+            success_codes: &[],
+        };
+        let unknown: ProjectCommand = ProjectCommand {
+            args: vec!["no-such-subcommand".to_string()],
+            ..version.clone()
+        };
+
+        let build: BuildOutput = run_project_commands(Path::new("."), std::slice::from_ref(&version)).await;
+        assert!(build.success);
+        assert!(build.stdout.starts_with("cargo "));
+
+        let build: BuildOutput = run_project_commands(Path::new("."), &[unknown.clone(), version.clone()]).await;
+        assert!(!build.success);
+        assert!(build.stderr.contains("no-such-subcommand"));
+
+        let tolerated: ProjectCommand = ProjectCommand { success_codes: &[101], ..unknown };
+        assert!(run_project_commands(Path::new("."), &[tolerated, version]).await.success);
+
+        let missing: ProjectCommand = ProjectCommand {
+            program: "autumn-no-such-program",
+            args: Vec::new(),
+            success_codes: &[],
+        };
+        assert!(run_project_commands(Path::new("."), &[missing]).await.stderr.starts_with("Could not run"));
+    }
+
+    #[tokio::test]
+    async fn tests_build_code_in_copy() {
+        let root: PathBuf = std::env::temp_dir().join(format!("autumn_build_copy_{}", std::process::id()));
+        let project_dir: PathBuf = root.join("web_template");
+        let build_dir: PathBuf = root.join("build");
+        fs::create_dir_all(project_dir.join("src")).unwrap();
+        fs::create_dir_all(project_dir.join("target/debug")).unwrap();
+        fs::write(project_dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(project_dir.join("target/debug/web_template"), "").unwrap();
+        let output_file: String = project_dir.join("src/main.rs").to_string_lossy().into_owned();
+
+        build_code_in_copy("fn main() { println!(\"copy\"); }\n", ProjectLanguage::Rust, &output_file, &build_dir).await;
+        assert_eq!(fs::read_to_string(project_dir.join("src/main.rs")).unwrap(), "fn main() {}\n");
+        assert_eq!(
+            fs::read_to_string(build_dir.join("src/main.rs")).unwrap(),
+            "fn main() { println!(\"copy\"); }\n"
+        );
+        assert!(!build_dir.join("target/debug/web_template").exists());
+
+        // Later builds reuse the copy and only replace the code
+        fs::write(build_dir.join("notes.txt"), "kept").unwrap();
+        build_code_in_copy("not code", ProjectLanguage::Rust, &output_file, &build_dir).await;
+        assert_eq!(fs::read_to_string(build_dir.join("src/main.rs")).unwrap(), "not code");
+        assert!(build_dir.join("notes.txt").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
     #[test]
     fn generate_code_file() {
-        save_code_to_file("./generated_code/code_file.txt", "Testing Testing");
+        save_code_to_file("./generated_code/code_file.txt", "Testing Testing").unwrap();
     }
 }
@@ -0,0 +1,222 @@
+use crate::agents::base::agent_error::AgentError;
+use crate::utils::command_line::normalize_answer;
+use dotenv::dotenv;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// Built-in code templates, used when no template file is configured
+const RUST_TEMPLATE: &str = include_str!("../../templates/rust/main.rs");
+const PYTHON_TEMPLATE: &str = include_str!("../../templates/python/main.py");
+const GO_TEMPLATE: &str = include_str!("../../templates/go/main.go");
+
+const RUST_ALLOWED_LIBRARIES: &str =
+    "reqwest, serde, serde_json, tokio, actix-web, async-trait, actix_cors";
+const PYTHON_ALLOWED_LIBRARIES: &str = "the Python standard library, flask, flask_cors, requests";
+const GO_ALLOWED_LIBRARIES: &str = "the Go standard library only (net/http, encoding/json, sync, ...)";
+
+// pytest exits with 5 when the project has no tests, which is not a failure
+const PYTEST_NO_TESTS_COLLECTED: i32 = 5;
+
+// Language the backend is written in
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ProjectLanguage {
+    Rust,
+    Python,
+    Go,
+}
+
+// A command run in the generated backend project
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectCommand {
+    pub program: &'static str,
+    pub args: Vec<String>,
+    // Exit codes besides 0 that count as success
+    pub success_codes: &'static [i32],
+}
+
+impl ProjectCommand {
+    fn new(program: &'static str, args: &[&str]) -> Self {
+        Self {
+            program,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            success_codes: &[],
+        }
+    }
+}
+
+impl fmt::Display for ProjectCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.program, self.args.join(" "))
+    }
+}
+
+impl ProjectLanguage {
+    // Rust keeps the original variable names, the others are prefixed, e.g. PYTHON_CODE_FILEPATH
+    fn env_prefix(&self) -> &'static str {
+        match self {
+            Self::Rust => "",
+            Self::Python => "PYTHON_",
+            Self::Go => "GO_",
+        }
+    }
+
+    pub fn allowed_libraries(&self) -> &'static str {
+        match self {
+            Self::Rust => RUST_ALLOWED_LIBRARIES,
+            Self::Python => PYTHON_ALLOWED_LIBRARIES,
+            Self::Go => GO_ALLOWED_LIBRARIES,
+        }
+    }
+
+    // Code template the first version of the backend is written from, read from
+    // <PREFIX>CODE_FILEPATH if set
+    pub fn code_template(&self) -> Result<String, AgentError> {
+        dotenv().ok();
+        match env::var(format!("{}CODE_FILEPATH", self.env_prefix())) {
+            Ok(filepath) => fs::read_to_string(&filepath).map_err(|e| AgentError::io(&filepath, e)),
+            Err(_) => Ok(match self {
+                Self::Rust => RUST_TEMPLATE,
+                Self::Python => PYTHON_TEMPLATE,
+                Self::Go => GO_TEMPLATE,
+            }
+            .to_string()),
+        }
+    }
+
+    // File the generated backend code is written to, e.g. `<project>/src/main.rs` or `<project>/main.py`
+    pub fn code_output_filepath(&self) -> Result<String, AgentError> {
+        dotenv().ok();
+        let key: String = format!("{}CODE_OUTPUT_FILEPATH", self.env_prefix());
+        env::var(&key).map_err(|_| AgentError::config(key))
+    }
+
+    // Commands checking the generated project, run in order until one fails. pytest only runs
+    // when the project has test files, as it is often not installed.
+    pub fn build_commands(&self, project_dir: &Path, code_file: &str) -> Vec<ProjectCommand> {
+        match self {
+            Self::Rust => vec![ProjectCommand::new("cargo", &["build", "--message-format=json"])],
+            Self::Python => {
+                let mut commands: Vec<ProjectCommand> =
+                    vec![ProjectCommand::new("python3", &["-m", "py_compile", code_file])];
+                if has_python_tests(project_dir) {
+                    commands.push(ProjectCommand {
+                        success_codes: &[PYTEST_NO_TESTS_COLLECTED],
+                        ..ProjectCommand::new("python3", &["-m", "pytest", "-q"])
+                    });
+                }
+                commands
+            }
+            Self::Go => vec![
+                ProjectCommand::new("go", &["build", "./..."]),
+                ProjectCommand::new("go", &["vet", "./..."]),
+            ],
+        }
+    }
+
+    // Command starting the built server, which listens on the port in PORT
+    pub fn run_command(&self, code_file: &str) -> ProjectCommand {
+        match self {
+            Self::Rust => ProjectCommand::new("cargo", &["run", "--quiet"]),
+            Self::Python => ProjectCommand::new("python3", &[code_file]),
+            Self::Go => ProjectCommand::new("go", &["run", "."]),
+        }
+    }
+}
+
+// Whether the project has files pytest collects, `test_*.py` or `*_test.py`, skipping hidden
+// folders and virtual environments
+fn has_python_tests(dir: &Path) -> bool {
+    let entries: fs::ReadDir = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    entries.flatten().any(|entry| {
+        let name: String = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            !name.starts_with('.') && !matches!(name.as_str(), "venv" | "__pycache__") && has_python_tests(&entry.path())
+        } else {
+            name.ends_with(".py") && (name.starts_with("test_") || name.ends_with("_test.py"))
+        }
+    })
+}
+
+impl FromStr for ProjectLanguage {
+    type Err = String;
+
+    // Accepts the CLI answers with or without brackets, e.g. "[Rust]" or "rust"
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match normalize_answer(input).as_str() {
+            "rust" => Ok(Self::Rust),
+            "python" => Ok(Self::Python),
+            "go" | "golang" => Ok(Self::Go),
+            _ => Err(format!("Unknown language {:?}, choose one of Rust, Python or Go", input.trim())),
+        }
+    }
+}
+
+impl fmt::Display for ProjectLanguage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rust => write!(f, "Rust"),
+            Self::Python => write!(f, "Python"),
+            Self::Go => write!(f, "Go"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tests_parse_language() {
+        assert_eq!("[Rust]\n".parse::<ProjectLanguage>(), Ok(ProjectLanguage::Rust));
+        assert_eq!(" golang ".parse::<ProjectLanguage>(), Ok(ProjectLanguage::Go));
+        assert!("Java".parse::<ProjectLanguage>().is_err());
+    }
+
+    #[test]
+    fn tests_language_commands() {
+        let dir: std::path::PathBuf = env::temp_dir().join(format!("autumn_language_{}", std::process::id()));
+        fs::create_dir_all(dir.join(".venv")).unwrap();
+        fs::write(dir.join("main.py"), "").unwrap();
+        fs::write(dir.join(".venv/test_site.py"), "").unwrap();
+
+        // Without test files pytest is not needed, and may not be installed
+        let python: Vec<ProjectCommand> = ProjectLanguage::Python.build_commands(&dir, "main.py");
+        assert_eq!(python.len(), 1);
+        assert_eq!(python[0].to_string(), "python3 -m py_compile main.py");
+
+        fs::create_dir_all(dir.join("tests")).unwrap();
+        fs::write(dir.join("tests/test_main.py"), "").unwrap();
+        let python: Vec<ProjectCommand> = ProjectLanguage::Python.build_commands(&dir, "main.py");
+        assert_eq!(python[1].to_string(), "python3 -m pytest -q");
+        assert_eq!(python[1].success_codes, &[PYTEST_NO_TESTS_COLLECTED]);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(ProjectLanguage::Go.build_commands(Path::new("."), "main.go")[1].to_string(), "go vet ./...");
+        assert_eq!(ProjectLanguage::Python.run_command("main.py").to_string(), "python3 main.py");
+        assert_eq!(ProjectLanguage::Rust.run_command("main.rs").to_string(), "cargo run --quiet");
+    }
+
+    #[test]
+    fn tests_missing_settings() {
+        // Unset here unless a developer's .env configures Go projects
+        if env::var("GO_CODE_OUTPUT_FILEPATH").is_err() {
+            let err: AgentError = ProjectLanguage::Go.code_output_filepath().unwrap_err();
+            assert_eq!(err.to_string(), "Could not find GO_CODE_OUTPUT_FILEPATH value from .env");
+        }
+        assert!(matches!(AgentError::config("LLM_MODEL"), AgentError::Config { key } if key == "LLM_MODEL"));
+    }
+
+    #[test]
+    fn tests_built_in_templates_read_port() {
+        for template in [RUST_TEMPLATE, PYTHON_TEMPLATE, GO_TEMPLATE] {
+            assert!(template.contains("PORT"));
+        }
+    }
+}
@@ -1,30 +1,63 @@
 use dotenv::dotenv;
-use serde::de::DeserializeOwned;
-use crate::agents::base::agent_traits::ProjectScope;
-use crate::models::general::llm::{APIResponse, ChatCompletion, Message};
-use crate::utils::command_line::PrintMessage;
+use crate::agents::base::agent_error::AgentError;
+use crate::ai_functions::ai_function::{
+    format_examples, format_input_sections, select_examples, AiFunction, FewShotExample,
+};
+use crate::ai_functions::prompt_library::{render_prompt, ResolvedPrompt};
+use crate::events::workflow_events::{emit, WorkflowEvent};
+use crate::models::general::llm::{APIResponse, ChatCompletion, LLMCompletion, Message};
 use reqwest::Client;
 use reqwest::header::{HeaderMap, HeaderValue};
 use std::env;
+use std::time::Instant;
 
+// Number of few-shot examples shown with a prompt, the ones closest to the request
+const MAX_FEW_SHOT_EXAMPLES: usize = 2;
 
 /// This function is the main way to interface with OpenAI's GPT 4 model
 /// 
 ///
 pub async fn call_gpt(messages: Vec<Message>) -> Result<String, Box<dyn std::error::Error + Send>> {
     dotenv().ok();
-    
+
+    // LLM model
+    let model: String = llm_setting("LLM_MODEL")?;
+
+    let completion: LLMCompletion = call_gpt_with_model(messages, &model).await?;
+    Ok(completion.content)
+}
+
+// Settings `call_gpt` reads from .env
+const LLM_SETTINGS: [&str; 4] = ["LLM_MODEL", "OPEN_AI_URL", "OPEN_AI_ORG", "OPEN_AI_KEY"];
+
+fn llm_setting(key: &str) -> Result<String, Box<dyn std::error::Error + Send>> {
+    env::var(key).map_err(|_| -> Box<dyn std::error::Error + Send> { Box::new(AgentError::config(key)) })
+}
+
+// Fails with the first missing LLM setting, before a request is made and retried
+pub fn check_llm_settings() -> Result<(), AgentError> {
+    dotenv().ok();
+    match LLM_SETTINGS.iter().find(|key| env::var(key).is_err()) {
+        Some(key) => Err(AgentError::config(*key)),
+        None => Ok(()),
+    }
+}
+
+// Same as `call_gpt` but with an explicit model, e.g. to evaluate prompts against other models
+pub async fn call_gpt_with_model(
+    messages: Vec<Message>,
+    model: &str
+) -> Result<LLMCompletion, Box<dyn std::error::Error + Send>> {
+    dotenv().ok();
+
     // OpenAI URL
-    let url: String = env::var("OPEN_AI_URL").expect("Could not find OPENAI url from .env file");
+    let url: String = llm_setting("OPEN_AI_URL")?;
 
     // OpenAI Organization
-    let org: String = env::var("OPEN_AI_ORG").expect("Could not find OPENAI org from .env file");
+    let org: String = llm_setting("OPEN_AI_ORG")?;
 
     // OpenAI Key
-    let key: String = env::var("OPEN_AI_KEY").expect("Could not find OPENAI key from .env file");
-
-    // LLM model
-    let model: String = env::var("LLM_MODEL").expect("Could not find LLM model from .env file");
+    let key: String = llm_setting("OPEN_AI_KEY")?;
 
     // Create the Headers
     let mut header_map: HeaderMap = HeaderMap::new();
@@ -44,8 +77,9 @@ pub async fn call_gpt(messages: Vec<Message>) -> Result<String, Box<dyn std::err
         .build()
         .map_err(|e| -> Box<dyn std::error::Error + Send> { Box::new(e) })?;
 
-    let chat_completion: ChatCompletion = ChatCompletion::new(model, messages);
+    let chat_completion: ChatCompletion = ChatCompletion::new(model.to_string(), messages);
 
+    let started: Instant = Instant::now();
     let res: APIResponse = client
         .post(&url)
         .json(&chat_completion)
@@ -56,20 +90,41 @@ pub async fn call_gpt(messages: Vec<Message>) -> Result<String, Box<dyn std::err
 
     let content = res.choices[0].message.content.clone();
 
-    Ok(content)
+    Ok(LLMCompletion {
+        content,
+        usage: res.usage,
+        latency_ms: started.elapsed().as_millis()
+    })
 }       
 
-// Converts function structure to static string reference
-fn api_instruction_wrapper(func: fn(&str) -> &'static str, user_input: &str) -> Message {
-    let ai_func: &str = func(user_input);
+// Wraps the ai function prompt with the instruction to the LLM
+fn api_instruction_wrapper(
+    ai_func: &str,
+    user_input: &str,
+    output_schema: Option<&str>,
+    examples: Option<&str>
+) -> Message {
+    // Structured outputs have to follow the JSON Schema of the output type
+    let schema_instruction: String = match output_schema {
+        Some(schema) => format!(
+            "OUTPUT SCHEMA: The output is JSON matching this JSON Schema: {}\n        ",
+            schema
+        ),
+        None => String::new(),
+    };
+    let examples_instruction: String = match examples {
+        Some(examples) => format!("EXAMPLES:\n{}\n        ", examples),
+        None => String::new(),
+    };
 
     // Instruction to the LLM
     let msg: String = format!(
         "FUNCTION: {}
-        INSTRUCTION: You are a function printer, You ONLY print the results of functions.
-        Nothing else. No commentary. Here is the input of the function: {}.
+        {}{}INSTRUCTION: You are a function printer, You ONLY print the results of functions.
+        Nothing else. No commentary. Here is the input of the function:
+        {}
         Print out what the function will return.",
-        ai_func, user_input
+        ai_func, schema_instruction, examples_instruction, user_input
     );
 
     Message {
@@ -78,43 +133,124 @@ fn api_instruction_wrapper(func: fn(&str) -> &'static str, user_input: &str) ->
     }
 }
 
-// Request to GPT or LLM to get response in string
-pub async fn request_task_llm(
-    ai_func: fn(&str) -> &'static str,
-    user_req: String,
+// Builds the message asking the LLM to print the result of an ai function for the given input
+// sections, using the requested prompt version or the active one. Returns the message and the
+// `<name>@<version>` of the prompt used.
+pub fn ai_function_message<F: AiFunction>(
+    ai_func: &F,
+    sections: &[(&str, String)],
+    version: Option<&str>
+) -> Result<(Message, String), String> {
+    let user_req: String = format_input_sections(sections);
+    let output_schema: Option<String> = ai_func.output_schema().map(|schema| schema.to_string());
+    let examples: Vec<FewShotExample<String>> =
+        select_examples(ai_func.rendered_examples(), &user_req, MAX_FEW_SHOT_EXAMPLES);
+    let examples_text: Option<String> = (!examples.is_empty()).then(|| format_examples(&examples));
+
+    // Prompt templates can place the schema and examples themselves with {{output_schema}} and {{examples}}
+    let mut input_vars: Vec<(&str, &str)> = sections
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+    if let Some(schema) = &output_schema {
+        input_vars.push(("output_schema", schema));
+    }
+    if let Some(examples) = &examples_text {
+        input_vars.push(("examples", examples));
+    }
+    let prompt: ResolvedPrompt = render_prompt(
+        ai_func.name(),
+        ai_func.prompt(),
+        ai_func.version(),
+        version,
+        &input_vars
+    )?;
+    let schema_in_prompt: Option<&str> = output_schema
+        .as_deref()
+        .filter(|schema| !prompt.text.contains(schema));
+    let examples_in_prompt: Option<&str> = examples_text
+        .as_deref()
+        .filter(|examples| !prompt.text.contains(examples));
+
+    Ok((
+        api_instruction_wrapper(&prompt.text, &user_req, schema_in_prompt, examples_in_prompt),
+        format!("{}@{}", ai_func.name(), prompt.version)
+    ))
+}
+
+// Makes an LLM call, emitting its request and response events
+pub async fn observe_llm_call<F, Fut>(
     agent_position: &str,
-    agent_operation: &str
-) -> String {
-    let req_str: Message = api_instruction_wrapper(ai_func, &user_req);
+    function: &str,
+    prompt_version: Option<&str>,
+    messages: Vec<Message>,
+    call: F
+) -> Result<String, String>
+where
+    F: FnOnce(Vec<Message>) -> Fut,
+    Fut: std::future::Future<Output = Result<String, String>>,
+{
+    emit(WorkflowEvent::LlmRequest {
+        agent: agent_position.to_string(),
+        function: function.to_string(),
+        prompt_version: prompt_version.map(str::to_string),
+        prompt: messages.iter().map(|message| message.content.as_str()).collect::<Vec<&str>>().join("\n\n"),
+    });
+
+    let started: Instant = Instant::now();
+    let result: Result<String, String> = call(messages).await;
+    emit(WorkflowEvent::LlmResponse {
+        agent: agent_position.to_string(),
+        function: function.to_string(),
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        response: result.as_ref().ok().cloned(),
+        error: result.as_ref().err().cloned(),
+    });
+    result
+}
+
+// Request to GPT or LLM to get the typed output of an ai function
+pub async fn request_task_llm<F: AiFunction>(
+    ai_func: F,
+    input: F::Input<'_>,
+    agent_position: &str
+) -> Result<F::Output, AgentError> {
+    Ok(request_task_llm_traced(ai_func, input, agent_position).await?.0)
+}
 
-    PrintMessage::Info.print_agent_msg(agent_position, agent_operation);
+// Same as `request_task_llm`, also returning the `<name>@<version>` of the prompt that produced
+// the output so it can be recorded with the artifact
+pub async fn request_task_llm_traced<F: AiFunction>(
+    ai_func: F,
+    input: F::Input<'_>,
+    agent_position: &str
+) -> Result<(F::Output, String), AgentError> {
+    let llm_error = |reason: String| AgentError::Llm { function: ai_func.name().to_string(), reason };
+    check_llm_settings()?;
 
-    // Make a request to LLM GPT
-    let llm_res = call_gpt(vec![req_str.clone()]).await;
+    let sections: Vec<(&str, String)> = ai_func.render_input(input);
+    let (req_str, prompt_version): (Message, String) = ai_function_message(&ai_func, &sections, None)
+        .map_err(|e| llm_error(format!("could not render the prompt: {}", e)))?;
 
-    match llm_res {
+    // Make a request to LLM GPT, calling it again when it fails
+    let observed_call = || observe_llm_call(
+        agent_position,
+        ai_func.name(),
+        Some(&prompt_version),
+        vec![req_str.clone()],
+        |messages| async move { call_gpt(messages).await.map_err(|e| e.to_string()) }
+    );
+    let llm_res_str: String = match observed_call().await {
         Ok(res) => res,
-        Err(e) => {
-            println!("Error calling the LLM: {}", e);
-            println!("Calling the GPT again...");
-            call_gpt(vec![req_str.clone()]).await.expect("Failed to call LLM twice")
-        },
-    }
-}
+        Err(_) => observed_call()
+            .await
+            .map_err(|e| llm_error(format!("failed twice: {}", e)))?,
+    };
 
-// Request to GPT or LLM to get response in flexible types
-pub async fn request_task_llm_deserialized<T: DeserializeOwned>(
-    ai_func: fn(&str) -> &'static str,
-    user_req: String,
-    agent_position: &str,
-    agent_operation: &str
-) -> T {
-    PrintMessage::Info.print_agent_msg(agent_position, agent_operation);
-    let llm_res_str = request_task_llm(ai_func, user_req, agent_position, agent_operation).await;
-    
-    let deserialized_obj: T = serde_json::from_str(llm_res_str.as_str()).expect("Failed to decode LLM response.");
-
-    deserialized_obj
+    match ai_func.parse_output(&llm_res_str) {
+        Ok(output) => Ok((output, prompt_version)),
+        Err(e) => Err(llm_error(format!("could not decode the response: {}", e))),
+    }
 }
 
 
@@ -128,11 +264,10 @@ mod tests{
     #[tokio::test]
     async fn example_call_gpt() {
         let sample_request_gpt = request_task_llm(
-            print_project_scope, 
-            "Build me a simple todo app with get and post request endpoints".to_string(),
-            "Project Manager",
-            get_function_string!(print_project_scope)
-        ).await;
+            print_project_scope,
+            "Build me a simple todo app with get and post request endpoints",
+            "Project Manager"
+        ).await.unwrap();
         dbg!(sample_request_gpt);
     }
 
@@ -153,14 +288,23 @@ mod tests{
 
     #[test]
     fn tests_api_wrapper() {
-        let func_str = api_instruction_wrapper(print_project_scope, "TESTING");
+        let schema: String = print_project_scope.output_schema().unwrap().to_string();
+        let func_str = api_instruction_wrapper(print_project_scope.prompt(), "TESTING", Some(&schema), None);
+        assert!(func_str.content.contains("OUTPUT SCHEMA"));
         dbg!(func_str);
     }
 
+    #[test]
+    fn tests_ai_function_message_examples() {
+        let sections: Vec<(&str, String)> = print_project_scope.render_input("I need a simple TODO app");
+        let (message, _): (Message, String) = ai_function_message(&print_project_scope, &sections, Some("2")).unwrap();
+        assert!(message.content.contains("EXAMPLES:\nExample 1:\n  INPUT: I need a simple TODO app"));
+    }
+
     #[tokio::test]
     async fn tests_request_task_llm() {
-        let project_req = "I want to build a application that allows me to forecast stock and crypto data".to_string();
-        let wrapped_req = request_task_llm(print_project_scope, project_req, "Project Manager", get_function_string!(print_project_scope)).await;
+        let project_req = "I want to build a application that allows me to forecast stock and crypto data";
+        let wrapped_req = request_task_llm(print_project_scope, project_req, "Project Manager").await.unwrap();
         dbg!(wrapped_req);
     }
 }
\ No newline at end of file
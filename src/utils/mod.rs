@@ -1,3 +1,9 @@
+pub mod cargo_diagnostics;
+pub mod code_review;
 pub mod command_line;
+pub mod endpoint_testing;
 pub mod general;
-pub mod llm_apis;
\ No newline at end of file
+pub mod language;
+pub mod llm_apis;
+pub mod openapi;
+pub mod route_extraction;
\ No newline at end of file
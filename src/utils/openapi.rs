@@ -0,0 +1,291 @@
+use crate::agents::base::agent_traits::RouteObject;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const OPENAPI_VERSION: &str = "3.1.0";
+
+// An OpenAPI 3.1 document of the generated backend. Typed down to the operations, so the
+// sections keep their usual order in the written files; schemas are plain JSON Schema values.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct OpenApiDocument {
+    pub openapi: String,
+    pub info: OpenApiInfo,
+    // Path, then lowercase HTTP method
+    pub paths: BTreeMap<String, BTreeMap<String, Operation>>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct OpenApiInfo {
+    pub title: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Operation {
+    pub operation_id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<Value>,
+    pub responses: BTreeMap<String, Value>,
+}
+
+// Builds the document from the routes. Path parameters come from `{id}` and `:id` segments,
+// request and response schemas are inferred from the example bodies.
+pub fn openapi_document(title: &str, description: Option<&str>, routes: &[RouteObject]) -> OpenApiDocument {
+    let mut paths: BTreeMap<String, BTreeMap<String, Operation>> = BTreeMap::new();
+
+    for route in routes {
+        let (path, path_params): (String, Vec<String>) = openapi_path(&route.route);
+        let method: String = route.method.to_lowercase();
+
+        let parameters: Vec<Value> = path_params
+            .iter()
+            .map(|name| {
+                json!({
+                    "name": name,
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" }
+                })
+            })
+            .collect();
+
+        let request_body: Option<Value> = (!route.request_body.is_null()).then(|| {
+            json!({
+                "required": true,
+                "content": { "application/json": media_type(&route.request_body) }
+            })
+        });
+
+        let mut success: Map<String, Value> = Map::new();
+        success.insert("description".to_string(), json!("Successful response"));
+        if !route.response.is_null() {
+            success.insert(
+                "content".to_string(),
+                json!({ "application/json": media_type(&route.response) }),
+            );
+        }
+
+        let operation: Operation = Operation {
+            operation_id: operation_id(&method, &path),
+            parameters,
+            request_body,
+            responses: BTreeMap::from([("200".to_string(), Value::Object(success))]),
+        };
+        paths.entry(path).or_default().insert(method, operation);
+    }
+
+    OpenApiDocument {
+        openapi: OPENAPI_VERSION.to_string(),
+        info: OpenApiInfo {
+            title: title.to_string(),
+            version: "0.1.0".to_string(),
+            description: description.map(str::to_string),
+        },
+        paths,
+    }
+}
+
+// Writes the document as openapi.json and openapi.yaml into the project directory
+pub fn save_openapi(project_dir: &Path, document: &OpenApiDocument) -> io::Result<(PathBuf, PathBuf)> {
+    let json_path: PathBuf = project_dir.join("openapi.json");
+    let yaml_path: PathBuf = project_dir.join("openapi.yaml");
+
+    fs::write(&json_path, serde_json::to_string_pretty(document)? + "\n")?;
+    let yaml: String = serde_yaml::to_string(document).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    fs::write(&yaml_path, yaml)?;
+
+    Ok((json_path, yaml_path))
+}
+
+// Routes the LLM marked as dynamic although their path has no parameter segment, which the
+// document then describes without parameters
+pub fn dynamic_routes_without_parameters(routes: &[RouteObject]) -> Vec<&RouteObject> {
+    routes
+        .iter()
+        .filter(|route| route.is_route_dynamic.trim().eq_ignore_ascii_case("true"))
+        .filter(|route| openapi_path(&route.route).1.is_empty())
+        .collect()
+}
+
+// `/todos/:id` and `/todos/{id:\d+}` become `/todos/{id}`, returning the path parameter names
+// in order
+fn openapi_path(route: &str) -> (String, Vec<String>) {
+    let mut params: Vec<String> = Vec::new();
+    let segments: Vec<String> = route
+        .split('/')
+        .map(|segment| {
+            let name: Option<&str> = segment
+                .strip_prefix('{')
+                .and_then(|rest| rest.strip_suffix('}'))
+                // actix-web restricts parameters with a regex, e.g. `{id:\d+}`
+                .map(|param| param.split_once(':').map_or(param, |(name, _)| name))
+                .or_else(|| segment.strip_prefix(':'));
+            match name {
+                Some(name) => {
+                    params.push(name.to_string());
+                    format!("{{{}}}", name)
+                }
+                None => segment.to_string(),
+            }
+        })
+        .collect();
+
+    (segments.join("/"), params)
+}
+
+// `get` and `/todos/{id}` give `get_todos_by_id`
+fn operation_id(method: &str, path: &str) -> String {
+    let mut parts: Vec<String> = vec![method.to_string()];
+    for segment in path.split('/').filter(|segment| !segment.is_empty()) {
+        match segment.strip_prefix('{').and_then(|rest| rest.strip_suffix('}')) {
+            Some(param) => parts.push(format!("by_{}", param)),
+            None => parts.push(
+                segment
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+                    .collect(),
+            ),
+        }
+    }
+    parts.join("_")
+}
+
+fn media_type(example: &Value) -> Value {
+    json!({ "schema": schema_from_example(example), "example": example })
+}
+
+// Infers a JSON Schema from an example value. Arrays are described by their first item, every
+// non-null field of an object is required and a null says nothing about the type.
+fn schema_from_example(example: &Value) -> Value {
+    match example {
+        Value::Null => json!({}),
+        Value::Bool(_) => json!({ "type": "boolean" }),
+        Value::Number(number) if number.is_i64() || number.is_u64() => json!({ "type": "integer" }),
+        Value::Number(_) => json!({ "type": "number" }),
+        Value::String(_) => json!({ "type": "string" }),
+        Value::Array(items) => json!({
+            "type": "array",
+            "items": items.first().map(schema_from_example).unwrap_or_else(|| json!({}))
+        }),
+        Value::Object(fields) => {
+            let properties: Map<String, Value> = fields
+                .iter()
+                .map(|(name, value)| (name.clone(), schema_from_example(value)))
+                .collect();
+            let required: Vec<&String> = fields
+                .iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(name, _)| name)
+                .collect();
+            json!({ "type": "object", "properties": properties, "required": required })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn routes() -> Vec<RouteObject> {
+        vec![
+            RouteObject {
+                is_route_dynamic: "false".to_string(),
+                method: "get".to_string(),
+                request_body: Value::Null,
+                response: json!([{ "id": 1, "title": "Buy milk", "done": false }]),
+                route: "/todos".to_string(),
+            },
+            RouteObject {
+                is_route_dynamic: "true".to_string(),
+                method: "PUT".to_string(),
+                request_body: json!({ "title": "Buy milk", "done": true, "due": null }),
+                response: Value::Null,
+                route: "/todos/:id".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn tests_openapi_path() {
+        assert_eq!(openapi_path("/todos/:id"), ("/todos/{id}".to_string(), vec!["id".to_string()]));
+        assert_eq!(
+            openapi_path("/users/{user_id}/todos/{id}"),
+            ("/users/{user_id}/todos/{id}".to_string(), vec!["user_id".to_string(), "id".to_string()])
+        );
+        assert_eq!(
+            openapi_path(r"/todos/{id:\d+}/tags/{tag}"),
+            ("/todos/{id}/tags/{tag}".to_string(), vec!["id".to_string(), "tag".to_string()])
+        );
+        assert_eq!(operation_id("get", "/users/{user_id}/todo-items"), "get_users_by_user_id_todo_items");
+    }
+
+    #[test]
+    fn tests_dynamic_routes_without_parameters() {
+        let mut routes: Vec<RouteObject> = routes();
+        routes[0].is_route_dynamic = "true".to_string();
+        let flagged: Vec<&RouteObject> = dynamic_routes_without_parameters(&routes);
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].route, "/todos");
+    }
+
+    #[test]
+    fn tests_schema_from_example() {
+        assert_eq!(
+            schema_from_example(&json!({ "id": 1, "price": 2.5, "tags": ["a"], "note": null })),
+            json!({
+                "type": "object",
+                "properties": {
+                    "id": { "type": "integer" },
+                    "note": {},
+                    "price": { "type": "number" },
+                    "tags": { "type": "array", "items": { "type": "string" } }
+                },
+                "required": ["id", "price", "tags"]
+            })
+        );
+    }
+
+    #[test]
+    fn tests_openapi_document() {
+        let document: OpenApiDocument = openapi_document("Todo API", Some("build a todo app"), &routes());
+        let value: Value = serde_json::to_value(&document).unwrap();
+
+        assert_eq!(value["openapi"], "3.1.0");
+        assert_eq!(value["info"]["title"], "Todo API");
+        assert_eq!(
+            value["paths"]["/todos"]["get"]["responses"]["200"]["content"]["application/json"]["schema"]["items"]["properties"]["done"],
+            json!({ "type": "boolean" })
+        );
+
+        let put: &Value = &value["paths"]["/todos/{id}"]["put"];
+        assert_eq!(put["operationId"], "put_todos_by_id");
+        assert_eq!(put["parameters"][0]["name"], "id");
+        assert_eq!(put["parameters"][0]["in"], "path");
+        assert_eq!(put["requestBody"]["content"]["application/json"]["schema"]["required"], json!(["done", "title"]));
+        assert!(put["responses"]["200"].get("content").is_none());
+        assert!(value["paths"]["/todos"]["get"].get("parameters").is_none());
+    }
+
+    #[test]
+    fn tests_save_openapi() {
+        let dir: PathBuf = std::env::temp_dir().join(format!("autumn_openapi_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let document: OpenApiDocument = openapi_document("Todo API", None, &routes());
+        let (json_path, yaml_path) = save_openapi(&dir, &document).unwrap();
+
+        let from_json: Value = serde_json::from_str(&fs::read_to_string(json_path).unwrap()).unwrap();
+        let from_yaml: Value = serde_yaml::from_str(&fs::read_to_string(&yaml_path).unwrap()).unwrap();
+        assert_eq!(from_json, from_yaml);
+        assert!(fs::read_to_string(yaml_path).unwrap().starts_with("openapi: 3.1.0\ninfo:\n  title: Todo API\n"));
+    }
+}
@@ -0,0 +1,573 @@
+use crate::agents::base::agent_traits::RouteObject;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+
+const HTTP_METHODS: [&str; 7] = ["get", "post", "put", "patch", "delete", "head", "options"];
+// Nesting of request body structs followed when building an example body
+const MAX_EXAMPLE_DEPTH: usize = 4;
+
+// Finds the routes of an actix-web server without running it: handlers annotated with
+// `#[get("/todos")]` or `#[route("/todos", method = "GET")]`, `.route("/todos", web::get().to(handler))`
+// and `web::resource("/todos").route(web::get().to(handler))`, prefixed with the paths of the
+// `web::scope("/api")` they are registered on. The request body is an example
+// built from the `web::Json<T>` argument of the handler. Handlers do not declare what they
+// respond, the responses are filled in with `merge_responses`.
+pub fn extract_actix_routes(code: &str) -> syn::Result<Vec<RouteObject>> {
+    let file: syn::File = syn::parse_file(code)?;
+    let mut collector: RouteCollector = RouteCollector::default();
+    collector.visit_file(&file);
+
+    // An annotated handler gets the prefix of every scope it is registered on with `.service(handler)`
+    let mut found_routes: Vec<FoundRoute> = Vec::new();
+    for found in collector.routes {
+        let prefixes: Vec<&String> = match (&found.handler, found.annotated) {
+            (Some(handler), true) => collector
+                .services
+                .iter()
+                .filter(|(service, _)| service == handler)
+                .map(|(_, prefix)| prefix)
+                .collect(),
+            _ => Vec::new(),
+        };
+        if prefixes.is_empty() {
+            found_routes.push(found);
+            continue;
+        }
+        for prefix in prefixes {
+            found_routes.push(FoundRoute { path: join_path(prefix, &found.path), ..found.clone() });
+        }
+    }
+
+    let mut routes: Vec<RouteObject> = Vec::new();
+    for found in &found_routes {
+        let is_duplicate: bool = routes
+            .iter()
+            .any(|route| route.method == found.method && route.route == found.path);
+        if is_duplicate {
+            continue;
+        }
+
+        let request_body: Value = found
+            .handler
+            .as_ref()
+            .and_then(|handler| collector.handlers.get(handler))
+            .and_then(|handler| json_body_type(handler))
+            .map(|ty| example_value(ty, &collector.structs, 0))
+            .unwrap_or(Value::Null);
+
+        routes.push(RouteObject {
+            is_route_dynamic: found.path.contains('{').to_string(),
+            method: found.method.clone(),
+            request_body,
+            response: Value::Null,
+            route: found.path.clone(),
+        });
+    }
+    Ok(routes)
+}
+
+// Copies the responses of the routes the LLM described into the parsed routes with the same
+// method and path, whatever their path parameters are named. Returns how many were filled in.
+pub fn merge_responses(routes: &mut [RouteObject], described: &[RouteObject]) -> usize {
+    let mut filled: usize = 0;
+    for route in routes.iter_mut() {
+        let response: Option<&Value> = described
+            .iter()
+            .find(|described| route_key(&described.method, &described.route) == route_key(&route.method, &route.route))
+            .map(|described| &described.response)
+            .filter(|response| !response.is_null());
+        if let Some(response) = response {
+            route.response = response.clone();
+            filled += 1;
+        }
+    }
+    filled
+}
+
+// `GET /todos/{id}/` and `get /todos/:todo_id` are the same route
+fn route_key(method: &str, path: &str) -> (String, Vec<String>) {
+    let segments: Vec<String> = path
+        .trim_end_matches('/')
+        .split('/')
+        .map(|segment| {
+            if segment.starts_with('{') || segment.starts_with(':') {
+                "{}".to_string()
+            } else {
+                segment.to_string()
+            }
+        })
+        .collect();
+    (method.to_lowercase(), segments)
+}
+
+// Writes the routes as `api_endpoints.json` in the directory of the generated code
+pub fn save_api_endpoints(code_filepath: &str, routes: &[RouteObject]) -> io::Result<PathBuf> {
+    let path: PathBuf = Path::new(code_filepath).with_file_name("api_endpoints.json");
+    let json: String = serde_json::to_string_pretty(routes)?;
+    fs::write(&path, json + "\n")?;
+    Ok(path)
+}
+
+#[derive(Clone)]
+struct FoundRoute {
+    method: String,
+    path: String,
+    handler: Option<String>,
+    // Declared by an attribute on the handler, the scope comes from where it is registered
+    annotated: bool,
+}
+
+#[derive(Default)]
+struct RouteCollector<'ast> {
+    handlers: HashMap<String, &'ast syn::ItemFn>,
+    structs: HashMap<String, &'ast syn::ItemStruct>,
+    routes: Vec<FoundRoute>,
+    // (handler, scope prefix) of every `.service(handler)`
+    services: Vec<(String, String)>,
+    // Prefixes of the scopes enclosing the expression being visited
+    scopes: Vec<String>,
+}
+
+impl RouteCollector<'_> {
+    fn scope_prefix(&self) -> &str {
+        self.scopes.last().map(String::as_str).unwrap_or_default()
+    }
+}
+
+impl<'ast> Visit<'ast> for RouteCollector<'ast> {
+    fn visit_item_fn(&mut self, node: &'ast syn::ItemFn) {
+        let handler: String = node.sig.ident.to_string();
+        self.handlers.insert(handler.clone(), node);
+
+        for attr in &node.attrs {
+            let Some(attr_name) = attr.path().segments.last().map(|segment| segment.ident.to_string()) else {
+                continue;
+            };
+            let Ok(args) = attr.parse_args_with(Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated) else {
+                continue;
+            };
+            let Some(path) = args.first().and_then(lit_str) else {
+                continue;
+            };
+
+            let methods: Vec<String> = if HTTP_METHODS.contains(&attr_name.as_str()) {
+                vec![attr_name]
+            } else if attr_name == "route" {
+                // #[route("/todos", method = "GET", method = "POST")]
+                args.iter()
+                    .filter_map(|arg| match arg {
+                        syn::Expr::Assign(assign) if is_ident(&assign.left, "method") => lit_str(&assign.right),
+                        _ => None,
+                    })
+                    .map(|method| method.to_lowercase())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            for method in methods {
+                self.routes.push(FoundRoute {
+                    method,
+                    path: path.clone(),
+                    handler: Some(handler.clone()),
+                    annotated: true,
+                });
+            }
+        }
+
+        visit::visit_item_fn(self, node);
+    }
+
+    fn visit_item_struct(&mut self, node: &'ast syn::ItemStruct) {
+        self.structs.insert(node.ident.to_string(), node);
+        visit::visit_item_struct(self, node);
+    }
+
+    fn visit_expr_method_call(&mut self, node: &'ast syn::ExprMethodCall) {
+        // Calls chained on `web::scope("/api")` register under its prefix
+        let prefix: String = match scope_path(&node.receiver) {
+            Some(scope) => join_path(self.scope_prefix(), &scope),
+            None => self.scope_prefix().to_string(),
+        };
+
+        if node.method == "route" {
+            let args: Vec<&syn::Expr> = node.args.iter().collect();
+            let (path, route_expr): (Option<String>, Option<&syn::Expr>) = match args.as_slice() {
+                // App::new().route("/todos", web::get().to(handler))
+                [path, route_expr] => (lit_str(path), Some(*route_expr)),
+                // web::resource("/todos").route(web::get().to(handler))
+                [route_expr] => (resource_path(&node.receiver), Some(*route_expr)),
+                _ => (None, None),
+            };
+
+            if let (Some(path), Some(route_expr)) = (path, route_expr) {
+                if let Some(method) = route_method(route_expr) {
+                    self.routes.push(FoundRoute {
+                        method,
+                        path: join_path(&prefix, &path),
+                        handler: route_handler(route_expr),
+                        annotated: false,
+                    });
+                }
+            }
+        }
+
+        if node.method == "service" {
+            if let Some(syn::Expr::Path(handler)) = node.args.first() {
+                self.services.push((last_segment(&handler.path), prefix.clone()));
+            }
+        }
+
+        // The calls of the receiver find their scope themselves, the arguments are inside it
+        self.visit_expr(&node.receiver);
+        self.scopes.push(prefix);
+        for arg in &node.args {
+            self.visit_expr(arg);
+        }
+        self.scopes.pop();
+    }
+}
+
+fn lit_str(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(lit), .. }) => Some(lit.value()),
+        _ => None,
+    }
+}
+
+fn is_ident(expr: &syn::Expr, ident: &str) -> bool {
+    matches!(expr, syn::Expr::Path(path) if path.path.is_ident(ident))
+}
+
+fn last_segment(path: &syn::Path) -> String {
+    path.segments
+        .last()
+        .map(|segment| segment.ident.to_string())
+        .unwrap_or_default()
+}
+
+// Walks a call chain like `web::get().guard(..).to(handler)` back to its first call
+fn chain_root(mut expr: &syn::Expr) -> &syn::Expr {
+    while let syn::Expr::MethodCall(call) = expr {
+        expr = &call.receiver;
+    }
+    expr
+}
+
+// `web::get()` is GET, `web::method(Method::PUT)` is PUT
+fn route_method(route_expr: &syn::Expr) -> Option<String> {
+    let syn::Expr::Call(call) = chain_root(route_expr) else {
+        return None;
+    };
+    let syn::Expr::Path(func) = &*call.func else {
+        return None;
+    };
+
+    let func_name: String = last_segment(&func.path);
+    if HTTP_METHODS.contains(&func_name.as_str()) {
+        return Some(func_name);
+    }
+    match (func_name.as_str(), call.args.first()) {
+        ("method", Some(syn::Expr::Path(method))) => Some(last_segment(&method.path).to_lowercase()),
+        _ => None,
+    }
+}
+
+// Name of the handler passed to `.to(handler)`
+fn route_handler(route_expr: &syn::Expr) -> Option<String> {
+    let mut expr: &syn::Expr = route_expr;
+    while let syn::Expr::MethodCall(call) = expr {
+        if call.method == "to" {
+            if let Some(syn::Expr::Path(handler)) = call.args.first() {
+                return Some(last_segment(&handler.path));
+            }
+        }
+        expr = &call.receiver;
+    }
+    None
+}
+
+// Path of the `web::resource("/todos")` a chain of `.route(..)` calls starts from
+fn resource_path(receiver: &syn::Expr) -> Option<String> {
+    chain_root_path(receiver, "resource")
+}
+
+// Prefix of the `web::scope("/api")` a chain of calls starts from
+fn scope_path(receiver: &syn::Expr) -> Option<String> {
+    chain_root_path(receiver, "scope")
+}
+
+fn chain_root_path(receiver: &syn::Expr, func_name: &str) -> Option<String> {
+    let syn::Expr::Call(call) = chain_root(receiver) else {
+        return None;
+    };
+    match &*call.func {
+        syn::Expr::Path(func) if last_segment(&func.path) == func_name => call.args.first().and_then(lit_str),
+        _ => None,
+    }
+}
+
+// `/api` and `/todos` are `/api/todos`
+fn join_path(prefix: &str, path: &str) -> String {
+    let prefix: &str = prefix.trim_end_matches('/');
+    let joined: String = match path {
+        "" => prefix.to_string(),
+        path if path.starts_with('/') => format!("{}{}", prefix, path),
+        path => format!("{}/{}", prefix, path),
+    };
+    if joined.is_empty() {
+        "/".to_string()
+    } else {
+        joined
+    }
+}
+
+// `T` of a `web::Json<T>` argument of the handler
+fn json_body_type(handler: &syn::ItemFn) -> Option<&syn::Type> {
+    handler.sig.inputs.iter().find_map(|input| match input {
+        syn::FnArg::Typed(pat_ty) => generic_argument(&pat_ty.ty, "Json"),
+        syn::FnArg::Receiver(_) => None,
+    })
+}
+
+// `T` of `Wrapper<T>` when the type is that wrapper
+fn generic_argument<'a>(ty: &'a syn::Type, wrapper: &str) -> Option<&'a syn::Type> {
+    let syn::Type::Path(type_path) = ty else {
+        return None;
+    };
+    let segment: &syn::PathSegment = type_path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+// An example JSON value of a Rust type, following the structs declared in the same file
+fn example_value(ty: &syn::Type, structs: &HashMap<String, &syn::ItemStruct>, depth: usize) -> Value {
+    let ty: &syn::Type = match ty {
+        syn::Type::Reference(reference) => &reference.elem,
+        ty => ty,
+    };
+    let syn::Type::Path(type_path) = ty else {
+        return Value::Null;
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return Value::Null;
+    };
+
+    match segment.ident.to_string().as_str() {
+        "String" | "str" | "Uuid" => json!("string"),
+        "bool" => json!(false),
+        "i8" | "i16" | "i32" | "i64" | "i128" | "isize" | "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => json!(0),
+        "f32" | "f64" => json!(0.0),
+        "Option" => Value::Null,
+        "Vec" => match generic_argument(ty, "Vec") {
+            Some(item) => json!([example_value(item, structs, depth + 1)]),
+            None => json!([]),
+        },
+        "HashMap" | "BTreeMap" | "Value" => json!({}),
+        name => match structs.get(name) {
+            Some(item) if depth < MAX_EXAMPLE_DEPTH => {
+                let fields: Map<String, Value> = item
+                    .fields
+                    .iter()
+                    .filter_map(|field| {
+                        field.ident.as_ref().map(|ident| {
+                            (ident.to_string(), example_value(&field.ty, structs, depth + 1))
+                        })
+                    })
+                    .collect();
+                Value::Object(fields)
+            }
+            _ => Value::Null,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVER_CODE: &str = r#"
+        use actix_web::{delete, get, web, App, HttpResponse, HttpServer, Responder};
+        use serde::{Deserialize, Serialize};
+
+        #[derive(Serialize, Deserialize)]
+        struct Todo {
+            id: u64,
+            title: String,
+            tags: Vec<String>,
+            due: Option<String>,
+        }
+
+        #[get("/todos")]
+        async fn get_todos() -> impl Responder {
+            HttpResponse::Ok().finish()
+        }
+
+        #[delete("/todos/{id}")]
+        async fn delete_todo(path: web::Path<u64>) -> impl Responder {
+            HttpResponse::Ok().finish()
+        }
+
+        async fn create_todo(todo: web::Json<Todo>) -> impl Responder {
+            HttpResponse::Ok().json(todo.into_inner())
+        }
+
+        async fn update_todo(path: web::Path<u64>, todo: web::Json<Todo>) -> impl Responder {
+            HttpResponse::Ok().finish()
+        }
+
+        #[actix_web::main]
+        async fn main() -> std::io::Result<()> {
+            HttpServer::new(|| {
+                App::new()
+                    .service(get_todos)
+                    .service(delete_todo)
+                    .route("/todos", web::post().to(create_todo))
+                    .service(web::resource("/todos/{id}").route(web::method(Method::PUT).to(update_todo)))
+            })
+            .bind(("127.0.0.1", 8080))?
+            .run()
+            .await
+        }
+    "#;
+
+    #[test]
+    fn tests_extract_actix_routes() {
+        let routes: Vec<RouteObject> = extract_actix_routes(SERVER_CODE).unwrap();
+        let summary: Vec<(String, String, String)> = routes
+            .iter()
+            .map(|route| (route.method.clone(), route.route.clone(), route.is_route_dynamic.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("get".to_string(), "/todos".to_string(), "false".to_string()),
+                ("delete".to_string(), "/todos/{id}".to_string(), "true".to_string()),
+                ("post".to_string(), "/todos".to_string(), "false".to_string()),
+                ("put".to_string(), "/todos/{id}".to_string(), "true".to_string()),
+            ]
+        );
+
+        let expected_body: Value = json!({ "id": 0, "title": "string", "tags": ["string"], "due": null });
+        assert_eq!(routes[0].request_body, Value::Null);
+        assert_eq!(routes[2].request_body, expected_body);
+        assert_eq!(routes[3].request_body, expected_body);
+    }
+
+    #[test]
+    fn tests_extract_scoped_routes() {
+        let code: &str = r#"
+            #[get("/health")]
+            async fn health() -> impl Responder { HttpResponse::Ok().finish() }
+
+            #[get("/todos")]
+            async fn list_todos() -> impl Responder { HttpResponse::Ok().finish() }
+
+            async fn create_todo(todo: web::Json<Todo>) -> impl Responder { HttpResponse::Ok().finish() }
+
+            async fn delete_todo(path: web::Path<u64>) -> impl Responder { HttpResponse::Ok().finish() }
+
+            #[actix_web::main]
+            async fn main() -> std::io::Result<()> {
+                HttpServer::new(|| {
+                    App::new()
+                        .service(health)
+                        .service(
+                            web::scope("/api")
+                                .service(list_todos)
+                                .route("/todos", web::post().to(create_todo))
+                                .service(
+                                    web::scope("/v1/")
+                                        .service(web::resource("/todos/{id}").route(web::delete().to(delete_todo)))
+                                )
+                        )
+                })
+                .bind(("127.0.0.1", 8080))?
+                .run()
+                .await
+            }
+        "#;
+        let routes: Vec<(String, String)> = extract_actix_routes(code)
+            .unwrap()
+            .into_iter()
+            .map(|route| (route.method, route.route))
+            .collect();
+        assert_eq!(
+            routes,
+            vec![
+                ("get".to_string(), "/health".to_string()),
+                ("get".to_string(), "/api/todos".to_string()),
+                ("post".to_string(), "/api/todos".to_string()),
+                ("delete".to_string(), "/api/v1/todos/{id}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn tests_extract_routes_from_other_frameworks() {
+        let warp_code: &str = r#"
+            #[tokio::main]
+            async fn main() {
+                let todos = warp::path("todos").and(warp::get()).map(|| "[]");
+                warp::serve(todos).run(([127, 0, 0, 1], 8080)).await;
+            }
+        "#;
+        assert!(extract_actix_routes(warp_code).unwrap().is_empty());
+        assert!(extract_actix_routes("this is not rust").is_err());
+    }
+
+    #[test]
+    fn tests_merge_responses() {
+        let mut routes: Vec<RouteObject> = extract_actix_routes(SERVER_CODE).unwrap();
+        let described: Vec<RouteObject> = vec![
+            RouteObject {
+                is_route_dynamic: "false".to_string(),
+                method: "GET".to_string(),
+                request_body: Value::Null,
+                response: json!([{ "id": 1, "title": "Buy milk" }]),
+                route: "/todos/".to_string(),
+            },
+            RouteObject {
+                is_route_dynamic: "true".to_string(),
+                method: "put".to_string(),
+                request_body: Value::Null,
+                response: json!({ "id": 1, "title": "Buy milk" }),
+                route: "/todos/:todo_id".to_string(),
+            },
+        ];
+
+        assert_eq!(merge_responses(&mut routes, &described), 2);
+        assert_eq!(routes[0].response, described[0].response);
+        assert_eq!(routes[1].response, Value::Null);
+        assert_eq!(routes[3].response, described[1].response);
+        // The parsed paths are kept
+        assert_eq!(routes[3].route, "/todos/{id}");
+    }
+
+    #[test]
+    fn tests_save_api_endpoints() {
+        let dir: PathBuf = std::env::temp_dir().join(format!("autumn_routes_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let code_filepath: String = dir.join("main.rs").to_string_lossy().into_owned();
+
+        let routes: Vec<RouteObject> = extract_actix_routes(SERVER_CODE).unwrap();
+        let path: PathBuf = save_api_endpoints(&code_filepath, &routes).unwrap();
+        assert_eq!(path, dir.join("api_endpoints.json"));
+
+        let saved: Vec<RouteObject> = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(saved, routes);
+    }
+}
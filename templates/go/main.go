@@ -0,0 +1,99 @@
+package main
+
+import (
+	"encoding/json"
+	"log"
+	"net/http"
+	"os"
+	"strconv"
+	"sync"
+)
+
+type Task struct {
+	ID        uint64 `json:"id"`
+	Name      string `json:"name"`
+	Completed bool   `json:"completed"`
+}
+
+type NewTask struct {
+	Name string `json:"name"`
+}
+
+type Store struct {
+	mu    sync.Mutex
+	tasks map[uint64]Task
+	next  uint64
+}
+
+func writeJSON(w http.ResponseWriter, status int, value any) {
+	w.Header().Set("Content-Type", "application/json")
+	w.Header().Set("Access-Control-Allow-Origin", "*")
+	w.WriteHeader(status)
+	json.NewEncoder(w).Encode(value)
+}
+
+func (s *Store) tasksHandler(w http.ResponseWriter, r *http.Request) {
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	switch r.Method {
+	case http.MethodGet:
+		tasks := make([]Task, 0, len(s.tasks))
+		for _, task := range s.tasks {
+			tasks = append(tasks, task)
+		}
+		writeJSON(w, http.StatusOK, tasks)
+	case http.MethodPost:
+		var body NewTask
+		if err := json.NewDecoder(r.Body).Decode(&body); err != nil {
+			writeJSON(w, http.StatusBadRequest, map[string]string{"error": err.Error()})
+			return
+		}
+		s.next++
+		task := Task{ID: s.next, Name: body.Name}
+		s.tasks[task.ID] = task
+		writeJSON(w, http.StatusOK, task)
+	default:
+		w.WriteHeader(http.StatusMethodNotAllowed)
+	}
+}
+
+func (s *Store) taskHandler(w http.ResponseWriter, r *http.Request) {
+	id, err := strconv.ParseUint(r.PathValue("id"), 10, 64)
+	if err != nil {
+		writeJSON(w, http.StatusBadRequest, map[string]string{"error": "invalid id"})
+		return
+	}
+
+	s.mu.Lock()
+	defer s.mu.Unlock()
+
+	task, ok := s.tasks[id]
+	if !ok {
+		writeJSON(w, http.StatusNotFound, map[string]string{"error": "task not found"})
+		return
+	}
+	switch r.Method {
+	case http.MethodGet:
+		writeJSON(w, http.StatusOK, task)
+	case http.MethodDelete:
+		delete(s.tasks, id)
+		writeJSON(w, http.StatusOK, task)
+	default:
+		w.WriteHeader(http.StatusMethodNotAllowed)
+	}
+}
+
+func main() {
+	port := os.Getenv("PORT")
+	if port == "" {
+		port = "8080"
+	}
+
+	store := &Store{tasks: map[uint64]Task{}}
+	mux := http.NewServeMux()
+	mux.HandleFunc("/tasks", store.tasksHandler)
+	mux.HandleFunc("/tasks/{id}", store.taskHandler)
+
+	log.Fatal(http.ListenAndServe("127.0.0.1:"+port, mux))
+}
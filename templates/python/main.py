@@ -0,0 +1,49 @@
+import os
+import threading
+
+from flask import Flask, jsonify, request
+from flask_cors import CORS
+
+app = Flask(__name__)
+CORS(app)
+
+tasks = {}
+tasks_lock = threading.Lock()
+
+
+@app.get("/tasks")
+def get_tasks():
+    with tasks_lock:
+        return jsonify(list(tasks.values()))
+
+
+@app.get("/tasks/<int:task_id>")
+def get_task(task_id):
+    with tasks_lock:
+        task = tasks.get(task_id)
+    if task is None:
+        return jsonify({"error": "task not found"}), 404
+    return jsonify(task)
+
+
+@app.post("/tasks")
+def create_task():
+    body = request.get_json(force=True)
+    with tasks_lock:
+        task_id = max(tasks.keys(), default=0) + 1
+        task = {"id": task_id, "name": body["name"], "completed": False}
+        tasks[task_id] = task
+    return jsonify(task)
+
+
+@app.delete("/tasks/<int:task_id>")
+def delete_task(task_id):
+    with tasks_lock:
+        task = tasks.pop(task_id, None)
+    if task is None:
+        return jsonify({"error": "task not found"}), 404
+    return jsonify(task)
+
+
+if __name__ == "__main__":
+    app.run(host="127.0.0.1", port=int(os.environ.get("PORT", "8080")))
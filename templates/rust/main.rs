@@ -0,0 +1,74 @@
+use actix_cors::Cors;
+use actix_web::{http::header, web, App, HttpResponse, HttpServer, Responder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Task {
+    id: u64,
+    name: String,
+    completed: bool,
+}
+
+#[derive(Deserialize)]
+struct NewTask {
+    name: String,
+}
+
+struct AppState {
+    tasks: Mutex<HashMap<u64, Task>>,
+}
+
+async fn get_tasks(data: web::Data<AppState>) -> impl Responder {
+    let tasks = data.tasks.lock().unwrap();
+    let tasks: Vec<Task> = tasks.values().cloned().collect();
+    HttpResponse::Ok().json(tasks)
+}
+
+async fn get_task(id: web::Path<u64>, data: web::Data<AppState>) -> impl Responder {
+    let tasks = data.tasks.lock().unwrap();
+    match tasks.get(&id.into_inner()) {
+        Some(task) => HttpResponse::Ok().json(task),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+async fn create_task(task: web::Json<NewTask>, data: web::Data<AppState>) -> impl Responder {
+    let mut tasks = data.tasks.lock().unwrap();
+    let id: u64 = tasks.keys().max().map(|id| id + 1).unwrap_or(1);
+    let task: Task = Task { id, name: task.into_inner().name, completed: false };
+    tasks.insert(id, task.clone());
+    HttpResponse::Ok().json(task)
+}
+
+async fn delete_task(id: web::Path<u64>, data: web::Data<AppState>) -> impl Responder {
+    let mut tasks = data.tasks.lock().unwrap();
+    match tasks.remove(&id.into_inner()) {
+        Some(task) => HttpResponse::Ok().json(task),
+        None => HttpResponse::NotFound().finish(),
+    }
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let port: u16 = env::var("PORT").ok().and_then(|port| port.parse().ok()).unwrap_or(8080);
+    let data = web::Data::new(AppState { tasks: Mutex::new(HashMap::new()) });
+
+    HttpServer::new(move || {
+        App::new()
+            .wrap(
+                Cors::permissive()
+                    .allowed_header(header::CONTENT_TYPE),
+            )
+            .app_data(data.clone())
+            .route("/tasks", web::get().to(get_tasks))
+            .route("/tasks", web::post().to(create_task))
+            .route("/tasks/{id}", web::get().to(get_task))
+            .route("/tasks/{id}", web::delete().to(delete_task))
+    })
+    .bind(("127.0.0.1", port))?
+    .run()
+    .await
+}